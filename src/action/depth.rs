@@ -0,0 +1,35 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::err::ParseError;
+use crate::AppError;
+use std::str::FromStr;
+
+// The color depth of an output in bits per pixel.
+// The depth of the X screen itself is fixed when the server starts. What can
+// be changed per output is the amount of bits per color channel that is sent
+// over the link, so 24 means 8 bits per channel and 30 means 10.
+pub type Depth = u8;
+
+pub const DEPTHS: [Depth; 2] = [24, 30];
+
+// Bits per channel, as used by the "max bpc" output property
+pub fn bpc(depth: Depth) -> u8 {
+    depth / 3
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::depth_list(),
+        Some(depth_s) => {
+            let depth = Depth::from_str(&depth_s)
+                .ok()
+                .filter(|d| DEPTHS.contains(d))
+                .ok_or(ParseError::Depth(depth_s))?;
+
+            ParseResult::depth(output, depth)
+        }
+    })
+}
@@ -0,0 +1,204 @@
+// Actions for (un)docking laptops, which act on all outputs at once
+use super::plan::{self, Change};
+use super::{Action, ParseResult};
+use crate::backend::{DisplayBackend, OutputEntry};
+use crate::config::Config;
+use crate::AppError;
+use std::collections::VecDeque;
+
+// Connector names of built in panels: embedded DisplayPort, LVDS on older
+// laptops and MIPI DSI on some tablets. X drivers leave out the dash
+// sometimes (`eDP1`), and some use `LVDS` in different case.
+const INTERNAL_PREFIXES: [&str; 3] = ["edp", "lvds", "dsi"];
+
+pub fn is_internal(output_name: &str) -> bool {
+    let name = output_name.to_lowercase();
+    INTERNAL_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+pub fn internal(outputs: &[OutputEntry]) -> Option<&OutputEntry> {
+    outputs.iter().find(|o| o.connected && is_internal(&o.name))
+}
+
+// Connected outputs that are not built in, left to right as they are now.
+// Disabled ones go last.
+pub fn externals(outputs: &[OutputEntry]) -> Vec<&OutputEntry> {
+    let mut externals: Vec<&OutputEntry> = outputs
+        .iter()
+        .filter(|o| o.connected && !is_internal(&o.name))
+        .collect();
+
+    externals.sort_by_key(|o| o.geometry.as_ref().map_or(i32::MAX, |g| g.x));
+    externals
+}
+
+// Without an answer, shows what `changes` will do. Applying is the only
+// answer, `Back` is the other way out.
+fn confirm(
+    config: &Config,
+    mut args: VecDeque<String>,
+    changes: impl FnOnce() -> Result<Vec<Change>, AppError>,
+    action: ParseResult<Action>,
+) -> Result<ParseResult<Action>, AppError> {
+    match args.pop_front().as_deref() {
+        None => Ok(ParseResult::changes_list(&changes()?, config)),
+        Some(plan::APPLY) => Ok(action),
+        Some(_) => {
+            unreachable!("There should only be 'Apply' in previous menu")
+        }
+    }
+}
+
+pub fn parse_laptop_only(
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+    outputs: &[OutputEntry],
+    args: VecDeque<String>,
+) -> Result<ParseResult<Action>, AppError> {
+    let internal = internal(outputs).ok_or(AppError::NoInternal)?;
+
+    confirm(
+        config,
+        args,
+        || laptop_only_changes(backend, &internal.name),
+        ParseResult::laptop_only(internal.name.clone()),
+    )
+}
+
+// The primary external stays primary. Otherwise it is the leftmost one.
+pub fn parse_external_only(
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+    outputs: &[OutputEntry],
+    args: VecDeque<String>,
+) -> Result<ParseResult<Action>, AppError> {
+    let externals = externals(outputs);
+    let primary = externals
+        .iter()
+        .find(|o| o.primary)
+        .or(externals.first())
+        .ok_or(AppError::NoExternal)?;
+
+    confirm(
+        config,
+        args,
+        || external_only_changes(backend, &primary.name),
+        ParseResult::external_only(primary.name.clone()),
+    )
+}
+
+// Enables the internal panel first, so that something is always on
+fn laptop_only_changes(
+    backend: &mut Box<dyn DisplayBackend>,
+    internal: &str,
+) -> Result<Vec<Change>, AppError> {
+    let outputs = backend.get_outputs()?;
+
+    let enable = outputs
+        .iter()
+        .filter(|o| o.name == internal && !o.enabled)
+        .map(|o| Change::Enable(o.name.clone()));
+    let disable = outputs
+        .iter()
+        .filter(|o| o.enabled && o.name != internal)
+        .map(|o| Change::Disable(o.name.clone()));
+    let primary = plan::primary(backend, &outputs, internal);
+
+    Ok(enable.chain(disable).chain(primary).collect())
+}
+
+pub fn laptop_only(
+    backend: &mut Box<dyn DisplayBackend>,
+    internal: &str,
+) -> Result<(), AppError> {
+    let changes = laptop_only_changes(backend, internal)?;
+    plan::apply(backend, &changes)
+}
+
+// Turns on and lines up the externals, left to right, before turning off
+// the internal panel, so that something is always on. Backends that can,
+// do all of that in one go.
+fn external_only_changes(
+    backend: &mut Box<dyn DisplayBackend>,
+    primary: &str,
+) -> Result<Vec<Change>, AppError> {
+    let outputs = backend.get_outputs()?;
+    let externals = externals(&outputs);
+    if externals.is_empty() {
+        return Err(AppError::NoExternal);
+    }
+
+    let enable = externals
+        .iter()
+        .filter(|o| !o.enabled)
+        .map(|o| Change::Enable(o.name.clone()));
+    let line_up = externals
+        .windows(2)
+        .map(|p| Change::RightOf(p[1].name.clone(), p[0].name.clone()));
+    let disable = outputs
+        .iter()
+        .filter(|o| o.enabled && is_internal(&o.name))
+        .map(|o| Change::Disable(o.name.clone()));
+    let primary = plan::primary(backend, &outputs, primary);
+
+    Ok(enable
+        .chain(line_up)
+        .chain(disable)
+        .chain(primary)
+        .collect())
+}
+
+pub fn external_only(
+    backend: &mut Box<dyn DisplayBackend>,
+    primary: &str,
+) -> Result<(), AppError> {
+    let changes = external_only_changes(backend, primary)?;
+    plan::apply(backend, &changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(name: &str, connected: bool) -> OutputEntry {
+        OutputEntry {
+            connected,
+            enabled: connected,
+            ..OutputEntry::test(name, None)
+        }
+    }
+
+    #[test]
+    fn internal_connector_names() {
+        for name in ["eDP-1", "eDP1", "eDP-1-1", "LVDS-1", "LVDS1", "lvds-1"] {
+            assert!(is_internal(name), "{name}");
+        }
+        assert!(is_internal("DSI-1"));
+    }
+
+    #[test]
+    fn external_connector_names() {
+        let names = [
+            "DP-1",
+            "DP1",
+            "DP-1-1",
+            "HDMI-A-1",
+            "HDMI-1",
+            "VGA-1",
+            "DVI-D-1",
+            "HEADLESS-1",
+        ];
+        for name in names {
+            assert!(!is_internal(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn internal_must_be_connected() {
+        let outputs = [output("eDP-1", false), output("HDMI-1", true)];
+        assert!(internal(&outputs).is_none());
+
+        let outputs = [output("HDMI-1", true), output("eDP-1", true)];
+        assert_eq!(internal(&outputs).map(|o| o.name.as_str()), Some("eDP-1"));
+    }
+}
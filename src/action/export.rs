@@ -0,0 +1,93 @@
+// Shows the current layout as a shell command, for dotfiles and scripts,
+// and puts it on the clipboard. The EDID of an output is exported the same
+// way, for debugging monitors.
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::DisplayBackend;
+use crate::AppError;
+use itertools::Itertools;
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
+
+// `wl-copy` on wayland, `xclip` otherwise. Both fork a child that keeps
+// serving the clipboard and exit once it is set, so waiting here only waits
+// for the copy itself. Their output is closed so that the forked child does
+// not keep rofi waiting on it.
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut cmd = if env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-copy")
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard"]);
+        cmd
+    };
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Unwrap: stdin was piped above. Dropping it closes it.
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+
+    if !child.wait()?.success() {
+        let program = cmd.get_program().to_string_lossy().to_string();
+        return Err(io::Error::other(format!("{program} failed")));
+    }
+    Ok(())
+}
+
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+) -> Result<ParseResult<Action>, AppError> {
+    let command = backend.layout_command()?;
+    let copied = copy_to_clipboard(&command);
+
+    Ok(ParseResult::export_list(&command, copied))
+}
+
+// Writes the raw EDID to a file, which tools like `edid-decode` read, and
+// copies it as hex, the way `xrandr --props` lists it
+pub fn edid(
+    backend: &mut Box<dyn DisplayBackend>,
+    output: &str,
+) -> Result<(PathBuf, io::Result<()>), AppError> {
+    let edid = backend.get_edid(output)?;
+
+    // The runtime dir is only accessible to the user. Anyone can create
+    // files in the temporary dir, so there the file must not exist yet, or
+    // it could be a link to somewhere else.
+    let mut options = OpenOptions::new();
+    let path = match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => {
+            options.write(true).create(true).truncate(true);
+            PathBuf::from(dir).join(format!("rofi-randr-{output}.edid"))
+        }
+        None => {
+            options.write(true).create_new(true);
+            let name = format!("rofi-randr-{output}-{}.edid", process::id());
+            env::temp_dir().join(name)
+        }
+    };
+
+    let written = options
+        .open(&path)
+        .and_then(|mut file| file.write_all(&edid));
+    if let Err(source) = written {
+        return Err(AppError::EdidWrite { path, source });
+    }
+
+    let hex = edid
+        .chunks(16)
+        .map(|line| line.iter().map(|b| format!("{b:02x}")).join(""))
+        .join("\n");
+    Ok((path, copy_to_clipboard(&hex)))
+}
+
+// Nothing to choose, the export itself happens when the action is applied
+pub fn parse_edid(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    Ok(ParseResult::export_edid(ctx.output))
+}
@@ -0,0 +1,106 @@
+// How a mirrored output shows the output it mirrors when their aspect
+// ratios differ, e.g. a 16:10 laptop panel on a 16:9 projector
+use super::{Action, ParseCtx, ParseResult};
+use crate::action::mirror;
+use crate::action::resolution::Resolution;
+use crate::backend::DisplayBackend;
+use crate::err::ParseError;
+use crate::AppError;
+use core::fmt;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter)]
+pub enum FitMode {
+    // All of it, with black bars where the ratios differ
+    Fit,
+    // All of the output, cutting off the edges where the ratios differ
+    Fill,
+    // All of it on all of the output, distorted
+    Stretch,
+    // Shown at its own size from the top left, as without this
+    #[default]
+    Off,
+}
+
+impl FitMode {
+    pub fn explain(&self) -> &'static str {
+        match self {
+            FitMode::Fit => "keep everything, add bars",
+            FitMode::Fill => "fill the screen, crop edges",
+            FitMode::Stretch => "fill the screen, distort",
+            FitMode::Off => "no scaling",
+        }
+    }
+}
+
+impl fmt::Display for FitMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fit_s = match self {
+            FitMode::Fit => "Fit",
+            FitMode::Fill => "Fill",
+            FitMode::Stretch => "Stretch",
+            FitMode::Off => "Off",
+        };
+
+        write!(f, "{fit_s}")
+    }
+}
+
+impl FromStr for FitMode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Fit" => Ok(FitMode::Fit),
+            "Fill" => Ok(FitMode::Fill),
+            "Stretch" => Ok(FitMode::Stretch),
+            "Off" => Ok(FitMode::Off),
+            _ => Err(Self::Err::FitMode(s.to_string())),
+        }
+    }
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::projector_fit_list(),
+        Some(fit_s) => {
+            let fit = FitMode::from_str(&fit_s)?;
+            ParseResult::projector_fit(output, fit)
+        }
+    })
+}
+
+// Fits what `output` mirrors onto it. Turning it off also works on outputs
+// that no longer mirror anything, the source does not matter then.
+pub fn apply(
+    backend: &mut Box<dyn DisplayBackend>,
+    output: &str,
+    fit: &FitMode,
+) -> Result<(), AppError> {
+    let outputs = backend.get_outputs()?;
+    let entry = outputs
+        .iter()
+        .find(|o| o.name == output)
+        .ok_or(AppError::NoOuput(output.to_string()))?;
+
+    let source = mirror::mirrored_with(&outputs, entry)
+        .into_iter()
+        .find_map(|o| o.geometry.as_ref())
+        .map(|g| Resolution {
+            width: g.width,
+            height: g.height,
+        });
+    let source = match source {
+        Some(source) => source,
+        None if *fit == FitMode::Off => Resolution::default(),
+        None => return Err(AppError::NotMirrored(output.to_string())),
+    };
+
+    backend.set_projector_fit(output, &source, fit)?;
+    Ok(())
+}
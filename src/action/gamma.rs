@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::{batch, DisplayBackend, OutputEntry};
+use crate::err::ParseError;
+use crate::AppError;
+
+// Per channel gamma correction, as in `xrandr --gamma`. 1.0 is neutral,
+// lower values dim the channel, which warms up the screen for the night.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Gamma {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+}
+
+impl Gamma {
+    pub const fn new(red: f64, green: f64, blue: f64) -> Self {
+        Gamma { red, green, blue }
+    }
+}
+
+impl Default for Gamma {
+    fn default() -> Self {
+        Gamma::new(1.0, 1.0, 1.0)
+    }
+}
+
+// Typed in as `R:G:B`, like the comments in the gamma menu
+impl FromStr for Gamma {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let channel = |c: &str| {
+            c.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|c| c.is_finite() && *c > 0.0)
+        };
+
+        match s.split(':').map(channel).collect::<Vec<_>>()[..] {
+            [Some(red), Some(green), Some(blue)] => {
+                Ok(Gamma::new(red, green, blue))
+            }
+            _ => Err(ParseError::Gamma(s.to_string())),
+        }
+    }
+}
+
+// Listed first in both menus, to get back to neutral in one go
+pub const RESET: &str = "Reset";
+
+// Presets are chosen by name
+pub const GAMMA_PRESETS: [(&str, Gamma); 3] = [
+    ("Warm", Gamma::new(1.0, 0.9, 0.8)),
+    ("Warmer", Gamma::new(1.0, 0.8, 0.6)),
+    ("Night", Gamma::new(1.0, 0.7, 0.4)),
+];
+
+pub const BRIGHTNESS_STEPS: [u32; 7] = [100, 90, 80, 70, 60, 50, 40];
+
+// A preset by name, or typed in
+fn gamma_from_arg(gamma_s: &str) -> Result<Gamma, ParseError> {
+    Ok(match gamma_s {
+        RESET => Gamma::default(),
+        name => match GAMMA_PRESETS.iter().find(|(n, _)| *n == name) {
+            Some((_, g)) => *g,
+            None => Gamma::from_str(name)?,
+        },
+    })
+}
+
+// A percentage, as listed in the brightness menu
+fn brightness_from_arg(brightness_s: &str) -> Result<f64, ParseError> {
+    match brightness_s {
+        RESET => Ok(1.0),
+        percent_s => percent_s
+            .strip_suffix('%')
+            .and_then(|p| p.parse::<u32>().ok())
+            .filter(|p| (1..=100).contains(p))
+            .map(|p| f64::from(p) / 100.0)
+            .ok_or(ParseError::Brightness(brightness_s.to_string())),
+    }
+}
+
+pub fn parse_gamma(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::gamma_list(&output),
+        Some(gamma_s) => ParseResult::gamma(output, gamma_from_arg(&gamma_s)?),
+    })
+}
+
+pub fn parse_brightness(
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::brightness_list(),
+        Some(brightness_s) => {
+            ParseResult::brightness(output, brightness_from_arg(&brightness_s)?)
+        }
+    })
+}
+
+// The same menus as for a single output, but for all enabled ones
+pub fn parse_gamma_all(
+    outputs: &[OutputEntry],
+    mut args: VecDeque<String>,
+) -> Result<ParseResult<Action>, AppError> {
+    let output = super::main_output(outputs)?;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::gamma_list("All outputs"),
+        Some(gamma_s) => {
+            ParseResult::gamma_all(output, gamma_from_arg(&gamma_s)?)
+        }
+    })
+}
+
+pub fn parse_brightness_all(
+    outputs: &[OutputEntry],
+    mut args: VecDeque<String>,
+) -> Result<ParseResult<Action>, AppError> {
+    let output = super::main_output(outputs)?;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::brightness_list(),
+        Some(brightness_s) => ParseResult::brightness_all(
+            output,
+            brightness_from_arg(&brightness_s)?,
+        ),
+    })
+}
+
+// In one xrandr call on X, like `rotate::rotate_all`
+pub fn gamma_all(
+    backend: &mut Box<dyn DisplayBackend>,
+    gamma: &Gamma,
+) -> Result<(), AppError> {
+    let outputs = backend.get_outputs()?;
+
+    batch(backend, |backend| {
+        for o in outputs.iter().filter(|o| o.enabled) {
+            backend.set_gamma(&o.name, gamma)?;
+        }
+        Ok(())
+    })
+}
+
+pub fn brightness_all(
+    backend: &mut Box<dyn DisplayBackend>,
+    brightness: f64,
+) -> Result<(), AppError> {
+    let outputs = backend.get_outputs()?;
+
+    batch(backend, |backend| {
+        for o in outputs.iter().filter(|o| o.enabled) {
+            backend.set_brightness(&o.name, brightness)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_channels() {
+        let gamma = Gamma::from_str("1:0.9: 0.8").unwrap();
+        assert_eq!(gamma, Gamma::new(1.0, 0.9, 0.8));
+    }
+
+    #[test]
+    fn rejects_wrong_channel_counts() {
+        for s in ["", "1", "1:1", "1:1:", "1:1:1:1", "::"] {
+            assert!(Gamma::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn rejects_unusable_channels() {
+        for s in ["0:1:1", "1:-1:1", "1:1:inf", "1:NaN:1"] {
+            assert!(Gamma::from_str(s).is_err(), "{s}");
+        }
+    }
+}
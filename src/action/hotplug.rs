@@ -0,0 +1,79 @@
+// What `--watch` does when outputs are plugged in or out and no profile
+// matches the outputs that are connected then, see `HotplugPolicy`
+use super::layout::{self, Arrangement};
+use super::plan::{self, Change};
+use crate::backend::DisplayBackend;
+use crate::config::{Config, HotplugPolicy};
+use crate::AppError;
+
+// Puts each of the `added` outputs on, the way the `hotplug` policy says.
+// Locked outputs are left as they are.
+pub fn connected(
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+    added: &[String],
+) -> Result<(), AppError> {
+    for name in added.iter().filter(|name| !config.is_locked(name)) {
+        let outputs = backend.get_outputs()?;
+        let others: Vec<_> = outputs
+            .iter()
+            .filter(|o| o.enabled && &o.name != name)
+            .collect();
+
+        match config.hotplug {
+            HotplugPolicy::Extend => {
+                let rightmost = others.iter().max_by_key(|o| {
+                    o.geometry.as_ref().map(|g| g.x + g.width as i32)
+                });
+                let mut changes = vec![Change::Enable(name.clone())];
+                if let Some(rightmost) = rightmost {
+                    let other = rightmost.name.clone();
+                    changes.push(Change::RightOf(name.clone(), other));
+                }
+                plan::apply(backend, &changes)?;
+            }
+            // Without another output on, there is nothing to mirror
+            HotplugPolicy::Mirror if !others.is_empty() => {
+                // Mirroring can change the mode of the source as well
+                let source = super::main_output(&outputs)?;
+                match config.is_locked(&source) {
+                    true => backend.enable(name)?,
+                    false => super::mirror::apply(backend, &source, name)?,
+                }
+            }
+            HotplugPolicy::Mirror => backend.enable(name)?,
+            HotplugPolicy::None => (),
+        }
+    }
+
+    Ok(())
+}
+
+// Turns off the outputs that were unplugged while on, which X keeps on
+// otherwise. When extending, the rest then closes up the hole they left,
+// unless that would move a locked output.
+pub fn disconnected(
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+) -> Result<(), AppError> {
+    if config.hotplug == HotplugPolicy::None {
+        return Ok(());
+    }
+
+    let outputs = backend.get_outputs()?;
+    let changes: Vec<_> = outputs
+        .iter()
+        .filter(|o| o.enabled && !o.connected && !config.is_locked(&o.name))
+        .map(|o| Change::Disable(o.name.clone()))
+        .collect();
+    plan::apply(backend, &changes)?;
+
+    let locked_on = backend
+        .get_outputs()?
+        .iter()
+        .any(|o| o.enabled && config.is_locked(&o.name));
+    if config.hotplug == HotplugPolicy::Extend && !locked_on {
+        layout::apply(backend, &Arrangement::Row)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,229 @@
+// ICC profiles hold a lot more than X can apply. The only part of them that
+// ends up in the gamma ramps is the video card gamma table (`vcgt`), which
+// calibration tools like DisplayCAL write for exactly this purpose.
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::err::SetColorProfile;
+use crate::AppError;
+use std::path::PathBuf;
+use std::{env, fs};
+
+const HEADER_LEN: usize = 128;
+const TAG_LEN: usize = 12;
+const TAG_VCGT: &[u8; 4] = b"vcgt";
+const EXTENSIONS: [&str; 2] = ["icc", "icm"];
+
+#[derive(Debug, Clone)]
+pub enum Vcgt {
+    // A curve per channel, evenly spread from 0 to 1
+    Table([Vec<u16>; 3]),
+    // Gamma, minimum and maximum per channel
+    Formula([(f64, f64, f64); 3]),
+}
+
+impl Vcgt {
+    // The ramp value of `channel` at `x`, which is from 0 to 1
+    pub fn sample(&self, channel: usize, x: f64) -> u16 {
+        let value = match self {
+            Vcgt::Table(curves) => {
+                // Linear between the two nearest entries
+                let curve = &curves[channel];
+                let pos = x * (curve.len() - 1) as f64;
+                let (i, frac) = (pos.floor() as usize, pos.fract());
+                let next = curve.get(i + 1).unwrap_or(&curve[i]);
+                f64::from(curve[i]) * (1.0 - frac) + f64::from(*next) * frac
+            }
+            Vcgt::Formula(channels) => {
+                let (gamma, min, max) = channels[channel];
+                (min + (max - min) * x.powf(gamma)) * 65535.0
+            }
+        };
+
+        value.round().clamp(0.0, 65535.0) as u16
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+// s15Fixed16Number
+fn fixed_at(data: &[u8], at: usize) -> Option<f64> {
+    Some(f64::from(u32_at(data, at)? as i32) / 65536.0)
+}
+
+fn parse_vcgt(tag: &[u8]) -> Option<Vcgt> {
+    // Signature and reserved bytes, then whether it is a table or formula
+    match u32_at(tag, 8)? {
+        0 => {
+            let channels = u16_at(tag, 12)? as usize;
+            let count = u16_at(tag, 14)? as usize;
+            let entry_size = u16_at(tag, 16)? as usize;
+            if !matches!(channels, 1 | 3) || count < 2 {
+                return None;
+            }
+
+            let entry = |at: usize| match entry_size {
+                1 => Some(u16::from(*tag.get(at)?) * 257),
+                2 => u16_at(tag, at),
+                _ => None,
+            };
+            let curve = |c: usize| {
+                (0..count)
+                    .map(|i| entry(18 + (c * count + i) * entry_size))
+                    .collect::<Option<Vec<_>>>()
+            };
+
+            // A single curve is for all channels
+            let red = curve(0)?;
+            Some(Vcgt::Table(match channels {
+                1 => [red.clone(), red.clone(), red],
+                _ => [red, curve(1)?, curve(2)?],
+            }))
+        }
+        1 => {
+            let channel = |c: usize| {
+                let at = 12 + c * 12;
+                Some((
+                    fixed_at(tag, at)?,
+                    fixed_at(tag, at + 4)?,
+                    fixed_at(tag, at + 8)?,
+                ))
+            };
+            Some(Vcgt::Formula([channel(0)?, channel(1)?, channel(2)?]))
+        }
+        _ => None,
+    }
+}
+
+// The calibration curves of the profile at `path`
+pub fn load(path: &str) -> Result<Vcgt, SetColorProfile> {
+    let invalid = |reason: &str| {
+        SetColorProfile::Invalid(path.to_string(), reason.to_string())
+    };
+    let data = fs::read(path).map_err(|e| invalid(&e.to_string()))?;
+    find_vcgt(&data).map_err(invalid)
+}
+
+fn find_vcgt(data: &[u8]) -> Result<Vcgt, &'static str> {
+    // The count comes from the file, so never look past the data for it
+    let tag_count = u32_at(data, HEADER_LEN).ok_or("empty")? as usize;
+    let max_tags = data.len().saturating_sub(HEADER_LEN + 4) / TAG_LEN;
+    let tag = (0..tag_count.min(max_tags))
+        .map(|i| HEADER_LEN + 4 + i * TAG_LEN)
+        .find(|&at| data.get(at..at + 4) == Some(TAG_VCGT))
+        .ok_or("no calibration (vcgt) in profile")?;
+
+    let start = u32_at(data, tag + 4).unwrap_or_default() as usize;
+    let len = u32_at(data, tag + 8).unwrap_or_default() as usize;
+    start
+        .checked_add(len)
+        .and_then(|end| data.get(start..end))
+        .and_then(parse_vcgt)
+        .ok_or("unreadable calibration (vcgt)")
+}
+
+// `$XDG_DATA_HOME/icc`, where colord and DisplayCAL put profiles
+pub fn profile_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir).join("icc")),
+        None => {
+            let home = PathBuf::from(env::var_os("HOME")?);
+            Some(home.join(".local/share/icc"))
+        }
+    }
+}
+
+// The profiles in `profile_dir`, by file name
+pub fn profiles() -> Vec<PathBuf> {
+    let Some(entries) = profile_dir().and_then(|d| fs::read_dir(d).ok()) else {
+        return Vec::new();
+    };
+
+    let mut profiles: Vec<_> = entries
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| {
+            let extension = p.extension().and_then(|e| e.to_str());
+            extension.is_some_and(|e| {
+                EXTENSIONS.contains(&e.to_lowercase().as_str())
+            })
+        })
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::color_profile_list(&output),
+        Some(path) => ParseResult::color_profile(output, path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A profile with only a tag table, holding `tags` and claiming `count`
+    fn profile(count: u32, tags: &[(&[u8; 4], u32, u32)]) -> Vec<u8> {
+        let mut data = vec![0; HEADER_LEN];
+        data.extend(count.to_be_bytes());
+        for (sig, start, len) in tags {
+            data.extend(*sig);
+            data.extend(start.to_be_bytes());
+            data.extend(len.to_be_bytes());
+        }
+        data
+    }
+
+    fn formula_vcgt() -> Vec<u8> {
+        let mut tag = Vec::from(*TAG_VCGT);
+        tag.extend([0; 4]);
+        tag.extend(1u32.to_be_bytes());
+        for _ in 0..3 {
+            // Gamma 1, from 0 to 1
+            tag.extend([0x10000u32, 0, 0x10000].map(u32::to_be_bytes).concat());
+        }
+        tag
+    }
+
+    #[test]
+    fn finds_vcgt() {
+        let tag = formula_vcgt();
+        let start = (HEADER_LEN + 4 + 2 * TAG_LEN) as u32;
+        let mut data =
+            profile(2, &[(b"desc", 0, 0), (TAG_VCGT, start, tag.len() as u32)]);
+        data.extend(tag);
+
+        let vcgt = find_vcgt(&data).unwrap();
+        assert_eq!(vcgt.sample(0, 0.0), 0);
+        assert_eq!(vcgt.sample(1, 0.5), 32768);
+        assert_eq!(vcgt.sample(2, 1.0), 65535);
+    }
+
+    #[test]
+    fn tag_count_past_the_data() {
+        let data = profile(u32::MAX, &[(b"desc", 0, 0)]);
+        assert_eq!(
+            find_vcgt(&data).err(),
+            Some("no calibration (vcgt) in profile")
+        );
+    }
+
+    #[test]
+    fn tag_past_the_data() {
+        let data = profile(1, &[(TAG_VCGT, u32::MAX, u32::MAX)]);
+        assert_eq!(
+            find_vcgt(&data).err(),
+            Some("unreadable calibration (vcgt)")
+        );
+        assert_eq!(find_vcgt(&[]).err(), Some("empty"));
+    }
+}
@@ -0,0 +1,50 @@
+// Tells which connector is which monitor, by where each output is in the
+// layout. Nothing is drawn on the monitors themselves.
+use super::{Action, ParseResult};
+use crate::backend::{Geometry, OutputEntry};
+use crate::AppError;
+use itertools::Itertools;
+
+// Where in the layout `g` is: which third of the width and of the height
+// its center falls in, e.g. "top left". Outputs in a single row only get
+// the horizontal part.
+fn region(g: &Geometry, all: &[&Geometry]) -> String {
+    let left = all.iter().map(|g| g.x).min().unwrap_or(g.x);
+    let top = all.iter().map(|g| g.y).min().unwrap_or(g.y);
+    let right = all.iter().map(|g| g.x + g.width as i32).max();
+    let bottom = all.iter().map(|g| g.y + g.height as i32).max();
+    let (width, height) =
+        (right.unwrap_or(left) - left, bottom.unwrap_or(top) - top);
+
+    let (x, y) = g.center();
+    let third = |pos: i32, start: i32, len: i32| match len {
+        0 => 1,
+        _ => ((pos - start) * 3 / len).clamp(0, 2),
+    };
+
+    let vertical = ["top", "", "bottom"][third(y, top, height) as usize];
+    let horizontal = ["left", "", "right"][third(x, left, width) as usize];
+    match (vertical, horizontal) {
+        ("", "") => "center".to_string(),
+        ("", side) | (side, "") => side.to_string(),
+        (v, h) => format!("{v} {h}"),
+    }
+}
+
+pub fn parse(outputs: &[OutputEntry]) -> Result<ParseResult<Action>, AppError> {
+    let placed: Vec<_> = outputs
+        .iter()
+        .filter_map(|o| Some((&o.name, o.geometry.as_ref()?)))
+        .collect();
+    if placed.is_empty() {
+        return Err(AppError::NoEnabled);
+    }
+
+    let geometries: Vec<_> = placed.iter().map(|(_, g)| *g).collect();
+    let regions = placed
+        .iter()
+        .map(|(name, g)| format!("{name} = {}", region(g, &geometries)))
+        .join(", ");
+
+    Ok(ParseResult::identify_list(regions))
+}
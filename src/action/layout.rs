@@ -0,0 +1,213 @@
+use super::plan::{self, Change};
+use super::{Action, ParseResult};
+use crate::backend::{DisplayBackend, OutputEntry};
+use crate::config::Config;
+use crate::err::ParseError;
+use crate::AppError;
+use core::fmt;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+// Outputs per row in `Arrangement::Grid`
+const GRID_COLUMNS: usize = 2;
+
+// Ways to put all enabled outputs next to each other at once
+#[derive(Debug, Default, Clone, Copy, EnumIter)]
+pub enum Arrangement {
+    #[default]
+    Row,
+    Stack,
+    Grid,
+}
+
+impl Arrangement {
+    pub fn explain(&self) -> String {
+        match self {
+            Arrangement::Row => "left to right",
+            Arrangement::Stack => "top to bottom",
+            Arrangement::Grid => "two per row",
+        }
+        .to_string()
+    }
+
+    // The top left corners for outputs of the given (width, height), in
+    // the same order. Every column is as wide as its widest output and
+    // every row as high as its highest, so that nothing overlaps.
+    pub fn coordinates(&self, sizes: &[(u32, u32)]) -> Vec<(i32, i32)> {
+        let columns = match self {
+            Arrangement::Row => sizes.len().max(1),
+            Arrangement::Stack => 1,
+            Arrangement::Grid => GRID_COLUMNS,
+        };
+
+        let mut widths = vec![0; columns];
+        let mut heights = vec![0; sizes.len().div_ceil(columns)];
+        for (i, (w, h)) in sizes.iter().enumerate() {
+            widths[i % columns] = widths[i % columns].max(*w as i32);
+            heights[i / columns] = heights[i / columns].max(*h as i32);
+        }
+
+        // Where each column and row starts
+        let starts = |sizes: &[i32]| {
+            sizes
+                .iter()
+                .scan(0, |start, size| {
+                    let this = *start;
+                    *start += size;
+                    Some(this)
+                })
+                .collect::<Vec<_>>()
+        };
+        let (xs, ys) = (starts(&widths), starts(&heights));
+
+        (0..sizes.len())
+            .map(|i| (xs[i % columns], ys[i / columns]))
+            .collect()
+    }
+}
+
+impl fmt::Display for Arrangement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrangement_s = match self {
+            Arrangement::Row => "Row",
+            Arrangement::Stack => "Stack",
+            Arrangement::Grid => "Grid",
+        };
+
+        write!(f, "{arrangement_s}")
+    }
+}
+
+impl FromStr for Arrangement {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Row" => Ok(Arrangement::Row),
+            "Stack" => Ok(Arrangement::Stack),
+            "Grid" => Ok(Arrangement::Grid),
+            _ => Err(Self::Err::Arrangement(s.to_string())),
+        }
+    }
+}
+
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+    outputs: &[OutputEntry],
+    mut args: VecDeque<String>,
+) -> Result<ParseResult<Action>, AppError> {
+    let output = super::main_output(outputs)?;
+
+    let arrangement = match args.pop_front() {
+        None => return Ok(ParseResult::arrangement_list()),
+        Some(arrangement_s) => Arrangement::from_str(&arrangement_s)?,
+    };
+
+    // The answer to the confirmation, see `ParseResult::changes_list`
+    Ok(match args.pop_front().as_deref() {
+        None => {
+            let changes = changes(backend, &arrangement)?;
+            ParseResult::changes_list(&changes, config)
+        }
+        Some(plan::APPLY) => ParseResult::arrange(output, arrangement),
+        Some(_) => {
+            unreachable!("There should only be 'Apply' in previous menu")
+        }
+    })
+}
+
+// Keeps the order the outputs are in now: left to right for a row, and
+// top to bottom (then left to right) otherwise. Outputs that are in place
+// already are left alone.
+fn changes(
+    backend: &mut Box<dyn DisplayBackend>,
+    arrangement: &Arrangement,
+) -> Result<Vec<Change>, AppError> {
+    let outputs = backend.get_outputs()?;
+    let mut placed: Vec<_> = outputs
+        .iter()
+        .filter_map(|o| Some((&o.name, o.geometry.as_ref()?)))
+        .collect();
+    match arrangement {
+        Arrangement::Row => placed.sort_by_key(|(_, g)| (g.x, g.y)),
+        _ => placed.sort_by_key(|(_, g)| (g.y, g.x)),
+    }
+
+    let sizes: Vec<_> =
+        placed.iter().map(|(_, g)| (g.width, g.height)).collect();
+    let coordinates = arrangement.coordinates(&sizes);
+
+    Ok(placed
+        .iter()
+        .zip(coordinates)
+        .filter(|((_, g), (x, y))| (g.x, g.y) != (*x, *y))
+        .map(|((name, _), (x, y))| Change::Move(name.to_string(), x, y))
+        .collect())
+}
+
+pub fn apply(
+    backend: &mut Box<dyn DisplayBackend>,
+    arrangement: &Arrangement,
+) -> Result<(), AppError> {
+    let changes = changes(backend, arrangement)?;
+    plan::apply(backend, &changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    const FHD: (u32, u32) = (1920, 1080);
+    const QHD: (u32, u32) = (2560, 1440);
+    const PORTRAIT: (u32, u32) = (1080, 1920);
+
+    #[test]
+    fn row_puts_outputs_side_by_side() {
+        let coordinates = Arrangement::Row.coordinates(&[FHD, QHD, PORTRAIT]);
+        assert_eq!(coordinates, [(0, 0), (1920, 0), (4480, 0)]);
+    }
+
+    #[test]
+    fn stack_puts_outputs_on_top_of_each_other() {
+        let coordinates = Arrangement::Stack.coordinates(&[FHD, QHD, PORTRAIT]);
+        assert_eq!(coordinates, [(0, 0), (0, 1080), (0, 2520)]);
+    }
+
+    #[test]
+    fn grid_sizes_columns_and_rows_by_the_largest() {
+        let sizes = [FHD, PORTRAIT, QHD, FHD];
+        let coordinates = Arrangement::Grid.coordinates(&sizes);
+
+        // The first column is as wide as QHD, the first row as high as
+        // PORTRAIT
+        assert_eq!(coordinates, [(0, 0), (2560, 0), (0, 1920), (2560, 1920)]);
+    }
+
+    #[test]
+    fn grid_with_an_odd_count() {
+        let coordinates = Arrangement::Grid.coordinates(&[FHD, FHD, FHD]);
+        assert_eq!(coordinates, [(0, 0), (1920, 0), (0, 1080)]);
+    }
+
+    #[test]
+    fn nothing_to_arrange() {
+        for arrangement in Arrangement::iter() {
+            assert!(arrangement.coordinates(&[]).is_empty());
+            assert_eq!(arrangement.coordinates(&[QHD]), [(0, 0)]);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for arrangement in Arrangement::iter() {
+            // Menu rows are trimmed when rofi passes them back
+            let name = arrangement.to_string();
+            let parsed = Arrangement::from_str(name.trim());
+            assert_eq!(parsed.unwrap().to_string(), arrangement.to_string());
+        }
+        assert!(Arrangement::from_str("Pinwheel").is_err());
+    }
+}
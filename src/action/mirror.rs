@@ -0,0 +1,279 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::action::fit::FitMode;
+use crate::action::position::{Position, Relation};
+use crate::action::resolution::Resolution;
+use crate::backend::err::SetPosition;
+use crate::backend::Error as BackendError;
+use crate::backend::{batch, DisplayBackend, OutputEntry, ResolutionEntry};
+use crate::AppError;
+use std::collections::HashSet;
+
+// The largest resolution (by pixel count) that every one of the given
+// outputs supports, e.g. to mirror a laptop panel to a projector
+pub fn best_common_resolution(
+    per_output: &[Vec<ResolutionEntry>],
+) -> Option<Resolution> {
+    let (first, rest) = per_output.split_first()?;
+
+    let key = |r: &Resolution| (r.width, r.height);
+    let mut common: HashSet<(u32, u32)> =
+        first.iter().map(|e| key(&e.val)).collect();
+
+    for entries in rest {
+        let other: HashSet<_> = entries.iter().map(|e| key(&e.val)).collect();
+        common.retain(|r| other.contains(r));
+    }
+
+    // Resolutions order by pixel count, then width, so ties do not depend
+    // on the order of the set
+    common
+        .into_iter()
+        .map(|(width, height)| Resolution { width, height })
+        .max()
+}
+
+// The other enabled outputs at the same coordinates as `output`, i.e. the
+// ones it mirrors or is mirrored by
+pub fn mirrored_with<'a>(
+    outputs: &'a [OutputEntry],
+    output: &OutputEntry,
+) -> Vec<&'a OutputEntry> {
+    let Some(g) = &output.geometry else {
+        return Vec::new();
+    };
+
+    outputs
+        .iter()
+        .filter(|o| o.name != output.name)
+        .filter(|o| {
+            o.geometry
+                .as_ref()
+                .is_some_and(|other| (other.x, other.y) == (g.x, g.y))
+        })
+        .collect()
+}
+
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output,
+        mut args,
+        config,
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::mirror_target_list(backend, config, &output)?,
+        Some(target) => ParseResult::mirror(output, target),
+    })
+}
+
+// Mirrors `source` onto `target` at the best resolution both support.
+// The target is enabled first if needed, since projectors are usually
+// just plugged in when this is used.
+pub fn apply(
+    backend: &mut Box<dyn DisplayBackend>,
+    source: &str,
+    target: &str,
+) -> Result<(), AppError> {
+    let target_enabled = backend
+        .get_outputs()?
+        .iter()
+        .any(|o| o.name == target && o.enabled);
+
+    batch(backend, |backend| {
+        if !target_enabled {
+            backend.enable(target)?;
+        }
+        same_as(backend, target, source)
+    })
+}
+
+// The resolution an output runs at, or will once it is enabled
+fn running(entries: &[ResolutionEntry]) -> Option<Resolution> {
+    let find = |f: fn(&ResolutionEntry) -> bool| entries.iter().find(|e| f(e));
+    find(|e| e.current)
+        .or_else(|| find(|e| e.preferred))
+        .or(entries.first())
+        .map(|e| e.val.clone())
+}
+
+// Without a resolution in common, the output that runs the smaller one
+// shows the other one scaled down, as with projector fit. Returns which of
+// the two that is, and the resolution that it shows.
+fn scaled_down(
+    per_output: &[Vec<ResolutionEntry>; 2],
+) -> Option<(usize, Resolution)> {
+    let (a, b) = (running(&per_output[0])?, running(&per_output[1])?);
+    Some(match a < b {
+        true => (0, b),
+        false => (1, a),
+    })
+}
+
+// Puts `output` at the coordinates of `other`. That only covers the same
+// area when both run the same resolution, so when they do not, both are
+// set to the best one they have in common. Without one, the smaller one is
+// scaled to fit, where the backend can do that.
+pub fn same_as(
+    backend: &mut Box<dyn DisplayBackend>,
+    output: &str,
+    other: &str,
+) -> Result<(), AppError> {
+    let resolutions = [
+        backend.get_resolutions(output)?,
+        backend.get_resolutions(other)?,
+    ];
+    let no_common = || {
+        let err = SetPosition::NoCommonResolution(
+            output.to_string(),
+            other.to_string(),
+        );
+        BackendError::from(err)
+    };
+
+    match best_common_resolution(&resolutions) {
+        Some(res) => {
+            let current = |entries: &[ResolutionEntry]| {
+                entries.iter().find(|e| e.current).map(|e| e.val.clone())
+            };
+            if current(&resolutions[0]) != current(&resolutions[1]) {
+                backend.set_resolution(output, &res)?;
+                backend.set_resolution(other, &res)?;
+            }
+        }
+        None => {
+            let (i, source) =
+                scaled_down(&resolutions).ok_or_else(no_common)?;
+            let smaller = [output, other][i];
+            match backend.set_projector_fit(smaller, &source, &FitMode::Fit) {
+                Err(BackendError::Unsupported) => Err(no_common())?,
+                res => res?,
+            }
+        }
+    }
+
+    backend.set_position(
+        output,
+        &Position {
+            relation: Relation::SameAs,
+            output_s: other.to_string(),
+        },
+    )?;
+    Ok(())
+}
+
+// Moves `output` out of the group it mirrors, to the right of the widest
+// one in it. Their modes are left as mirroring chose them.
+pub fn unmirror(
+    backend: &mut Box<dyn DisplayBackend>,
+    output: &str,
+) -> Result<(), AppError> {
+    let outputs = backend.get_outputs()?;
+    let entry = outputs
+        .iter()
+        .find(|o| o.name == output)
+        .ok_or(AppError::NoOuput(output.to_string()))?;
+
+    let right =
+        |o: &&OutputEntry| o.geometry.as_ref().map(|g| g.x + g.width as i32);
+    let group = mirrored_with(&outputs, entry);
+    let Some(rightmost) = group.into_iter().max_by_key(right) else {
+        return Ok(());
+    };
+
+    backend.set_position(
+        output,
+        &Position {
+            relation: Relation::RightOf,
+            output_s: rightmost.name.clone(),
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(sizes: &[(u32, u32)]) -> Vec<ResolutionEntry> {
+        sizes
+            .iter()
+            .map(|&(width, height)| ResolutionEntry {
+                val: Resolution { width, height },
+                current: false,
+                preferred: false,
+                interlaced: false,
+            })
+            .collect()
+    }
+
+    fn best(per_output: &[&[(u32, u32)]]) -> Option<(u32, u32)> {
+        let per_output: Vec<_> =
+            per_output.iter().map(|sizes| entries(sizes)).collect();
+        best_common_resolution(&per_output).map(|r| (r.width, r.height))
+    }
+
+    const LAPTOP: &[(u32, u32)] = &[(1920, 1080), (1600, 900), (1280, 720)];
+    const PROJECTOR: &[(u32, u32)] = &[(1280, 720), (1024, 768), (800, 600)];
+
+    #[test]
+    fn largest_in_common() {
+        assert_eq!(best(&[LAPTOP, PROJECTOR]), Some((1280, 720)));
+        assert_eq!(best(&[PROJECTOR, LAPTOP]), Some((1280, 720)));
+    }
+
+    #[test]
+    fn common_to_every_output() {
+        let tv = &[(1920, 1080), (1024, 768)][..];
+        assert_eq!(best(&[LAPTOP, tv]), Some((1920, 1080)));
+        assert_eq!(best(&[LAPTOP, tv, PROJECTOR]), None);
+    }
+
+    #[test]
+    fn nothing_in_common() {
+        assert_eq!(best(&[LAPTOP, &[(1024, 768)]]), None);
+        assert_eq!(best(&[LAPTOP, &[]]), None);
+        assert_eq!(best(&[]), None);
+    }
+
+    fn current(mut entries: Vec<ResolutionEntry>) -> Vec<ResolutionEntry> {
+        entries[0].current = true;
+        entries
+    }
+
+    #[test]
+    fn smaller_output_shows_the_larger_scaled_down() {
+        let laptop = current(entries(LAPTOP));
+        let projector = current(entries(&[(1024, 768), (800, 600)]));
+        let source = Resolution {
+            width: 1920,
+            height: 1080,
+        };
+
+        let per_output = [laptop.clone(), projector.clone()];
+        assert_eq!(scaled_down(&per_output), Some((1, source.clone())));
+        let per_output = [projector, laptop];
+        assert_eq!(scaled_down(&per_output), Some((0, source)));
+    }
+
+    #[test]
+    fn disabled_outputs_scale_at_their_preferred_mode() {
+        let laptop = current(entries(LAPTOP));
+        let mut projector = entries(&[(1024, 768), (2048, 1536)]);
+        projector[1].preferred = true;
+
+        let source = Resolution {
+            width: 2048,
+            height: 1536,
+        };
+        assert_eq!(scaled_down(&[laptop, projector]), Some((0, source)));
+    }
+
+    #[test]
+    fn equal_pixel_counts_pick_the_widest() {
+        let sizes = &[(1600, 1200), (2400, 800)][..];
+        assert_eq!(best(&[sizes, sizes]), Some((2400, 800)));
+    }
+}
@@ -1,31 +1,159 @@
+pub mod depth;
+pub mod dock;
+pub mod export;
+pub mod fit;
+pub mod gamma;
+pub mod hotplug;
+pub mod icc;
+pub mod identify;
+pub mod layout;
+pub mod mirror;
+pub mod panning;
+pub mod plan;
 pub mod position;
+pub mod power;
+pub mod primary;
 pub mod rate;
+pub mod render_time;
 pub mod resolution;
 pub mod rotate;
+pub mod scale_filter;
+pub mod seat;
+pub mod shortcut;
+pub mod subpixel;
+pub mod swap;
+pub mod toggle;
+pub mod workspace;
 
 use crate::backend::DisplayBackend;
+use crate::backend::Error as BackendError;
 use crate::backend::OutputEntry;
+use crate::config::{Config, ConfirmDisable};
 use crate::rofi::List as RofiList;
 use std::collections::VecDeque;
 use std::fmt;
 
+// Top level entry that reads the outputs again
+pub const RESCAN: &str = "Rescan";
+// Top level entry that shows the layout as a command
+pub const EXPORT_LAYOUT: &str = "Export layout";
+// Top level entry that adds a headless output, see `main::step`
+pub const CREATE_OUTPUT: &str = "Create virtual output";
+// Top level entry that rotates every enabled output the same way
+pub const ROTATE_ALL: &str = "Rotate all";
+// Top level entries that set the brightness or gamma of every enabled output
+pub const DIM_ALL: &str = "Dim all";
+pub const NIGHT_MODE_ALL: &str = "Night mode all";
+// Top level entry that puts every enabled output in a row, stack or grid
+pub const ARRANGE: &str = "Arrange";
+// Top level entry that makes the previous primary output primary again
+pub const SWAP_PRIMARY: &str = "Swap primary";
+// Top level entry that tells where each output is in the layout
+pub const IDENTIFY: &str = "Identify";
+
+// The entries of the operation menu, as `Action::parse` takes them
+pub const OPERATION_NAMES: &[&str] = &[
+    "Enable",
+    "Disable",
+    "Toggle enabled",
+    "Power",
+    "Make primary",
+    "Reset",
+    "Change resolution",
+    "Rotate",
+    "Change rate",
+    "Limit rate",
+    "Position",
+    "Panning",
+    "Color depth",
+    "HDR",
+    "Allow tearing",
+    "Max render time",
+    "Scale filter",
+    "Subpixel order",
+    "Mirror to",
+    "Unmirror",
+    "Projector fit",
+    "Swap with",
+    "Assign workspace",
+    "Bind to seat",
+    "Gamma",
+    "Brightness",
+    "Color profile",
+    "Export EDID",
+];
+
+use crate::action::depth::Depth;
+use crate::action::fit::FitMode;
+use crate::action::gamma::Gamma;
+use crate::action::layout::Arrangement;
+use crate::action::panning::Panning;
 use crate::action::position::Position;
 use crate::action::position::Relation;
+use crate::action::power::PowerState;
 use crate::action::rate::parse as parse_rate;
+use crate::action::rate::Rate;
 use crate::action::resolution::Resolution;
-use crate::action::rotate::Rotation;
+use crate::action::rotate::Transform;
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
+use crate::backend::batch;
+use crate::backend::err::SetRate;
 use crate::err::AppError;
 use crate::err::ParseError;
+use crate::state::PendingRotation;
 
 #[derive(Debug)]
 pub enum Operation {
     Enable,
     Disable,
+    // Enable or disable, whichever the output is not at the moment
+    Toggle,
+    // DPMS, the output stays enabled
+    Power(PowerState),
     SetPrimary,
     ChangeRes(Resolution),
+    // Only reached by typing a mode, see `Resolution::parse`
+    ChangeMode(Resolution, Rate),
     Position(Position),
+    Panning(Panning),
     ChangeRate(f64),
-    Rotate(Rotation),
+    // The highest rate at the current resolution up to this one
+    LimitRate(Rate),
+    Rotate(Transform),
+    ColorDepth(Depth),
+    Hdr(bool),
+    AllowTearing(bool),
+    // In milliseconds, `None` is off
+    MaxRenderTime(Option<u32>),
+    ScaleFilter(Filter),
+    Subpixel(Subpixel),
+    MirrorTo(String),
+    // Only offered for outputs that share their coordinates with another
+    Unmirror,
+    // Also only offered for mirrored outputs
+    ProjectorFit(FitMode),
+    Swap(String),
+    Workspace(String),
+    // The name of the seat whose input devices go to the output
+    Seat(String),
+    Reset,
+    Gamma(Gamma),
+    Brightness(f64),
+    // The path of an ICC profile, of which the calibration curves are set
+    ColorProfile(String),
+    // Lists where it was written to, see `export::edid`
+    ExportEdid,
+    // Act on all outputs. The action's output is the one to make primary.
+    LaptopOnly,
+    ExternalOnly,
+    // Also acts on all enabled outputs. Reverting puts each back the way
+    // it was, when they were not all rotated the same before.
+    RotateAll(Transform),
+    RevertRotations(Vec<PendingRotation>),
+    Arrange(Arrangement),
+    BrightnessAll(f64),
+    GammaAll(Gamma),
 }
 
 #[derive(Debug)]
@@ -35,38 +163,211 @@ pub struct Action {
 }
 
 // To list the possible operations
+impl Operation {
+    // Whether applying this changes the configuration of the output,
+    // rather than only what is on it or which input goes to it
+    pub fn changes_output(&self) -> bool {
+        !matches!(
+            self,
+            Operation::Workspace(_)
+                | Operation::Seat(_)
+                | Operation::ExportEdid
+        )
+    }
+}
+
 impl fmt::Display for Operation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let op_s = match self {
             Operation::Enable => "Enable",
             Operation::Disable => "Disable",
+            Operation::Toggle => "Toggle enabled",
             Operation::SetPrimary => "Make primary",
             Operation::ChangeRes(_) => "Change resolution",
+            Operation::ChangeMode(..) => "Change mode",
             Operation::ChangeRate(..) => "Change rate",
+            Operation::LimitRate(..) => "Limit rate",
             Operation::Position(_) => "Position",
+            Operation::Panning(_) => "Panning",
             Operation::Rotate(_) => "Rotate",
+            Operation::ColorDepth(_) => "Color depth",
+            Operation::Hdr(_) => "HDR",
+            Operation::AllowTearing(_) => "Allow tearing",
+            Operation::MaxRenderTime(_) => "Max render time",
+            Operation::ScaleFilter(_) => "Scale filter",
+            Operation::Subpixel(_) => "Subpixel order",
+            Operation::Power(_) => "Power",
+            Operation::MirrorTo(_) => "Mirror to",
+            Operation::Unmirror => "Unmirror",
+            Operation::ProjectorFit(_) => "Projector fit",
+            Operation::Swap(_) => "Swap with",
+            Operation::Workspace(_) => "Assign workspace",
+            Operation::Seat(_) => "Bind to seat",
+            Operation::Reset => "Reset",
+            Operation::Gamma(_) => "Gamma",
+            Operation::ColorProfile(_) => "Color profile",
+            Operation::Brightness(_) => "Brightness",
+            Operation::ExportEdid => "Export EDID",
+            Operation::LaptopOnly => "Laptop only",
+            Operation::ExternalOnly => "External only",
+            Operation::RotateAll(_) => ROTATE_ALL,
+            Operation::RevertRotations(_) => "Revert rotations",
+            Operation::Arrange(_) => ARRANGE,
+            Operation::BrightnessAll(_) => DIM_ALL,
+            Operation::GammaAll(_) => NIGHT_MODE_ALL,
         };
-        write!(f, "{op_s} ")
+        write!(f, "{op_s}")
     }
 }
 
 // Apply the action: just constructs and calls a command
 impl Action {
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    pub fn op(&self) -> &Operation {
+        &self.op
+    }
+
+    // The outputs that applying this would change. Operations on all
+    // outputs count every output that is enabled or could be.
+    fn changed_outputs(
+        &self,
+        backend: &mut Box<dyn DisplayBackend>,
+    ) -> Result<Vec<String>, AppError> {
+        let output = self.output.clone();
+
+        Ok(match &self.op {
+            op if !op.changes_output() => Vec::new(),
+            Operation::Swap(other) | Operation::MirrorTo(other) => {
+                vec![output, other.clone()]
+            }
+            Operation::LaptopOnly
+            | Operation::ExternalOnly
+            | Operation::RotateAll(_)
+            | Operation::RevertRotations(_)
+            | Operation::Arrange(_)
+            | Operation::BrightnessAll(_)
+            | Operation::GammaAll(_) => backend
+                .get_outputs()?
+                .into_iter()
+                .filter(|o| o.enabled || o.connected)
+                .map(|o| o.name)
+                .collect(),
+            _ => vec![output],
+        })
+    }
+
+    // Returns what to show afterwards, for the operations that have a result
     pub fn apply(
         &self,
-        mut backend: Box<dyn DisplayBackend>,
-    ) -> Result<(), AppError> {
+        backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
+    ) -> Result<Option<RofiList>, AppError> {
+        // Checked here, so that shortcuts can not get around it either
+        if !config.locked.is_empty() {
+            let changed = self.changed_outputs(backend)?;
+            if let Some(o) = changed.into_iter().find(|o| config.is_locked(o)) {
+                return Err(AppError::Locked(o));
+            }
+        }
+
         let output = &self.output;
 
-        Ok(match &self.op {
-            Operation::Enable => backend.enable(output),
-            Operation::Disable => backend.disable(output),
-            Operation::SetPrimary => backend.set_primary(output),
-            Operation::ChangeRes(res) => backend.set_resolution(output, res),
-            Operation::ChangeRate(rate) => backend.set_rate(output, *rate),
-            Operation::Rotate(r) => backend.set_rotation(output, r),
-            Operation::Position(p) => backend.set_position(output, p),
-        }?)
+        match &self.op {
+            Operation::Enable => backend.enable(output)?,
+            Operation::Disable => backend.disable(output)?,
+            Operation::Toggle => {
+                let enabled = backend
+                    .get_outputs()?
+                    .iter()
+                    .any(|o| &o.name == output && o.enabled);
+                if enabled {
+                    backend.disable(output)?;
+                } else {
+                    backend.enable(output)?;
+                }
+            }
+            Operation::SetPrimary => backend.set_primary(output)?,
+            Operation::ChangeRes(res) => backend.set_resolution(output, res)?,
+            Operation::ChangeRate(rate) => backend.set_rate(output, *rate)?,
+            Operation::LimitRate(limit) => {
+                let rate = rate::limit(backend, output, *limit)?
+                    .ok_or(SetRate::NoRate(*limit))
+                    .map_err(BackendError::from)?;
+                backend.set_rate(output, rate)?;
+            }
+            Operation::ChangeMode(res, rate) => {
+                backend.set_resolution(output, res)?;
+                backend.set_rate(output, *rate)?;
+            }
+            Operation::Rotate(t) => backend.set_transform(output, t)?,
+            // Checked for a common resolution first, see `mirror::same_as`
+            Operation::Position(Position {
+                relation: Relation::SameAs,
+                output_s,
+            }) => batch(backend, |backend| {
+                mirror::same_as(backend, output, output_s)
+            })?,
+            Operation::Position(p) => backend.set_position(output, p)?,
+            Operation::Panning(p) => backend.set_panning(output, p)?,
+            Operation::MaxRenderTime(ms) => {
+                backend.set_max_render_time(output, *ms)?
+            }
+            Operation::ColorDepth(d) => backend.set_color_depth(output, *d)?,
+            Operation::Hdr(on) => backend.set_hdr(output, *on)?,
+            Operation::AllowTearing(a) => backend.set_tearing(output, *a)?,
+            Operation::ScaleFilter(f) => backend.set_scale_filter(output, f)?,
+            Operation::Subpixel(s) => backend.set_subpixel(output, s)?,
+            Operation::Power(p) => backend.set_power(output, p)?,
+            Operation::MirrorTo(target) => {
+                mirror::apply(backend, output, target)?;
+            }
+            Operation::Unmirror => mirror::unmirror(backend, output)?,
+            Operation::ProjectorFit(fit) => fit::apply(backend, output, fit)?,
+            Operation::Swap(other) => swap::apply(backend, output, other)?,
+            Operation::Workspace(ws) => backend.set_workspace(output, ws)?,
+            Operation::Seat(seat) => backend.set_seat(output, seat)?,
+            // Undo rotation, mode and scale changes. Stops at the first
+            // failure.
+            Operation::Reset => {
+                backend.set_transform(output, &Transform::default())?;
+                let res = Resolution::preferred(backend, output)?;
+                backend.set_preferred_mode(output, &res)?;
+                match backend.set_scale(output, 1.0) {
+                    Err(BackendError::Unsupported) => (),
+                    res => res?,
+                }
+            }
+            Operation::Gamma(g) => backend.set_gamma(output, g)?,
+            Operation::ColorProfile(path) => {
+                let vcgt = icc::load(path).map_err(BackendError::from)?;
+                backend.set_color_profile(output, &vcgt)?;
+            }
+            Operation::Brightness(b) => backend.set_brightness(output, *b)?,
+            Operation::ExportEdid => {
+                let (path, copied) = export::edid(backend, output)?;
+                let list = RofiList::edid_exported(output, &path, copied);
+                return Ok(Some(list));
+            }
+            Operation::LaptopOnly => dock::laptop_only(backend, output)?,
+            Operation::ExternalOnly => dock::external_only(backend, output)?,
+            Operation::RotateAll(r) => rotate::rotate_all(backend, r)?,
+            Operation::Arrange(a) => layout::apply(backend, a)?,
+            Operation::BrightnessAll(b) => gamma::brightness_all(backend, *b)?,
+            Operation::GammaAll(g) => gamma::gamma_all(backend, g)?,
+            Operation::RevertRotations(previous) => {
+                batch(backend, |backend| {
+                    for p in previous {
+                        backend.set_transform(&p.output, &p.previous)?;
+                    }
+                    Ok::<_, AppError>(())
+                })?;
+            }
+        };
+
+        Ok(None)
     }
 }
 
@@ -92,6 +393,62 @@ impl ParseResult<Action> {
         })
     }
 
+    fn color_profile(output: String, path: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::ColorProfile(path),
+        })
+    }
+
+    fn gamma(output: String, gamma: Gamma) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Gamma(gamma),
+        })
+    }
+
+    fn brightness(output: String, brightness: f64) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Brightness(brightness),
+        })
+    }
+
+    fn laptop_only(internal: String) -> Self {
+        Self::Done(Action {
+            output: internal,
+            op: Operation::LaptopOnly,
+        })
+    }
+
+    fn external_only(primary: String) -> Self {
+        Self::Done(Action {
+            output: primary,
+            op: Operation::ExternalOnly,
+        })
+    }
+
+    fn unmirror(output: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Unmirror,
+        })
+    }
+
+    fn projector_fit(output: String, fit: FitMode) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::ProjectorFit(fit),
+        })
+    }
+
+    fn reset(output: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Reset,
+        })
+    }
+
     fn disable(output: String) -> Self {
         Self::Done(Action {
             output,
@@ -99,6 +456,13 @@ impl ParseResult<Action> {
         })
     }
 
+    fn toggle(output: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Toggle,
+        })
+    }
+
     fn primary(output: String) -> Self {
         Self::Done(Action {
             output,
@@ -113,6 +477,13 @@ impl ParseResult<Action> {
         })
     }
 
+    fn mode(output: String, res: Resolution, rate: f64) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::ChangeMode(res, rate),
+        })
+    }
+
     // TODO: we currently need the cur_res because of the apply backend
     // that just calls xrandr. Ideally we just contact the xrandr backend
     fn rate(output: String, rate: f64) -> Self {
@@ -122,10 +493,146 @@ impl ParseResult<Action> {
         })
     }
 
-    fn rotate(output: String, r: Rotation) -> Self {
+    fn limit_rate(output: String, limit: Rate) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::LimitRate(limit),
+        })
+    }
+
+    fn rotate(output: String, t: Transform) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Rotate(t),
+        })
+    }
+
+    fn rotate_all(output: String, t: Transform) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::RotateAll(t),
+        })
+    }
+
+    fn arrange(output: String, arrangement: Arrangement) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Arrange(arrangement),
+        })
+    }
+
+    fn brightness_all(output: String, brightness: f64) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::BrightnessAll(brightness),
+        })
+    }
+
+    fn gamma_all(output: String, gamma: Gamma) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::GammaAll(gamma),
+        })
+    }
+
+    fn revert_rotations(
+        output: String,
+        previous: Vec<PendingRotation>,
+    ) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::RevertRotations(previous),
+        })
+    }
+
+    fn depth(output: String, depth: Depth) -> Self {
         Self::Done(Action {
             output,
-            op: Operation::Rotate(r),
+            op: Operation::ColorDepth(depth),
+        })
+    }
+
+    fn hdr(output: String, on: bool) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Hdr(on),
+        })
+    }
+
+    fn tearing(output: String, allow: bool) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::AllowTearing(allow),
+        })
+    }
+
+    fn scale_filter(output: String, filter: Filter) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::ScaleFilter(filter),
+        })
+    }
+
+    fn max_render_time(output: String, ms: Option<u32>) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::MaxRenderTime(ms),
+        })
+    }
+
+    fn panning(output: String, panning: Panning) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Panning(panning),
+        })
+    }
+
+    fn subpixel(output: String, subpixel: Subpixel) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Subpixel(subpixel),
+        })
+    }
+
+    fn power(output: String, power: PowerState) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Power(power),
+        })
+    }
+
+    fn mirror(output: String, target: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::MirrorTo(target),
+        })
+    }
+
+    fn swap(output: String, other: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Swap(other),
+        })
+    }
+
+    fn workspace(output: String, workspace: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Workspace(workspace),
+        })
+    }
+
+    fn seat(output: String, seat: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::Seat(seat),
+        })
+    }
+
+    fn export_edid(output: String) -> Self {
+        Self::Done(Action {
+            output,
+            op: Operation::ExportEdid,
         })
     }
 
@@ -142,46 +649,135 @@ impl ParseResult<Action> {
 
 // xrandr lets you disable your last display, leaving your system in a
 // hard to recover state. This function prompts you on whether you really
-// want to disable your last display.
-fn confirm_last_display_disable(
+// want to disable your last display, or any display with
+// `confirm_disable = "always"`. `action` is the disabling action, which is
+// returned once it is confirmed or does not need to be.
+fn confirm_disable(
     outputs: &[OutputEntry],
     mut ctx: ParseCtx,
+    action: fn(String) -> ParseResult<Action>,
 ) -> Result<ParseResult<Action>, AppError> {
     if let Some(confirmation) = ctx.args.pop_front() {
         return match confirmation.as_str() {
-            "Yes" => Ok(ParseResult::disable(ctx.output)),
+            "Yes" => Ok(action(ctx.output)),
             _ => unreachable!("There should only be 'Yes' in previous menu"),
         };
     }
 
-    // There are no other displays that are connected: prompt to confirm
-    if !outputs.iter().any(|o| o.name != ctx.output && o.enabled) {
-        return Ok(ParseResult::confirm_disable_list());
+    // Toggling a disabled output enables it, which needs no confirming
+    let enabled = outputs.iter().any(|o| o.name == ctx.output && o.enabled);
+    let last =
+        enabled && !outputs.iter().any(|o| o.name != ctx.output && o.enabled);
+    let confirm = match ctx.config.confirm_disable {
+        ConfirmDisable::Always => enabled,
+        ConfirmDisable::Last => last,
+        ConfirmDisable::Never => false,
+    };
+    if confirm {
+        return Ok(ParseResult::confirm_disable_list(last));
     }
 
     // Otherwise, immediately disable.
-    Ok(ParseResult::disable(ctx.output))
+    Ok(action(ctx.output))
+}
+
+// The output that actions on all outputs are applied to: the primary one,
+// or else the first enabled one
+fn main_output(outputs: &[OutputEntry]) -> Result<String, AppError> {
+    let output = outputs
+        .iter()
+        .filter(|o| o.enabled)
+        .max_by_key(|o| o.primary)
+        .ok_or(AppError::NoEnabled)?;
+
+    Ok(output.name.clone())
 }
 
 #[derive(Debug)]
-pub struct ParseCtx {
+pub struct ParseCtx<'a> {
     output: String,
     args: VecDeque<String>,
+    config: &'a Config,
 }
 
 impl Action {
+    // The configured operation to skip the operation menu with, when the
+    // only argument is an output that supports it
+    pub fn default_operation(
+        backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
+        args: &VecDeque<String>,
+    ) -> Result<Option<String>, AppError> {
+        let Some(op_s) = &config.default_operation else {
+            return Ok(None);
+        };
+        if args.len() != 1 {
+            return Ok(None);
+        }
+
+        let outputs = backend.get_outputs()?;
+        let Some(output) = outputs.iter().find(|o| o.name == args[0]) else {
+            // Not an output, but one of the entries above them
+            return Ok(None);
+        };
+
+        // Toggling is not in the menu, next to Enable or Disable it only
+        // repeats them. It applies to every output.
+        let supported = *op_s == Operation::Toggle.to_string()
+            || backend
+                .supported_operations(output)
+                .iter()
+                .any(|op| &op.to_string() == op_s);
+        Ok(supported.then(|| op_s.clone()))
+    }
+
     // Parse needed arguments for an action, and returns the
     // generated action If not all arguments are present yet,
     // a list of options for the next argument is returned instead
     pub fn parse(
         backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
         mut args: VecDeque<String>,
     ) -> Result<ParseResult<Self>, AppError> {
         let outputs = backend.get_outputs()?;
 
         // First argument should be the output
         let output = match args.pop_front() {
-            None => return ParseResult::output_list(backend),
+            None => return ParseResult::output_list(backend, config),
+            Some(entry) if entry == RESCAN => {
+                *backend = crate::backend::from_env(config)?;
+                return ParseResult::output_list(backend, config);
+            }
+            Some(entry) if entry == EXPORT_LAYOUT => {
+                return export::parse(backend)
+            }
+            Some(entry) if entry == IDENTIFY => {
+                return identify::parse(&outputs)
+            }
+            Some(entry) if entry == SWAP_PRIMARY => {
+                return primary::parse(&outputs)
+            }
+            // Entries above the outputs, that act on all of them at once
+            Some(entry) if entry == "Laptop only" => {
+                return dock::parse_laptop_only(backend, config, &outputs, args)
+            }
+            Some(entry) if entry == "External only" => {
+                return dock::parse_external_only(
+                    backend, config, &outputs, args,
+                )
+            }
+            Some(entry) if entry == ROTATE_ALL => {
+                return rotate::parse_all(backend, &outputs, args)
+            }
+            Some(entry) if entry == ARRANGE => {
+                return layout::parse(backend, config, &outputs, args)
+            }
+            Some(entry) if entry == DIM_ALL => {
+                return gamma::parse_brightness_all(&outputs, args)
+            }
+            Some(entry) if entry == NIGHT_MODE_ALL => {
+                return gamma::parse_gamma_all(&outputs, args)
+            }
             Some(name) => outputs
                 .iter()
                 .find(|o| o.name == name)
@@ -190,7 +786,9 @@ impl Action {
 
         // No arguments further args, list possible operations on the output
         let op_str = match args.pop_front() {
-            None => return Ok(ParseResult::operation_list(backend, output)),
+            None => {
+                return Ok(ParseResult::operation_list(backend, config, output))
+            }
             Some(op_s) => op_s,
         };
 
@@ -199,24 +797,150 @@ impl Action {
         let ctx = ParseCtx {
             output: output.name.clone(),
             args: args.clone(),
+            config,
         };
 
         let action_p: ParseResult<Self> = match op_str.as_str() {
             // Nullary actions, return the action
             "Enable" => ParseResult::enable(ctx.output),
-            "Disable" => confirm_last_display_disable(&outputs, ctx)?,
+            "Disable" => confirm_disable(&outputs, ctx, ParseResult::disable)?,
+            // Confirmed like disabling, when that is what it will do
+            "Toggle enabled" => {
+                confirm_disable(&outputs, ctx, ParseResult::toggle)?
+            }
             "Make primary" => ParseResult::primary(ctx.output),
+            "Reset" => ParseResult::reset(ctx.output),
+            "Unmirror" => ParseResult::unmirror(ctx.output),
+            "Projector fit" => fit::parse(ctx)?,
 
             // Unary/binary, parse further
             "Change resolution" => Resolution::parse(backend, ctx)?,
-            "Rotate" => Rotation::parse(ctx)?,
+            "Rotate" => Transform::parse(backend, ctx)?,
             "Change rate" => parse_rate(backend, ctx)?,
+            "Limit rate" => rate::parse_limit(backend, ctx)?,
             "Position" => Position::parse(backend, ctx)?,
+            "Panning" => panning::parse(ctx)?,
+            "Color depth" => depth::parse(ctx)?,
+            "HDR" => toggle::parse_hdr(ctx)?,
+            "Allow tearing" => toggle::parse_tearing(ctx)?,
+            "Max render time" => render_time::parse(ctx)?,
+            "Scale filter" => scale_filter::parse(ctx)?,
+            "Subpixel order" => subpixel::parse(ctx)?,
+            "Power" => power::parse(backend, ctx)?,
+            "Mirror to" => mirror::parse(backend, ctx)?,
+            "Swap with" => swap::parse(backend, ctx)?,
+            "Assign workspace" => workspace::parse(backend, ctx)?,
+            "Bind to seat" => seat::parse(backend, ctx)?,
+            "Gamma" => gamma::parse_gamma(ctx)?,
+            "Brightness" => gamma::parse_brightness(ctx)?,
+            "Color profile" => icc::parse(ctx)?,
+            "Export EDID" => export::parse_edid(ctx)?,
 
             // If not handled now, this is an invalid action
-            _ => return Err(ParseError::Operation(op_str))?
+            _ => return Err(ParseError::Operation(op_str))?,
         };
 
         Ok(action_p)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::Prompt;
+
+    // What choosing "Disable" on `name` leads to, without an answer yet
+    fn disable(
+        confirm: ConfirmDisable,
+        outputs: &[OutputEntry],
+        name: &str,
+    ) -> ParseResult<Action> {
+        let config = Config {
+            confirm_disable: confirm,
+            ..Default::default()
+        };
+        let ctx = ParseCtx {
+            output: name.to_string(),
+            args: VecDeque::new(),
+            config: &config,
+        };
+        confirm_disable(outputs, ctx, ParseResult::disable).unwrap()
+    }
+
+    fn prompt(res: &ParseResult<Action>) -> Option<Prompt> {
+        match res {
+            ParseResult::Next(list) => list.prompt,
+            ParseResult::Done(_) => None,
+        }
+    }
+
+    fn disables(res: &ParseResult<Action>) -> bool {
+        matches!(
+            res,
+            ParseResult::Done(Action {
+                op: Operation::Disable,
+                ..
+            })
+        )
+    }
+
+    fn two_on() -> [OutputEntry; 2] {
+        [
+            OutputEntry::test("eDP-1", None),
+            OutputEntry::test("HDMI-1", None),
+        ]
+    }
+
+    fn one_on() -> [OutputEntry; 2] {
+        let disabled = OutputEntry {
+            enabled: false,
+            ..OutputEntry::test("HDMI-1", None)
+        };
+        [OutputEntry::test("eDP-1", None), disabled]
+    }
+
+    #[test]
+    fn always_confirms_every_enabled_output() {
+        let res = disable(ConfirmDisable::Always, &two_on(), "HDMI-1");
+        assert_eq!(prompt(&res), Some(Prompt::ConfirmDisableOutput));
+
+        let res = disable(ConfirmDisable::Always, &one_on(), "eDP-1");
+        assert_eq!(prompt(&res), Some(Prompt::ConfirmDisable));
+    }
+
+    #[test]
+    fn last_only_confirms_the_last_output() {
+        let res = disable(ConfirmDisable::Last, &two_on(), "HDMI-1");
+        assert!(disables(&res));
+
+        let res = disable(ConfirmDisable::Last, &one_on(), "eDP-1");
+        assert_eq!(prompt(&res), Some(Prompt::ConfirmDisable));
+    }
+
+    #[test]
+    fn never_confirms() {
+        let res = disable(ConfirmDisable::Never, &two_on(), "HDMI-1");
+        assert!(disables(&res));
+
+        let res = disable(ConfirmDisable::Never, &one_on(), "eDP-1");
+        assert!(disables(&res));
+    }
+
+    #[test]
+    fn disabled_outputs_need_no_confirming() {
+        let res = disable(ConfirmDisable::Always, &one_on(), "HDMI-1");
+        assert!(disables(&res));
+    }
+
+    #[test]
+    fn the_answer_disables() {
+        let config = Config::default();
+        let ctx = ParseCtx {
+            output: "eDP-1".to_string(),
+            args: VecDeque::from(["Yes".to_string()]),
+            config: &config,
+        };
+        let res = confirm_disable(&one_on(), ctx, ParseResult::disable);
+        assert!(disables(&res.unwrap()));
+    }
+}
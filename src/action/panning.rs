@@ -0,0 +1,160 @@
+// Panning makes a small output a viewport into a larger area of the screen,
+// which scrolls along when the pointer reaches an edge of the output
+use super::{Action, ParseCtx, ParseResult};
+use crate::err::ParseError;
+use crate::AppError;
+use core::fmt;
+use std::str::FromStr;
+
+// The answer that turns panning off, same as an area of 0x0
+pub const OFF: &str = "Off";
+
+// The area to pan over, in screen coordinates, like xrandr's `WxH+X+Y`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Panning {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Panning {
+    pub fn is_off(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // The bottom right corner of the area, if it is within the coordinates
+    // that X can address
+    pub fn end(&self) -> Option<(i32, i32)> {
+        let end = |pos: u32, len: u32| {
+            pos.checked_add(len).and_then(|e| i32::try_from(e).ok())
+        };
+        Some((end(self.x, self.width)?, end(self.y, self.height)?))
+    }
+}
+
+impl fmt::Display for Panning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Panning {
+            width,
+            height,
+            x,
+            y,
+        } = self;
+        write!(f, "{width}x{height}+{x}+{y}")
+    }
+}
+
+// `WxH+X+Y`, or `WxH` for an area from the top left of the screen
+impl FromStr for Panning {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseError::Panning(s.to_string());
+        if s == OFF {
+            return Ok(Panning::default());
+        }
+
+        let (size, offset) = match s.split_once('+') {
+            Some((size, offset)) => (size, Some(offset)),
+            None => (s, None),
+        };
+        let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+        let (x, y) = match offset {
+            Some(offset) => offset.split_once('+').ok_or_else(invalid)?,
+            None => ("0", "0"),
+        };
+
+        // Only digits, so no signs or spaces
+        let num = |n: &str| match n.bytes().all(|b| b.is_ascii_digit()) {
+            true => n.parse::<u32>().map_err(|_| invalid()),
+            false => Err(invalid()),
+        };
+        let panning = Panning {
+            width: num(width)?,
+            height: num(height)?,
+            x: num(x)?,
+            y: num(y)?,
+        };
+        panning.end().ok_or_else(invalid)?;
+        Ok(panning)
+    }
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::panning_list(&output),
+        Some(panning_s) => {
+            let panning = Panning::from_str(panning_s.trim())?;
+            ParseResult::panning(output, panning)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pan(width: u32, height: u32, x: u32, y: u32) -> Panning {
+        Panning {
+            width,
+            height,
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn parses_size_and_offset() {
+        let parse = |s: &str| s.parse::<Panning>().ok();
+        assert_eq!(parse("3840x2160"), Some(pan(3840, 2160, 0, 0)));
+        assert_eq!(parse("3840x2160+1920+0"), Some(pan(3840, 2160, 1920, 0)));
+    }
+
+    #[test]
+    fn off_is_no_area() {
+        let off: Panning = OFF.parse().unwrap();
+        assert!(off.is_off());
+        assert!(pan(1920, 0, 0, 0).is_off());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let panning = pan(2560, 1440, 10, 20);
+        assert_eq!(panning.to_string().parse::<Panning>().ok(), Some(panning));
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for s in [
+            "",
+            "3840",
+            "3840x",
+            "x2160",
+            "3840x2160+10",
+            "3840x2160+10+",
+            "3840x2160+-10+0",
+            "3840x2160+10+0+0",
+            " 3840x2160",
+            "+3840x2160",
+            "3840*2160",
+        ] {
+            assert!(s.parse::<Panning>().is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_areas_beyond_x_coordinates() {
+        assert!("4294967295x1".parse::<Panning>().is_err());
+        assert!("2147483647x1+1+0".parse::<Panning>().is_err());
+        assert!("1x1+4294967295+0".parse::<Panning>().is_err());
+        assert_eq!(
+            "2147483646x1+1+0".parse::<Panning>().unwrap().end(),
+            Some((i32::MAX, 1))
+        );
+    }
+}
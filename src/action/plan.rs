@@ -0,0 +1,93 @@
+// Actions that change several outputs at once work out their changes up
+// front, so that those can be shown for confirmation before making them
+use super::position::{Position, Relation};
+use crate::backend::{batch, DisplayBackend, OutputEntry};
+use crate::AppError;
+use core::fmt;
+
+// The answer to the confirmation, see `ParseResult::changes_list`
+pub const APPLY: &str = "Apply";
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Enable(String),
+    Disable(String),
+    // The first output goes right of the second
+    RightOf(String, String),
+    // The top left corner of the output goes to (x, y)
+    Move(String, i32, i32),
+    Primary(String),
+}
+
+impl Change {
+    fn apply(
+        &self,
+        backend: &mut Box<dyn DisplayBackend>,
+    ) -> Result<(), AppError> {
+        match self {
+            Change::Enable(o) => backend.enable(o)?,
+            Change::Disable(o) => backend.disable(o)?,
+            Change::RightOf(o, other) => {
+                let pos = Position {
+                    relation: Relation::RightOf,
+                    output_s: other.clone(),
+                };
+                backend.set_position(o, &pos)?;
+            }
+            Change::Move(o, x, y) => backend.set_coordinates(o, *x, *y)?,
+            Change::Primary(o) => backend.set_primary(o)?,
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Enable(o) => write!(f, "Enable {o}"),
+            Change::Disable(o) => write!(f, "Disable {o}"),
+            Change::RightOf(o, other) => write!(f, "Put {o} right of {other}"),
+            Change::Move(o, x, y) => write!(f, "Move {o} to {x},{y}"),
+            Change::Primary(o) => write!(f, "Make {o} primary"),
+        }
+    }
+}
+
+impl Change {
+    // The same change, with each output named by `name`
+    pub fn renamed(&self, name: impl Fn(&str) -> String) -> Change {
+        match self {
+            Change::Enable(o) => Change::Enable(name(o)),
+            Change::Disable(o) => Change::Disable(name(o)),
+            Change::RightOf(o, other) => Change::RightOf(name(o), name(other)),
+            Change::Move(o, x, y) => Change::Move(name(o), *x, *y),
+            Change::Primary(o) => Change::Primary(name(o)),
+        }
+    }
+}
+
+// In the given order, in one go on backends that can
+pub fn apply(
+    backend: &mut Box<dyn DisplayBackend>,
+    changes: &[Change],
+) -> Result<(), AppError> {
+    batch(backend, |backend| {
+        for change in changes {
+            change.apply(backend)?;
+        }
+        Ok(())
+    })
+}
+
+// Making `output` primary, unless it already is or the backend has no
+// primary output
+pub fn primary(
+    backend: &mut Box<dyn DisplayBackend>,
+    outputs: &[OutputEntry],
+    output: &str,
+) -> Option<Change> {
+    let already = outputs.iter().any(|o| o.name == output && o.primary);
+    (backend.capabilities().primary && !already)
+        .then(|| Change::Primary(output.to_string()))
+}
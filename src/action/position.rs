@@ -1,5 +1,5 @@
 use crate::action::ParseCtx;
-use crate::backend::DisplayBackend;
+use crate::backend::{DisplayBackend, OutputEntry};
 use crate::err::{AppError, ParseError};
 use crate::Action;
 use crate::ParseResult;
@@ -13,16 +13,22 @@ pub enum Relation {
     RightOf,
     Above,
     Below,
+    // Overlaps the middle of the other output, for projectors and
+    // small monitors
+    CenteredOn,
 }
 
-impl From<&Relation> for xrandr::Relation {
-    fn from(relation: &Relation) -> Self {
-        match relation {
-            Relation::LeftOf => xrandr::Relation::LeftOf,
-            Relation::RightOf => xrandr::Relation::RightOf,
-            Relation::Above => xrandr::Relation::Above,
-            Relation::Below => xrandr::Relation::Below,
-            Relation::SameAs => xrandr::Relation::SameAs,
+impl Relation {
+    // X has no relation for `CenteredOn`, such outputs are placed by
+    // coordinates instead, see `backend::centered_on`
+    pub fn xrandr(&self) -> Option<xrandr::Relation> {
+        match self {
+            Relation::LeftOf => Some(xrandr::Relation::LeftOf),
+            Relation::RightOf => Some(xrandr::Relation::RightOf),
+            Relation::Above => Some(xrandr::Relation::Above),
+            Relation::Below => Some(xrandr::Relation::Below),
+            Relation::SameAs => Some(xrandr::Relation::SameAs),
+            Relation::CenteredOn => None,
         }
     }
 }
@@ -35,9 +41,10 @@ impl fmt::Display for Relation {
             Relation::Above => "Above",
             Relation::Below => "Below",
             Relation::SameAs => "Mirroring",
+            Relation::CenteredOn => "Centered on",
         };
 
-        write!(f, "{pos_s} ")
+        write!(f, "{pos_s}")
     }
 }
 
@@ -50,45 +57,31 @@ impl FromStr for Relation {
             "Above" => Ok(Relation::Above),
             "Below" => Ok(Relation::Below),
             "Mirroring" => Ok(Relation::SameAs),
+            "Centered on" => Ok(Relation::CenteredOn),
             _ => Err(Self::Err::Relation(s.to_string())),
         }
     }
 }
 
+// Both the relation and the output name can contain spaces, so they are
+// never joined into a single string. Each is chosen in its own menu and
+// stored as its own `ROFI_DATA` field.
 #[derive(Debug, Default)]
 pub struct Position {
     pub relation: Relation,
     pub output_s: String,
 }
 
-impl FromStr for Position {
-    type Err = ParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let data: Vec<&str> = s.split(' ').collect();
-        if data.len() != 2 {
-            return Err(Self::Err::Position(s.to_string()));
-        }
-
-        Ok(Position {
-            relation: Relation::from_str(data[0])?,
-            output_s: data[1].to_string(),
-        })
-    }
-}
-
-impl fmt::Display for Position {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}", self.relation, self.output_s)
-    }
-}
-
 impl Position {
     pub fn parse(
         backend: &mut Box<dyn DisplayBackend>,
         ctx: ParseCtx,
     ) -> Result<ParseResult<Action>, AppError> {
-        let ParseCtx { output, mut args } = ctx;
+        let ParseCtx {
+            output,
+            mut args,
+            config,
+        } = ctx;
 
         let relation = match args.pop_front() {
             None => return Ok(ParseResult::relation_list(backend)),
@@ -97,9 +90,148 @@ impl Position {
 
         Ok(match args.pop_front() {
             None => {
-                return ParseResult::relatives_list(backend, &output, &relation)
+                return ParseResult::relatives_list(
+                    backend, config, &output, &relation,
+                )
             }
             Some(o2) => ParseResult::position(output, relation, &o2),
         })
     }
 }
+
+// The outputs that `output` would partly cover after moving to `pos`.
+// Covering one completely is mirroring, which is not reported. Neither is
+// the output it is centered on, which it covers on purpose.
+pub fn overlapping(
+    outputs: &[OutputEntry],
+    output: &str,
+    pos: &Position,
+) -> Vec<String> {
+    let geometry_of = |name: &str| {
+        outputs
+            .iter()
+            .find(|o| o.name == name)
+            .and_then(|o| o.geometry.as_ref())
+    };
+
+    let (Some(current), Some(relative)) =
+        (geometry_of(output), geometry_of(&pos.output_s))
+    else {
+        return Vec::new();
+    };
+    let new = current.placed(&pos.relation, relative);
+
+    outputs
+        .iter()
+        .filter(|o| o.name != output)
+        .filter(|o| {
+            !matches!(pos.relation, Relation::CenteredOn)
+                || o.name != pos.output_s
+        })
+        .filter(|o| {
+            o.geometry.as_ref().is_some_and(|g| {
+                let mirrored = (g.x, g.y, g.width, g.height)
+                    == (new.x, new.y, new.width, new.height);
+                g.overlaps(&new) && !mirrored
+            })
+        })
+        .map(|o| o.name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::rotate::Rotation;
+    use crate::backend::Geometry;
+
+    fn output(name: &str, x: i32, width: u32, height: u32) -> OutputEntry {
+        let geometry = Geometry {
+            x,
+            y: 0,
+            width,
+            height,
+            rotation: Rotation::Normal,
+            scale: 1.0,
+        };
+        OutputEntry::test(name, Some(geometry))
+    }
+
+    fn pos(relation: Relation, output_s: &str) -> Position {
+        Position {
+            relation,
+            output_s: output_s.to_string(),
+        }
+    }
+
+    #[test]
+    fn beside_overlaps_nothing() {
+        let outputs = [
+            output("DP-1", 0, 1920, 1080),
+            output("HDMI-1", 1920, 1920, 1080),
+        ];
+        let pos = pos(Relation::LeftOf, "DP-1");
+        assert!(overlapping(&outputs, "HDMI-1", &pos).is_empty());
+    }
+
+    #[test]
+    fn mirroring_is_not_overlapping() {
+        let outputs = [
+            output("DP-1", 0, 1920, 1080),
+            output("HDMI-1", 1920, 1920, 1080),
+        ];
+        let pos = pos(Relation::SameAs, "DP-1");
+        assert!(overlapping(&outputs, "HDMI-1", &pos).is_empty());
+    }
+
+    #[test]
+    fn partly_covered_is_overlapping() {
+        let outputs = [
+            output("DP-1", 0, 1920, 1080),
+            output("DP-2", 1920, 1920, 1080),
+            output("HDMI-1", 3840, 2560, 1440),
+        ];
+        let pos = pos(Relation::SameAs, "DP-1");
+        assert_eq!(overlapping(&outputs, "HDMI-1", &pos), ["DP-1", "DP-2"]);
+    }
+
+    #[test]
+    fn centered_on_covers_its_relative_on_purpose() {
+        let outputs = [
+            output("DP-1", 0, 1920, 1080),
+            output("HDMI-1", 1920, 1280, 720),
+        ];
+        let pos = pos(Relation::CenteredOn, "DP-1");
+        assert!(overlapping(&outputs, "HDMI-1", &pos).is_empty());
+    }
+
+    #[test]
+    fn centered_on_reports_other_outputs() {
+        let outputs = [
+            output("DP-1", 0, 1280, 1080),
+            output("DP-2", 1280, 1280, 1080),
+            output("HDMI-1", 2560, 1920, 720),
+        ];
+        let pos = pos(Relation::CenteredOn, "DP-1");
+        assert_eq!(overlapping(&outputs, "HDMI-1", &pos), ["DP-2"]);
+    }
+
+    #[test]
+    fn relations_round_trip() {
+        let relations = [
+            Relation::LeftOf,
+            Relation::RightOf,
+            Relation::Above,
+            Relation::Below,
+            Relation::SameAs,
+            Relation::CenteredOn,
+        ];
+        for relation in relations {
+            let name = relation.to_string();
+            assert_eq!(name.trim(), name);
+            let parsed = Relation::from_str(&name).unwrap();
+            assert_eq!(parsed.to_string(), name);
+        }
+        assert!(Relation::from_str("Above ").is_err());
+    }
+}
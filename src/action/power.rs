@@ -0,0 +1,59 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::DisplayBackend;
+use crate::err::ParseError;
+use crate::AppError;
+use core::fmt;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+// DPMS power states. Unlike disabling, the output keeps its place in the
+// layout, and its mode.
+#[derive(Debug, Default, Clone, Copy, EnumIter)]
+pub enum PowerState {
+    #[default]
+    On,
+    Standby,
+    Off,
+}
+
+impl fmt::Display for PowerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let power_s = match self {
+            PowerState::On => "On",
+            PowerState::Standby => "Standby",
+            PowerState::Off => "Off",
+        };
+
+        write!(f, "{power_s}")
+    }
+}
+
+impl FromStr for PowerState {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "On" => Ok(PowerState::On),
+            "Standby" => Ok(PowerState::Standby),
+            "Off" => Ok(PowerState::Off),
+            _ => Err(Self::Err::Power(s.to_string())),
+        }
+    }
+}
+
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::power_list(backend.capabilities().power_all),
+        Some(power_s) => {
+            let power = PowerState::from_str(&power_s)?;
+            ParseResult::power(output, power)
+        }
+    })
+}
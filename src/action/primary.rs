@@ -0,0 +1,33 @@
+// Swapping primary goes back and forth between the two outputs that were
+// made primary last, e.g. a gaming monitor and a work monitor
+use super::{Action, ParseResult};
+use crate::backend::OutputEntry;
+use crate::state::State;
+use crate::AppError;
+
+// The output that swapping makes primary: the one that was primary before
+// the current one, or else the only other enabled output
+pub fn swap_target<'a>(
+    outputs: &'a [OutputEntry],
+    previous: Option<&str>,
+) -> Option<&'a OutputEntry> {
+    let others: Vec<_> =
+        outputs.iter().filter(|o| o.enabled && !o.primary).collect();
+
+    let only = match others.as_slice() {
+        [only] => Some(only),
+        _ => None,
+    };
+    previous
+        .and_then(|p| others.iter().find(|o| o.name == p))
+        .or(only)
+        .copied()
+}
+
+pub fn parse(outputs: &[OutputEntry]) -> Result<ParseResult<Action>, AppError> {
+    let previous = State::load().previous_primary;
+    let target =
+        swap_target(outputs, previous.as_deref()).ok_or(AppError::NoEnabled)?;
+
+    Ok(ParseResult::primary(target.name.clone()))
+}
@@ -5,23 +5,86 @@ use std::str::FromStr;
 
 pub type Rate = f64;
 
+// The entry of the rate limit menu that limits to the lowest rate
+pub const POWER_SAVER: &str = "Power saver (min rate)";
+
+// Strips the " Hz" that was printed in the menu (see: ListItem::rate),
+// typed in rates may come without it
+fn from_arg(rate_s: &str) -> Result<Rate, ParseError> {
+    let rate_stripped = rate_s.trim().trim_end_matches("Hz").trim_end();
+
+    f64::from_str(rate_stripped)
+        .ok()
+        .filter(|r| r.is_finite() && *r > 0.0)
+        .ok_or_else(|| ParseError::Rate(rate_s.to_string()))
+}
+
+// The lowest rate at the current resolution of the output
+pub fn min(
+    backend: &mut Box<dyn DisplayBackend>,
+    output: &str,
+) -> Result<Option<Rate>, AppError> {
+    let rates = backend.get_rates(output)?;
+    Ok(rates.into_iter().map(|r| r.val).min_by(f64::total_cmp))
+}
+
+// The highest rate at the current resolution that does not go over `limit`,
+// or the lowest one when they all do
+pub fn limit(
+    backend: &mut Box<dyn DisplayBackend>,
+    output: &str,
+    limit: Rate,
+) -> Result<Option<Rate>, AppError> {
+    let rates: Vec<_> = backend
+        .get_rates(output)?
+        .into_iter()
+        .map(|r| r.val)
+        .collect();
+
+    let below = rates.iter().copied().filter(|r| *r <= limit);
+    Ok(below
+        .max_by(f64::total_cmp)
+        .or_else(|| rates.into_iter().min_by(f64::total_cmp)))
+}
+
 pub fn parse(
     backend: &mut Box<dyn DisplayBackend>,
     ctx: ParseCtx,
 ) -> Result<ParseResult<Action>, AppError> {
-    let ParseCtx { output, mut args } = ctx;
+    let ParseCtx {
+        output,
+        mut args,
+        config,
+    } = ctx;
 
     let result = if let Some(rate_s) = args.pop_front() {
-        // Strip the " Hz" that was printed in the menu
-        // see: From<&RateEntry> for ListItem
-        let rate_stripped = &rate_s[..rate_s.len() - 3];
+        ParseResult::rate(output, from_arg(&rate_s)?)
+    } else {
+        ParseResult::rate_list(backend, &output, config)?
+    };
+
+    Ok(result)
+}
 
-        let rate = f64::from_str(rate_stripped)
-            .map_err(|_| ParseError::Rate(rate_s.to_string()))?;
+// Either the power saver entry, or a typed in rate to stay at or below
+pub fn parse_limit(
+    backend: &mut Box<dyn DisplayBackend>,
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output,
+        mut args,
+        config,
+    } = ctx;
 
-        ParseResult::rate(output, rate)
-    } else {
-        ParseResult::rate_list(backend, &output)?
+    let result = match args.pop_front() {
+        None => ParseResult::rate_limit_list(backend, &output, config)?,
+        Some(limit_s) if limit_s == POWER_SAVER => {
+            let rate = min(backend, &output)?
+                .ok_or_else(|| ParseError::Rate(limit_s.to_string()))?;
+            ParseResult::limit_rate(output, rate)
+        }
+        Some(limit_s) => ParseResult::limit_rate(output, from_arg(&limit_s)?),
     };
 
     Ok(result)
@@ -0,0 +1,42 @@
+// Sway can start rendering a frame later, closer to when it is shown. That
+// lowers latency, but frames that take longer than this are dropped.
+use super::{Action, ParseCtx, ParseResult};
+use crate::err::ParseError;
+use crate::AppError;
+
+// The answer that lets sway render as early as it can, which is the default
+pub const OFF: &str = "Off";
+
+// In milliseconds, to start from. Other values can be typed in.
+pub const SUGGESTED: [u32; 4] = [1, 2, 4, 8];
+
+// Strips the " ms" that is printed in the menu, typed in values may come
+// without it
+fn from_arg(time_s: &str) -> Result<Option<u32>, ParseError> {
+    if time_s == OFF {
+        return Ok(None);
+    }
+
+    time_s
+        .trim()
+        .trim_end_matches("ms")
+        .trim_end()
+        .parse::<u32>()
+        .ok()
+        .filter(|ms| *ms > 0)
+        .map(Some)
+        .ok_or_else(|| ParseError::MaxRenderTime(time_s.to_string()))
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::max_render_time_list(&output),
+        Some(time_s) => {
+            ParseResult::max_render_time(output, from_arg(&time_s)?)
+        }
+    })
+}
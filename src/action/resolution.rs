@@ -2,12 +2,14 @@ use crate::{
     backend::DisplayBackend,
     err::{AppError, ParseError},
 };
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::str::FromStr;
 
-use super::{Action, ParseCtx, ParseResult};
+use super::{rate::Rate, Action, ParseCtx, ParseResult};
 
 // Usually i want to pick resolutions and rates separately
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Resolution {
     pub width: u32,
     pub height: u32,
@@ -17,18 +19,12 @@ impl FromStr for Resolution {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let e = Self::Err::Resolution(s.to_string());
+        let e = || Self::Err::Resolution(s.to_string());
 
-        let data: Vec<&str> = s.split('x').collect();
-        if data.len() != 2 {
-            return Err(e);
-        }
-
-        let size_res: Result<Vec<u32>, _> =
-            data.iter().map(|s| s.parse::<u32>()).collect();
-
-        let size = size_res.map_err(|_| e)?;
-        let (width, height) = (size[0], size[1]);
+        // A second `x` ends up in the height, which then fails to parse
+        let (width_s, height_s) = s.split_once('x').ok_or_else(e)?;
+        let width = width_s.parse::<u32>().map_err(|_| e())?;
+        let height = height_s.parse::<u32>().map_err(|_| e())?;
 
         Ok(Resolution { width, height })
     }
@@ -43,19 +39,166 @@ impl From<&xrandr::Mode> for Resolution {
     }
 }
 
+// Aspect ratios as they are commonly named. Some resolutions are only
+// roughly one of these, e.g. 1366x768 is sold as 16:9 and 2560x1080 as 21:9.
+const NAMED_RATIOS: [(u32, u32); 8] = [
+    (16, 9),
+    (16, 10),
+    (4, 3),
+    (5, 4),
+    (3, 2),
+    (21, 9),
+    (32, 9),
+    (32, 10),
+];
+const RATIO_TOLERANCE: f64 = 0.03;
+
+// Ordered on pixel count first, then width and height
+impl Ord for Resolution {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.pixels()
+            .cmp(&other.pixels())
+            .then(self.width.cmp(&other.width))
+            .then(self.height.cmp(&other.height))
+    }
+}
+
+impl PartialOrd for Resolution {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Resolution {
+    pub fn pixels(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    // Like `16:9`, or the exact ratio if it is not close to a named one
+    pub fn aspect_ratio(&self) -> String {
+        if self.width == 0 || self.height == 0 {
+            return String::from("?");
+        }
+
+        let ratio = f64::from(self.width) / f64::from(self.height);
+        let named = NAMED_RATIOS.iter().find(|(w, h)| {
+            let named = f64::from(*w) / f64::from(*h);
+            (ratio - named).abs() / named < RATIO_TOLERANCE
+        });
+        if let Some((w, h)) = named {
+            return format!("{w}:{h}");
+        }
+
+        fn gcd(a: u32, b: u32) -> u32 {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+        let d = gcd(self.width, self.height);
+        format!("{}:{}", self.width / d, self.height / d)
+    }
+
+    // Not all backends know the preferred mode,
+    // so this falls back to the largest one in that case
+    pub fn preferred(
+        backend: &mut Box<dyn DisplayBackend>,
+        output_name: &str,
+    ) -> Result<Self, AppError> {
+        let resolutions = backend.get_resolutions(output_name)?;
+
+        resolutions
+            .iter()
+            .find(|r| r.preferred)
+            .or(resolutions.iter().max_by_key(|r| r.val.pixels()))
+            .map(|r| r.val.clone())
+            .ok_or_else(|| AppError::NoModes(format!("{output_name} has none")))
+    }
+
     pub fn parse(
         backend: &mut Box<dyn DisplayBackend>,
         ctx: ParseCtx,
     ) -> Result<ParseResult<Action>, AppError> {
-        let ParseCtx { output, mut args } = ctx;
+        let ParseCtx {
+            output,
+            mut args,
+            config,
+        } = ctx;
+
+        let Some(mode_s) = args.pop_front() else {
+            return ParseResult::resolution_list(backend, config, &output);
+        };
+
+        // Typed in values can include a rate, like `2560x1440@60`
+        let (res_s, rate_s) = match mode_s.split_once('@') {
+            Some((res_s, rate_s)) => (res_s.trim(), Some(rate_s.trim())),
+            None => (mode_s.as_str(), None),
+        };
+
+        let res = Resolution::from_str(res_s)?;
+        let available = backend
+            .get_resolutions(&output)?
+            .iter()
+            .any(|r| r.val == res);
+        if !available && !backend.accepts_any_mode(&output)? {
+            Err(ParseError::Unavailable(res_s.to_string(), output.clone()))?;
+        }
 
-        Ok(match args.pop_front() {
-            None => ParseResult::resolution_list(backend, &output)?,
-            Some(res_s) => {
-                let mode = Resolution::from_str(&res_s)?;
-                ParseResult::resolution(output, mode)
+        Ok(match rate_s {
+            None => ParseResult::resolution(output, res),
+            Some(rate_s) => {
+                let rate = Rate::from_str(rate_s)
+                    .map_err(|_| ParseError::Rate(rate_s.to_string()))?;
+                ParseResult::mode(output, res, rate)
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(width: u32, height: u32) -> String {
+        Resolution { width, height }.aspect_ratio()
+    }
+
+    #[test]
+    fn ultrawide_is_21_9() {
+        // Exactly 64:27
+        assert_eq!(ratio(2560, 1080), "21:9");
+        assert_eq!(ratio(3440, 1440), "21:9");
+    }
+
+    #[test]
+    fn rounds_to_named_ratios() {
+        assert_eq!(ratio(1920, 1080), "16:9");
+        assert_eq!(ratio(1366, 768), "16:9");
+        assert_eq!(ratio(1920, 1200), "16:10");
+        assert_eq!(ratio(1024, 768), "4:3");
+        assert_eq!(ratio(1280, 1024), "5:4");
+        assert_eq!(ratio(5120, 1440), "32:9");
+    }
+
+    #[test]
+    fn reduces_other_ratios() {
+        assert_eq!(ratio(1024, 1024), "1:1");
+        assert_eq!(ratio(1080, 1920), "9:16");
+        assert_eq!(ratio(0, 1080), "?");
+    }
+
+    #[test]
+    fn parses_resolutions() {
+        let res = Resolution::from_str("1920x1080").unwrap();
+        assert_eq!((res.width, res.height), (1920, 1080));
+    }
+
+    #[test]
+    fn rejects_partial_resolutions() {
+        for s in ["1920x", "x1080", "x", "1920", "", "1920x1080x60"] {
+            assert!(Resolution::from_str(s).is_err(), "{s}");
+        }
+        assert!(Resolution::from_str("-1920x1080").is_err());
+    }
+}
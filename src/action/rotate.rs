@@ -1,11 +1,23 @@
 use super::{Action, ParseCtx, ParseResult};
+use crate::backend::{batch, DisplayBackend, OutputEntry};
 use crate::err::ParseError;
+use crate::state::{PendingRotation, State};
 use crate::AppError;
 use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::str::FromStr;
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-#[derive(Debug, Default, EnumIter)]
+// The answer to the confirmation after rotating all outputs, when they
+// are not all rotated back the same way, see `List::confirm_rotation`
+pub const REVERT: &str = "Revert";
+
+#[derive(
+    Debug, Clone, PartialEq, Default, EnumIter, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
 pub enum Rotation {
     #[default]
     Normal,
@@ -25,6 +37,17 @@ impl From<&Rotation> for xrandr::Rotation {
     }
 }
 
+impl From<xrandr::Rotation> for Rotation {
+    fn from(r: xrandr::Rotation) -> Self {
+        match r {
+            xrandr::Rotation::Normal => Rotation::Normal,
+            xrandr::Rotation::Left => Rotation::Left,
+            xrandr::Rotation::Right => Rotation::Right,
+            xrandr::Rotation::Inverted => Rotation::Inverted,
+        }
+    }
+}
+
 impl fmt::Display for Rotation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let pos_s = match self {
@@ -34,11 +57,22 @@ impl fmt::Display for Rotation {
             Rotation::Inverted => "Inverted",
         };
 
-        write!(f, "{pos_s} ")
+        write!(f, "{pos_s}")
     }
 }
 
 impl Rotation {
+    // From a number of counterclockwise quarter turns, the way wayland
+    // transforms are numbered. Flipped transforms count as their rotation.
+    pub fn from_quarter_turns(turns: u32) -> Self {
+        match turns % 4 {
+            1 => Rotation::Left,
+            2 => Rotation::Inverted,
+            3 => Rotation::Right,
+            _ => Rotation::Normal,
+        }
+    }
+
     // Alternative phrasings for clarity
     pub fn explain(&self) -> String {
         match self {
@@ -48,20 +82,188 @@ impl Rotation {
             Rotation::Inverted => String::from("upside down"),
         }
     }
+}
+
+// A rotation, optionally mirrored (left to right) before rotating, like the
+// eight transforms of wayland. The X backends only do the unflipped ones,
+// see `Capabilities::flip`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub flipped: bool,
+}
+
+impl From<Rotation> for Transform {
+    fn from(rotation: Rotation) -> Self {
+        Transform {
+            rotation,
+            flipped: false,
+        }
+    }
+}
+
+impl Transform {
+    // The rotations, followed by their flipped versions if `flip`
+    pub fn all(flip: bool) -> Vec<Self> {
+        let flips: &[bool] = if flip { &[false, true] } else { &[false] };
+        flips
+            .iter()
+            .flat_map(|&flipped| {
+                Rotation::iter()
+                    .map(move |rotation| Transform { rotation, flipped })
+            })
+            .collect()
+    }
+
+    // Numbered like wayland transforms: quarter turns counterclockwise,
+    // plus 4 when flipped
+    pub fn from_wayland(transform: u32) -> Self {
+        Transform {
+            rotation: Rotation::from_quarter_turns(transform),
+            flipped: transform % 8 >= 4,
+        }
+    }
+
+    pub fn wayland(&self) -> u32 {
+        let turns = match self.rotation {
+            Rotation::Normal => 0,
+            Rotation::Left => 1,
+            Rotation::Inverted => 2,
+            Rotation::Right => 3,
+        };
+        if self.flipped {
+            turns + 4
+        } else {
+            turns
+        }
+    }
+
+    pub fn explain(&self) -> String {
+        match self.flipped {
+            false => self.rotation.explain(),
+            true => format!("{}, mirrored", self.rotation.explain()),
+        }
+    }
+
+    pub fn parse(
+        backend: &mut Box<dyn DisplayBackend>,
+        ctx: ParseCtx,
+    ) -> Result<ParseResult<Action>, AppError> {
+        let ParseCtx {
+            output, mut args, ..
+        } = ctx;
 
-    pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
-        let ParseCtx { output, mut args } = ctx;
+        let transform = match args.pop_front() {
+            None => {
+                let flip = backend.capabilities().flip;
+                return Ok(ParseResult::rotation_list(flip));
+            }
+            Some(rot_s) => Transform::from_str(&rot_s)?,
+        };
 
+        // The answer to the confirmation after rotating, which is
+        // the rotation to end up with, see `List::confirm_rotation`
         Ok(match args.pop_front() {
-            None => ParseResult::rotation_list(),
-            Some(rot_s) => {
-                let rotation = Rotation::from_str(&rot_s)?;
-                ParseResult::rotate(output, rotation)
+            None => ParseResult::rotate(output, transform),
+            Some(final_s) => {
+                ParseResult::rotate(output, Transform::from_str(&final_s)?)
             }
         })
     }
 }
 
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.flipped {
+            false => write!(f, "{}", self.rotation),
+            true => {
+                let rot_s = self.rotation.to_string().to_lowercase();
+                write!(f, "Flipped {rot_s}")
+            }
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(rot_s) = s.strip_prefix("Flipped ") else {
+            return Ok(Transform::from(Rotation::from_str(s)?));
+        };
+
+        Rotation::iter()
+            .find(|r| r.to_string().to_lowercase() == rot_s)
+            .map(|rotation| Transform {
+                rotation,
+                flipped: true,
+            })
+            .ok_or(ParseError::Rotation(s.to_string()))
+    }
+}
+
+pub fn parse_all(
+    backend: &mut Box<dyn DisplayBackend>,
+    outputs: &[OutputEntry],
+    mut args: VecDeque<String>,
+) -> Result<ParseResult<Action>, AppError> {
+    let output = super::main_output(outputs)?;
+
+    let transform = match args.pop_front() {
+        None => {
+            let flip = backend.capabilities().flip;
+            return Ok(ParseResult::rotation_list(flip));
+        }
+        Some(rot_s) => Transform::from_str(&rot_s)?,
+    };
+
+    // The answer to the confirmation, like in `Transform::parse`
+    Ok(match args.pop_front().as_deref() {
+        None => ParseResult::rotate_all(output, transform),
+        Some(REVERT) => {
+            let previous = State::load().pending_rotations;
+            ParseResult::revert_rotations(output, previous)
+        }
+        Some(final_s) => {
+            ParseResult::rotate_all(output, Transform::from_str(final_s)?)
+        }
+    })
+}
+
+// The current transform of every enabled output
+pub fn rotations(
+    backend: &mut Box<dyn DisplayBackend>,
+) -> Result<Vec<PendingRotation>, AppError> {
+    let outputs = backend.get_outputs()?;
+
+    outputs
+        .iter()
+        .filter(|o| o.enabled)
+        .map(|o| {
+            Ok(PendingRotation {
+                output: o.name.clone(),
+                previous: backend.get_transform(&o.name)?,
+                timer: 0,
+            })
+        })
+        .collect()
+}
+
+pub fn rotate_all(
+    backend: &mut Box<dyn DisplayBackend>,
+    transform: &Transform,
+) -> Result<(), AppError> {
+    let outputs = backend.get_outputs()?;
+
+    // In one xrandr call on X, so that they all turn at once
+    batch(backend, |backend| {
+        for o in outputs.iter().filter(|o| o.enabled) {
+            backend.set_transform(&o.name, transform)?;
+        }
+        Ok(())
+    })
+}
+
 impl FromStr for Rotation {
     type Err = ParseError;
 
@@ -75,3 +277,35 @@ impl FromStr for Rotation {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotations_round_trip() {
+        for rotation in Rotation::iter() {
+            let name = rotation.to_string();
+            assert_eq!(name.trim(), name);
+            assert_eq!(Rotation::from_str(&name).unwrap(), rotation);
+        }
+    }
+
+    #[test]
+    fn transforms_round_trip() {
+        for transform in Transform::all(true) {
+            let name = transform.to_string();
+            assert_eq!(name.trim(), name);
+            assert_eq!(Transform::from_str(&name).unwrap(), transform);
+        }
+        assert!(Transform::from_str("Flipped").is_err());
+        assert!(Transform::from_str("Normal ").is_err());
+    }
+
+    #[test]
+    fn wayland_transforms_round_trip() {
+        for transform in Transform::all(true) {
+            assert_eq!(Transform::from_wayland(transform.wayland()), transform);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::err::ParseError;
+use crate::AppError;
+use core::fmt;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+// How an output is upscaled when its scale is not 1
+#[derive(Debug, Default, Clone, Copy, EnumIter)]
+pub enum Filter {
+    #[default]
+    Linear,
+    Nearest, // Keeps pixel art and integer scaling sharp
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filter_s = match self {
+            Filter::Linear => "Linear",
+            Filter::Nearest => "Nearest",
+        };
+
+        write!(f, "{filter_s}")
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Linear" => Ok(Filter::Linear),
+            "Nearest" => Ok(Filter::Nearest),
+            _ => Err(Self::Err::ScaleFilter(s.to_string())),
+        }
+    }
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::scale_filter_list(),
+        Some(filter_s) => {
+            let filter = Filter::from_str(&filter_s)?;
+            ParseResult::scale_filter(output, filter)
+        }
+    })
+}
@@ -0,0 +1,18 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::DisplayBackend;
+use crate::AppError;
+
+// Unlike workspaces, seats are only picked from the list, not typed in
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::seat_list(backend, &output)?,
+        Some(seat) => ParseResult::seat(output, seat),
+    })
+}
@@ -0,0 +1,184 @@
+use super::{resolution::Resolution, Action, Operation};
+use crate::backend::DisplayBackend;
+use crate::err::AppError;
+use std::collections::VecDeque;
+use std::env;
+
+// Rofi reports custom keybindings (`-kb-custom-N`) as `ROFI_RETV` = 9 + N.
+// Shortcuts apply an operation to an output without going through the
+// (rest of the) submenus:
+// - kb-custom-1 (default Alt+1): switch to the preferred mode, on an output
+//   row or on any row in the menus of that output
+// - kb-custom-2 (default Alt+2): make the output primary, on a mode row
+// On other rows, kb-custom-2 does the same as selecting the row.
+// kb-custom-3 (default Alt+3) works on any row instead, see `apply_and_stay`
+// kb-custom-4 (default Alt+4) opens the operation menu of an output, also
+// when `default_operation` would skip it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    PreferredMode,
+    Primary,
+}
+
+// The menus whose rows are modes of the output
+const MODE_MENUS: [&str; 2] = ["Change resolution", "Change rate"];
+
+// How the script was called, as rofi reports it in `ROFI_RETV`
+#[derive(Debug, PartialEq, Eq)]
+enum Retv {
+    // When rofi starts, without input
+    Initial,
+    Selected,
+    // Typed in (with `allow_custom`), instead of a row selected
+    Typed,
+    Shortcut(Shortcut),
+    // When the chosen row completes an action, go back to the output list
+    // after applying it, instead of closing rofi
+    ApplyAndStay,
+    // Choosing an output this way does not skip to the default operation
+    ShowOperations,
+}
+
+impl Retv {
+    // Other keys, like deleting a row, are not bound to anything
+    fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0 => Some(Retv::Initial),
+            1 => Some(Retv::Selected),
+            2 => Some(Retv::Typed),
+            10 => Some(Retv::Shortcut(Shortcut::PreferredMode)),
+            11 => Some(Retv::Shortcut(Shortcut::Primary)),
+            12 => Some(Retv::ApplyAndStay),
+            13 => Some(Retv::ShowOperations),
+            _ => None,
+        }
+    }
+}
+
+fn retv() -> Option<Retv> {
+    Retv::from_code(env::var("ROFI_RETV").ok()?.parse().ok()?)
+}
+
+pub fn apply_and_stay() -> bool {
+    retv() == Some(Retv::ApplyAndStay)
+}
+
+pub fn show_operations() -> bool {
+    retv() == Some(Retv::ShowOperations)
+}
+
+pub fn typed() -> bool {
+    retv() == Some(Retv::Typed)
+}
+
+impl Shortcut {
+    pub fn from_env() -> Option<Self> {
+        match retv()? {
+            Retv::Shortcut(shortcut) => Some(shortcut),
+            _ => None,
+        }
+    }
+
+    // Whether the shortcut is bound on the row that `args` end with
+    pub fn applies_to(self, args: &VecDeque<String>) -> bool {
+        match self {
+            Shortcut::PreferredMode => !args.is_empty(),
+            Shortcut::Primary => {
+                args.len() == 3 && MODE_MENUS.contains(&args[1].as_str())
+            }
+        }
+    }
+}
+
+impl Action {
+    // Builds the action that a shortcut on the given output stands for
+    pub fn from_shortcut(
+        backend: &mut Box<dyn DisplayBackend>,
+        output_name: &str,
+        shortcut: Shortcut,
+    ) -> Result<Self, AppError> {
+        let output = backend
+            .get_outputs()?
+            .into_iter()
+            .find(|o| o.name == output_name)
+            .ok_or(AppError::NoOuput(output_name.to_string()))?;
+
+        let op = match shortcut {
+            // Enabling an output picks its preferred mode in every backend
+            Shortcut::PreferredMode if !output.enabled => Operation::Enable,
+            Shortcut::PreferredMode => Operation::ChangeRes(
+                Resolution::preferred(backend, &output.name)?,
+            ),
+            Shortcut::Primary => {
+                let supported = backend
+                    .supported_operations(&output)
+                    .iter()
+                    .any(|op| matches!(op, Operation::SetPrimary));
+
+                if !supported {
+                    Err(AppError::Unsupported(output.name.clone()))?;
+                }
+                Operation::SetPrimary
+            }
+        };
+
+        Ok(Action {
+            output: output.name,
+            op,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selecting_and_typing() {
+        assert_eq!(Retv::from_code(0), Some(Retv::Initial));
+        assert_eq!(Retv::from_code(1), Some(Retv::Selected));
+        assert_eq!(Retv::from_code(2), Some(Retv::Typed));
+    }
+
+    #[test]
+    fn custom_keys() {
+        let preferred = Retv::Shortcut(Shortcut::PreferredMode);
+        assert_eq!(Retv::from_code(10), Some(preferred));
+        let primary = Retv::Shortcut(Shortcut::Primary);
+        assert_eq!(Retv::from_code(11), Some(primary));
+        assert_eq!(Retv::from_code(12), Some(Retv::ApplyAndStay));
+        assert_eq!(Retv::from_code(13), Some(Retv::ShowOperations));
+    }
+
+    fn args(args: &[&str]) -> VecDeque<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn preferred_mode_on_any_row_of_an_output() {
+        let preferred = Shortcut::PreferredMode;
+        assert!(preferred.applies_to(&args(&["DP-1"])));
+        assert!(preferred.applies_to(&args(&["DP-1", "Rotate"])));
+        assert!(!preferred.applies_to(&args(&[])));
+    }
+
+    #[test]
+    fn primary_on_mode_rows() {
+        let primary = Shortcut::Primary;
+        let res = args(&["DP-1", "Change resolution", "1920x1080"]);
+        assert!(primary.applies_to(&res));
+        assert!(primary.applies_to(&args(&["DP-1", "Change rate", "60.00"])));
+
+        assert!(!primary.applies_to(&args(&["DP-1"])));
+        assert!(!primary.applies_to(&args(&["DP-1", "Change resolution"])));
+        assert!(!primary.applies_to(&args(&["DP-1", "Rotate", "Left"])));
+    }
+
+    #[test]
+    fn unbound_keys() {
+        // Deleting a row, and custom keys 5 to 19
+        for code in [3, 14, 28] {
+            assert_eq!(Retv::from_code(code), None);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::err::ParseError;
+use crate::AppError;
+use core::fmt;
+use std::str::FromStr;
+use strum_macros::EnumIter;
+
+// The order of the subpixels of the panel, for font antialiasing
+#[derive(Debug, Default, Clone, Copy, EnumIter)]
+pub enum Subpixel {
+    #[default]
+    Rgb,
+    Bgr,
+    Vrgb, // Vertical, e.g. on panels that are rotated
+    Vbgr,
+    None, // Grayscale antialiasing only
+}
+
+impl fmt::Display for Subpixel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subpixel_s = match self {
+            Subpixel::Rgb => "RGB",
+            Subpixel::Bgr => "BGR",
+            Subpixel::Vrgb => "VRGB",
+            Subpixel::Vbgr => "VBGR",
+            Subpixel::None => "None",
+        };
+
+        write!(f, "{subpixel_s}")
+    }
+}
+
+impl FromStr for Subpixel {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "RGB" => Ok(Subpixel::Rgb),
+            "BGR" => Ok(Subpixel::Bgr),
+            "VRGB" => Ok(Subpixel::Vrgb),
+            "VBGR" => Ok(Subpixel::Vbgr),
+            "None" => Ok(Subpixel::None),
+            _ => Err(Self::Err::Subpixel(s.to_string())),
+        }
+    }
+}
+
+pub fn parse(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::subpixel_list(),
+        Some(subpixel_s) => {
+            let subpixel = Subpixel::from_str(&subpixel_s)?;
+            ParseResult::subpixel(output, subpixel)
+        }
+    })
+}
@@ -0,0 +1,143 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::{batch, DisplayBackend, Geometry, OutputEntry};
+use crate::AppError;
+
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output,
+        mut args,
+        config,
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::swap_target_list(backend, config, &output)?,
+        Some(other) => ParseResult::swap(output, other),
+    })
+}
+
+// The origins that `a` and `b` move to when they trade places, in that
+// order. The pair keeps the span it covers: the one that moves into the
+// first slot starts where that slot started, the other one ends where the
+// second slot ended. That way outputs of different sizes do not end up
+// overlapping, and outputs in between stay where they are. Overlapping
+// (e.g. mirrored) outputs have nothing to swap.
+fn swapped_origins(
+    a: &Geometry,
+    b: &Geometry,
+) -> Option<((i32, i32), (i32, i32))> {
+    let right = |g: &Geometry| g.x + g.width as i32;
+    let bottom = |g: &Geometry| g.y + g.height as i32;
+
+    // Where `first` (left or above) and `second` go, in that order
+    let side_by_side = |first: &Geometry, second: &Geometry| {
+        let end = right(second) - first.width as i32;
+        ((end, second.y), (first.x, first.y))
+    };
+    let stacked = |first: &Geometry, second: &Geometry| {
+        let end = bottom(second) - first.height as i32;
+        ((second.x, end), (first.x, first.y))
+    };
+    let flip = |(a, b)| (b, a);
+
+    if right(a) <= b.x {
+        Some(side_by_side(a, b))
+    } else if right(b) <= a.x {
+        Some(flip(side_by_side(b, a)))
+    } else if bottom(a) <= b.y {
+        Some(stacked(a, b))
+    } else if bottom(b) <= a.y {
+        Some(flip(stacked(b, a)))
+    } else {
+        None
+    }
+}
+
+// Swaps the positions of two outputs, and which of them is primary
+pub fn apply(
+    backend: &mut Box<dyn DisplayBackend>,
+    a: &str,
+    b: &str,
+) -> Result<(), AppError> {
+    let outputs = backend.get_outputs()?;
+    let find = |name: &str| {
+        outputs
+            .iter()
+            .find(|o| o.name == name)
+            .ok_or(AppError::NoOuput(name.to_string()))
+    };
+    let (a_entry, b_entry) = (find(a)?, find(b)?);
+
+    let geometry = |o: &OutputEntry| {
+        o.geometry.clone().ok_or(AppError::Disabled(o.name.clone()))
+    };
+    let (a_geom, b_geom) = (geometry(a_entry)?, geometry(b_entry)?);
+
+    let ((a_x, a_y), (b_x, b_y)) = swapped_origins(&a_geom, &b_geom)
+        .ok_or(AppError::SwapOverlap(a.to_string(), b.to_string()))?;
+
+    batch(backend, |backend| {
+        backend.set_coordinates(a, a_x, a_y)?;
+        backend.set_coordinates(b, b_x, b_y)?;
+
+        if a_entry.primary {
+            backend.set_primary(b)?;
+        } else if b_entry.primary {
+            backend.set_primary(a)?;
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::rotate::Rotation;
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> Geometry {
+        Geometry {
+            x,
+            y,
+            width,
+            height,
+            rotation: Rotation::Normal,
+            scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn swaps_the_outer_of_three() {
+        // A|B|C, B stays in the middle
+        let a = geometry(0, 0, 1920, 1080);
+        let c = geometry(3840, 0, 1920, 1080);
+        assert_eq!(swapped_origins(&a, &c), Some(((3840, 0), (0, 0))));
+        assert_eq!(swapped_origins(&c, &a), Some(((0, 0), (3840, 0))));
+    }
+
+    #[test]
+    fn keeps_the_span_of_different_sizes() {
+        let a = geometry(0, 0, 1920, 1080);
+        let b = geometry(1920, 0, 2560, 1440);
+        // B|A, from 0 to 4480 as before
+        assert_eq!(swapped_origins(&a, &b), Some(((2560, 0), (0, 0))));
+    }
+
+    #[test]
+    fn swaps_stacked_outputs() {
+        let top = geometry(0, 0, 1920, 1080);
+        let bottom = geometry(320, 1080, 1280, 720);
+        assert_eq!(swapped_origins(&top, &bottom), Some(((320, 720), (0, 0))));
+    }
+
+    #[test]
+    fn overlapping_outputs_do_not_swap() {
+        let a = geometry(0, 0, 1920, 1080);
+        let mirror = geometry(0, 0, 1920, 1080);
+        let partly = geometry(960, 540, 1920, 1080);
+        assert_eq!(swapped_origins(&a, &mirror), None);
+        assert_eq!(swapped_origins(&a, &partly), None);
+    }
+}
@@ -0,0 +1,38 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::err::ParseError;
+use crate::prompt::Prompt;
+use crate::AppError;
+
+// Shared by the operations that switch some output setting on or off
+pub const ON: &str = "On";
+pub const OFF: &str = "Off";
+
+pub fn from_str(s: &str) -> Result<bool, ParseError> {
+    match s {
+        ON => Ok(true),
+        OFF => Ok(false),
+        _ => Err(ParseError::Toggle(s.to_string())),
+    }
+}
+
+pub fn parse_hdr(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::toggle_list(Prompt::Hdr),
+        Some(s) => ParseResult::hdr(output, from_str(&s)?),
+    })
+}
+
+pub fn parse_tearing(ctx: ParseCtx) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::toggle_list(Prompt::Tearing),
+        Some(s) => ParseResult::tearing(output, from_str(&s)?),
+    })
+}
@@ -0,0 +1,19 @@
+use super::{Action, ParseCtx, ParseResult};
+use crate::backend::DisplayBackend;
+use crate::AppError;
+
+// Workspace names are free form: besides picking an existing one,
+// a new name can be typed into rofi
+pub fn parse(
+    backend: &mut Box<dyn DisplayBackend>,
+    ctx: ParseCtx,
+) -> Result<ParseResult<Action>, AppError> {
+    let ParseCtx {
+        output, mut args, ..
+    } = ctx;
+
+    Ok(match args.pop_front() {
+        None => ParseResult::workspace_list(backend, &output)?,
+        Some(workspace) => ParseResult::workspace(output, workspace),
+    })
+}
@@ -0,0 +1,37 @@
+// Connector names like `DP-3` change between docks and reboots, the monitor
+// behind them does not. Outputs are therefore also identified by a hash of
+// the EDID that the monitor reports. Every backend hashes the raw bytes, so
+// a monitor keeps its id when switching backends.
+
+use std::fs;
+
+// Where the kernel lists DRM connectors, as `card<N>-<connector>`
+const DRM_DIR: &str = "/sys/class/drm";
+
+// The raw EDID that the kernel read from the monitor on `connector`, e.g.
+// `DP-1`. Wayland compositors name their outputs after these connectors.
+pub fn from_sysfs(connector: &str) -> Option<Vec<u8>> {
+    fs::read_dir(DRM_DIR)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .find(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.split_once('-').is_some_and(|(card, c)| {
+                card.starts_with("card") && c == connector
+            })
+        })
+        .and_then(|e| fs::read(e.path().join("edid")).ok())
+        .filter(|edid| !edid.is_empty())
+}
+
+// 64 bit FNV-1a. Unlike the std hashers, this is guaranteed to stay the
+// same between builds, which matters because the hashes are saved.
+pub fn hash(edid: &[u8]) -> String {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let hash = edid
+        .iter()
+        .fold(OFFSET, |h, b| (h ^ *b as u64).wrapping_mul(PRIME));
+    format!("{hash:016x}")
+}
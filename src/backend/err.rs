@@ -8,11 +8,17 @@ pub enum BackendCall {
     #[error("libxrandr")]
     LibXrandr(#[from] xrandr::XrandrError),
 
-    #[error("swayipc")]
+    #[error("swayipc: {0}")]
     SwayIPC(#[from] swayipc::Error),
 
+    #[error("mutter")]
+    Mutter(#[from] zbus::Error),
+
     #[error("wayland-client")]
     WaylandClient(#[from] wayland_client::ConnectError),
+
+    #[error("wlr-randr")]
+    WlrRandr(String),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -25,9 +31,6 @@ pub enum GetHandle {
 pub enum GetOutputs {
     #[error("Call in display backend failed:\n{0}")]
     BackendCall(#[from] BackendCall),
-
-    #[error("xrandr CLI")]
-    XrandrCLI(String),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -77,10 +80,22 @@ pub enum SetRate {
     #[error("Output '{0}' has no current mode")]
     NoMode(String),
 
-    #[error("Could not find requested rate ({0})")]
+    #[error("No mode with rate {0} Hz at the current resolution")]
     NoRate(Rate),
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum GetGeometry {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("Output '{0}' is disabled")]
+    Disabled(String),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum SetRotation {
     #[error("Call in display backend failed:\n{0}")]
@@ -97,6 +112,48 @@ pub enum SetPosition {
 
     #[error("Could not find requested output ({0})")]
     NoOutput(String),
+
+    #[error("Can not position relative to disabled output '{0}'")]
+    RelativeDisabled(String),
+
+    #[error("'{0}' and '{1}' have no resolution in common to mirror at")]
+    NoCommonResolution(String, String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetCoordinates {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CommitBatch {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WarpPointer {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CreateOutput {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("The new output did not show up")]
+    NotFound,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WaitForChange {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -108,6 +165,183 @@ pub enum SetPrimary {
     NoOutput(String),
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum SetColorDepth {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("Output {0} has no \"max bpc\" property")]
+    NoMaxBpc(String),
+
+    #[error("Output {0} does not support {1} bits per channel")]
+    UnsupportedBpc(String, u8),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetHdr {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetTearing {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetScaleFilter {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetScale {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("Output '{0}' has no current mode")]
+    NoMode(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetSubpixel {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetProjectorFit {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("Output '{0}' has no current mode")]
+    NoMode(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetMaxRenderTime {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetPanning {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetPower {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetColorProfile {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("Invalid color profile {0}: {1}")]
+    Invalid(String, String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetGamma {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetGamma {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetWorkspaces {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetEdid {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("{0} did not report an EDID")]
+    NoEdid(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetSeats {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetSeat {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+
+    #[error("Could not find seat {0}")]
+    NoSeat(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SetWorkspace {
+    #[error("Call in display backend failed:\n{0}")]
+    BackendCall(#[from] BackendCall),
+
+    #[error("Could not find requested output ({0})")]
+    NoOutput(String),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Enable {
     #[error("Call in display backend failed:\n{0}")]
@@ -131,6 +365,12 @@ pub enum Error {
     #[error("Could not find fitting display server")]
     GetBackend,
 
+    #[error("Could not find fitting display server. Detected:\n{0}")]
+    NoBackend(Box<super::Detected>),
+
+    #[error("Unknown display backend '{0}'")]
+    UnknownBackend(String),
+
     #[error("Could not open a connection to the display server ({0})")]
     GetHandle(#[from] GetHandle),
 
@@ -149,15 +389,104 @@ pub enum Error {
     #[error("Could not set rate:\n{0}")]
     SetRate(#[from] SetRate),
 
+    #[error("Could not get geometry:\n{0}")]
+    GetGeometry(#[from] GetGeometry),
+
     #[error("Could not set rate:\n{0}")]
     SetRotation(#[from] SetRotation),
 
     #[error("Could not set position:\n{0}")]
     SetPosition(#[from] SetPosition),
 
+    #[error("Could not set coordinates:\n{0}")]
+    SetCoordinates(#[from] SetCoordinates),
+
+    #[error("Could not wait for output changes:\n{0}")]
+    WaitForChange(#[from] WaitForChange),
+
+    #[error("Could not apply the changes together:\n{0}")]
+    CommitBatch(#[from] CommitBatch),
+
+    #[error("Could not move the pointer:\n{0}")]
+    WarpPointer(#[from] WarpPointer),
+
+    #[error("Could not create output:\n{0}")]
+    CreateOutput(#[from] CreateOutput),
+
     #[error("Could not set display as primary:\n{0}")]
     SetPrimary(#[from] SetPrimary),
 
+    #[error("Could not set color depth:\n{0}")]
+    SetColorDepth(#[from] SetColorDepth),
+
+    #[error("Could not set HDR:\n{0}")]
+    SetHdr(#[from] SetHdr),
+
+    #[error("Could not set tearing:\n{0}")]
+    SetTearing(#[from] SetTearing),
+
+    #[error("Could not set scale filter:\n{0}")]
+    SetScaleFilter(#[from] SetScaleFilter),
+
+    #[error("Could not set scale:\n{0}")]
+    SetScale(#[from] SetScale),
+
+    #[error("Could not set subpixel order:\n{0}")]
+    SetSubpixel(#[from] SetSubpixel),
+
+    #[error("Could not set panning:\n{0}")]
+    SetPanning(#[from] SetPanning),
+
+    #[error("Could not fit output:\n{0}")]
+    SetProjectorFit(#[from] SetProjectorFit),
+
+    #[error("Could not set max render time:\n{0}")]
+    SetMaxRenderTime(#[from] SetMaxRenderTime),
+
+    #[error("Could not set power state:\n{0}")]
+    SetPower(#[from] SetPower),
+
+    #[error("Could not set color profile:\n{0}")]
+    SetColorProfile(#[from] SetColorProfile),
+
+    #[error("Could not set gamma:\n{0}")]
+    SetGamma(#[from] SetGamma),
+
+    #[error("Could not get gamma:\n{0}")]
+    GetGamma(#[from] GetGamma),
+
+    #[error("Could not get workspaces:\n{0}")]
+    GetWorkspaces(#[from] GetWorkspaces),
+
+    #[error("Could not assign workspace:\n{0}")]
+    SetWorkspace(#[from] SetWorkspace),
+
+    #[error("Could not get EDID:\n{0}")]
+    GetEdid(#[from] GetEdid),
+
+    #[error("Could not get seats:\n{0}")]
+    GetSeats(#[from] GetSeats),
+
+    #[error("Could not bind seat:\n{0}")]
+    SetSeat(#[from] SetSeat),
+
+    #[error("Operation is not supported by this display backend")]
+    Unsupported,
+
+    #[error("Could not run `{0}`: it must be installed and on PATH")]
+    MissingBinary(String),
+
+    #[error(
+        "The new layout would be {width}x{height}, which exceeds the \
+        maximum screen size of {max_width}x{max_height}"
+    )]
+    ScreenSize {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+
     #[error("Could not enable display")]
     Enable(#[from] Enable),
 
@@ -165,10 +494,46 @@ pub enum Error {
     Disable(#[from] Disable),
 }
 
+impl Error {
+    // The output that the operation was for does not exist (anymore)
+    pub fn is_no_output(&self) -> bool {
+        matches!(
+            self,
+            Error::GetResolutions(GetResolutions::NoOutput(_))
+                | Error::SetResolution(SetResolution::NoOutput(_))
+                | Error::GetRates(GetRates::NoOutput(_))
+                | Error::SetRate(SetRate::NoOutput(_))
+                | Error::GetGeometry(GetGeometry::NoOutput(_))
+                | Error::SetRotation(SetRotation::NoOutput(_))
+                | Error::SetPosition(SetPosition::NoOutput(_))
+                | Error::SetCoordinates(SetCoordinates::NoOutput(_))
+                | Error::SetPrimary(SetPrimary::NoOutput(_))
+                | Error::SetColorDepth(SetColorDepth::NoOutput(_))
+                | Error::SetHdr(SetHdr::NoOutput(_))
+                | Error::SetTearing(SetTearing::NoOutput(_))
+                | Error::SetScaleFilter(SetScaleFilter::NoOutput(_))
+                | Error::SetScale(SetScale::NoOutput(_))
+                | Error::SetSubpixel(SetSubpixel::NoOutput(_))
+                | Error::SetProjectorFit(SetProjectorFit::NoOutput(_))
+                | Error::SetMaxRenderTime(SetMaxRenderTime::NoOutput(_))
+                | Error::SetPanning(SetPanning::NoOutput(_))
+                | Error::SetPower(SetPower::NoOutput(_))
+                | Error::SetColorProfile(SetColorProfile::NoOutput(_))
+                | Error::SetGamma(SetGamma::NoOutput(_))
+                | Error::GetGamma(GetGamma::NoOutput(_))
+                | Error::GetEdid(GetEdid::NoOutput(_))
+                | Error::SetSeat(SetSeat::NoOutput(_))
+                | Error::SetWorkspace(SetWorkspace::NoOutput(_))
+                | Error::Enable(Enable::NoOutput(_))
+                | Error::Disable(Disable::NoOutput(_))
+        )
+    }
+}
+
 /// Helps keep error propegation in the backend short
 /// # Arguments
 /// * `err_type` - the error that should be built from the backend error,
-///     e.g. `GetResolutions`.
+///   e.g. `GetResolutions`.
 /// * `backend ` - The backend from which the error came, e.g. `XrandrCLI`.
 /// * `args` - Potential arguments to the `backend` error type.
 #[macro_export]
@@ -1,19 +1,46 @@
+use crate::action::depth::{bpc, Depth};
+use crate::action::fit::FitMode;
+use crate::action::gamma::Gamma;
+use crate::action::icc::Vcgt;
+use crate::action::panning::Panning;
 use crate::action::position::Position;
 use crate::action::position::Relation;
+use crate::action::power::PowerState;
 use crate::action::rate::Rate;
 use crate::action::resolution::Resolution;
 use crate::action::rotate::Rotation;
-use crate::action::Operation;
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
 use crate::backend::Error as BackendError;
 use crate::backend_call as backend_call_err;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_int;
+use std::ptr::NonNull;
+use x11::xrender::XTransform;
+use x11::{dpms, xlib};
 use xrandr::ScreenResources;
 use xrandr::XHandle;
+use xrandr::XrandrError;
 
-use super::{OutputEntry, RateEntry, ResolutionEntry};
+use super::edid;
+use super::screen_size::{self, LayoutChange};
+use super::DisplayBackend;
+use super::{
+    find_output, unique_names, Capabilities, Geometry, Named, OutputEntry,
+    RateEntry, ResolutionEntry, WorkspaceEntry,
+};
+
+impl Named for xrandr::Output {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
 
 pub struct Backend {
     handle: XHandle,
     res: ScreenResources,
+    dpy: Display,
 }
 
 impl Backend {
@@ -22,33 +49,750 @@ impl Backend {
             .map_err(|e| backend_call_err!(GetOutputs, LibXrandr, e))?;
         let res = ScreenResources::new(&mut handle)
             .map_err(|e| backend_call_err!(GetOutputs, LibXrandr, e))?;
+        let dpy = Display::open()
+            .map_err(|e| backend_call_err!(GetOutputs, LibXrandr, e))?;
 
-        Ok(Self { handle, res })
+        Ok(Self { handle, res, dpy })
+    }
+}
+
+// The xrandr crate does not expose the display pointer of its handle, so
+// this is a connection of our own for the calls it does not wrap. It is
+// opened once, and closed when the backend is dropped.
+struct Display(NonNull<xlib::Display>);
+
+impl Display {
+    fn open() -> Result<Self, XrandrError> {
+        let dpy = unsafe { xlib::XOpenDisplay(std::ptr::null()) };
+        NonNull::new(dpy).map(Self).ok_or(XrandrError::Open)
+    }
+
+    fn as_ptr(&self) -> *mut xlib::Display {
+        self.0.as_ptr()
+    }
+}
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        unsafe { xlib::XCloseDisplay(self.0.as_ptr()) };
     }
 }
 
 const RATE_EPSILON: f64 = 0.01; // xrandr rates are rounded to 2 decimals
 
-impl super::DisplayBackend for Backend {
-    fn supported_operations(&mut self, output: &OutputEntry) -> Vec<Operation> {
-        match (output.connected, output.enabled) {
-            // If the output is not connected, just give the option
-            // to disable/enable it. (X allows you to unplug an output
-            // while still having it as active)
-            (false, _) => vec![Operation::Disable],
-
-            // If the output is connected but disabled, only show enable option
-            (_, false) => vec![Operation::Enable],
-
-            // Otherwise, list all except enable
-            _ => vec![
-                Operation::Disable,
-                Operation::SetPrimary,
-                Operation::ChangeRes(Resolution::default()),
-                Operation::Position(Position::default()),
-                Operation::ChangeRate(Rate::default()),
-                Operation::Rotate(Rotation::default()),
-            ],
+fn crtc_geometry(crtc: &xrandr::Crtc) -> Geometry {
+    Geometry {
+        x: crtc.x,
+        y: crtc.y,
+        width: crtc.width,
+        height: crtc.height,
+        rotation: crtc.rotation.into(),
+        scale: 1.0, // X has no per output scaling
+    }
+}
+
+// The xrandr crate does not expose output properties for writing, so this
+// talks to Xlib directly.
+// The screen depth is fixed, we can only set the bits per channel that
+// the output sends out, through the "max bpc" property.
+fn set_max_bpc(
+    dpy: &Display,
+    output: &xrandr::Output,
+    bpc: u8,
+) -> Result<(), BackendError> {
+    let name = CString::new("max bpc").expect("No nul bytes in literal");
+    let value = std::os::raw::c_long::from(bpc);
+
+    unsafe {
+        let dpy = dpy.as_ptr();
+
+        // True: only look up the atom, without creating it. It exists
+        // when any driver supports the property.
+        let atom = xlib::XInternAtom(dpy, name.as_ptr(), xlib::True);
+        let info = match atom {
+            0 => std::ptr::null_mut(),
+            _ => x11::xrandr::XRRQueryOutputProperty(dpy, output.xid, atom),
+        };
+        if info.is_null() {
+            let name = output.name.clone();
+            return Err(super::err::SetColorDepth::NoMaxBpc(name).into());
+        }
+
+        // Setting a value outside the range is a BadValue error, which
+        // would make Xlib exit the program
+        let values = match (*info).num_values {
+            n if n > 0 => {
+                std::slice::from_raw_parts((*info).values, n as usize)
+            }
+            _ => &[],
+        };
+        let in_range = match values {
+            [min, max] if (*info).range != 0 => (*min..=*max).contains(&value),
+            _ => values.is_empty() || values.contains(&value),
+        };
+        let immutable = (*info).immutable != 0;
+        xlib::XFree(info.cast());
+
+        if immutable || !in_range {
+            let name = output.name.clone();
+            let e = super::err::SetColorDepth::UnsupportedBpc(name, bpc);
+            return Err(e.into());
+        }
+
+        // Format 32 properties are passed as an array of longs
+        x11::xrandr::XRRChangeOutputProperty(
+            dpy,
+            output.xid,
+            atom,
+            xlib::XA_INTEGER,
+            32,
+            xlib::PropModeReplace,
+            std::ptr::addr_of!(value).cast(),
+            1,
+        );
+        xlib::XSync(dpy, xlib::False);
+    }
+
+    Ok(())
+}
+
+// Width and height of the X screen
+type ScreenSize = (u32, u32);
+
+// The smallest and the largest the X screen can get, which the xrandr crate
+// does not expose
+fn screen_size_range(
+    dpy: &Display,
+) -> Result<(ScreenSize, ScreenSize), XrandrError> {
+    let (mut min_w, mut min_h, mut max_w, mut max_h) = (0, 0, 0, 0);
+
+    unsafe {
+        let dpy = dpy.as_ptr();
+
+        let status = x11::xrandr::XRRGetScreenSizeRange(
+            dpy,
+            xlib::XDefaultRootWindow(dpy),
+            &mut min_w,
+            &mut min_h,
+            &mut max_w,
+            &mut max_h,
+        );
+
+        if status == 0 {
+            return Err(XrandrError::GetResources);
+        }
+    }
+
+    Ok(((min_w as u32, min_h as u32), (max_w as u32, max_h as u32)))
+}
+
+// Maps outputs to the name of the provider (GPU) they belong to. Only has
+// entries when there are multiple providers, as on hybrid graphics laptops.
+// This is only extra information, so it is left empty when X fails to
+// report the providers.
+fn output_providers(dpy: &Display) -> HashMap<xrandr::XId, String> {
+    let mut providers = HashMap::new();
+
+    unsafe {
+        let dpy = dpy.as_ptr();
+        let root = xlib::XDefaultRootWindow(dpy);
+
+        let res = x11::xrandr::XRRGetScreenResourcesCurrent(dpy, root);
+        let prov_res = x11::xrandr::XRRGetProviderResources(dpy, root);
+
+        // With a single provider, there is nothing to tell apart
+        let n_providers = match (res.is_null(), prov_res.is_null()) {
+            (false, false) => (*prov_res).nproviders as usize,
+            _ => 0,
+        };
+        let n_providers = if n_providers > 1 { n_providers } else { 0 };
+
+        for i in 0..n_providers {
+            let id = *(*prov_res).providers.add(i);
+            let info = x11::xrandr::XRRGetProviderInfo(dpy, res, id);
+            if info.is_null() {
+                continue;
+            }
+
+            let name = CStr::from_ptr((*info).name).to_string_lossy();
+            for j in 0..(*info).noutputs as usize {
+                providers.insert(*(*info).outputs.add(j), name.to_string());
+            }
+            x11::xrandr::XRRFreeProviderInfo(info);
+        }
+
+        if !prov_res.is_null() {
+            x11::xrandr::XRRFreeProviderResources(prov_res);
+        }
+        if !res.is_null() {
+            x11::xrandr::XRRFreeScreenResources(res);
+        }
+    }
+
+    providers
+}
+
+// Gamma ramps are built like xrandr does: x^(1/gamma) * brightness, for x
+// from 0 to 1. The current gamma and brightness are estimated from the ramp
+// in the same way.
+fn estimate_gamma(channels: &[&mut [u16]; 3]) -> (Gamma, f64) {
+    let size = channels[0].len();
+    let x = |i: usize| i as f64 / (size - 1) as f64;
+
+    let brightness = channels
+        .iter()
+        .map(|c| f64::from(c[size - 1]) / 65535.0)
+        .fold(0.0, f64::max);
+    let brightness = if brightness > 0.0 {
+        (brightness * 100.0).round() / 100.0
+    } else {
+        1.0
+    };
+
+    let mid = size / 2;
+    let estimate = |c: &[u16]| {
+        let v = f64::from(c[mid]) / 65535.0 / brightness;
+        if v > 0.0 && v < 1.0 {
+            (x(mid).ln() / v.ln() * 100.0).round() / 100.0
+        } else {
+            1.0
+        }
+    };
+    let gamma = Gamma {
+        red: estimate(channels[0]),
+        green: estimate(channels[1]),
+        blue: estimate(channels[2]),
+    };
+
+    (gamma, brightness)
+}
+
+fn crtc_gamma(
+    dpy: &Display,
+    crtc: xrandr::XId,
+) -> Result<(Gamma, f64), XrandrError> {
+    unsafe {
+        let dpy = dpy.as_ptr();
+
+        let ramp = x11::xrandr::XRRGetCrtcGamma(dpy, crtc);
+        if ramp.is_null() {
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+        if (*ramp).size < 2 {
+            x11::xrandr::XRRFreeGamma(ramp);
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+
+        let size = (*ramp).size as usize;
+        let channels = [(*ramp).red, (*ramp).green, (*ramp).blue]
+            .map(|c| std::slice::from_raw_parts_mut(c, size));
+        let current = estimate_gamma(&channels);
+
+        x11::xrandr::XRRFreeGamma(ramp);
+        Ok(current)
+    }
+}
+
+// `update` gets the current gamma and brightness, so that it can change
+// one and keep the other.
+fn update_crtc_gamma(
+    dpy: &Display,
+    crtc: xrandr::XId,
+    update: impl FnOnce(Gamma, f64) -> (Gamma, f64),
+) -> Result<(), XrandrError> {
+    write_crtc_ramp(dpy, crtc, |channels| {
+        let size = channels[0].len();
+        let x = |i: usize| i as f64 / (size - 1) as f64;
+        let (current, brightness) = estimate_gamma(channels);
+        let (gamma, brightness) = update(current, brightness);
+        let gammas = [gamma.red, gamma.green, gamma.blue];
+        for (channel, g) in channels.iter_mut().zip(gammas) {
+            for (i, v) in channel.iter_mut().enumerate() {
+                let value = x(i).powf(1.0 / g) * brightness * 65535.0;
+                *v = value.round().clamp(0.0, 65535.0) as u16;
+            }
+        }
+    })
+}
+
+// Sets the calibration curves of a color profile, resampled to the size of
+// the crtc's ramp
+fn set_crtc_curves(
+    dpy: &Display,
+    crtc: xrandr::XId,
+    vcgt: &Vcgt,
+) -> Result<(), XrandrError> {
+    write_crtc_ramp(dpy, crtc, |channels| {
+        let size = channels[0].len();
+        for (c, channel) in channels.iter_mut().enumerate() {
+            for (i, v) in channel.iter_mut().enumerate() {
+                *v = vcgt.sample(c, i as f64 / (size - 1) as f64);
+            }
+        }
+    })
+}
+
+// Hands the current gamma ramp of the crtc to `fill`, and sets it to what
+// `fill` leaves in there
+fn write_crtc_ramp(
+    dpy: &Display,
+    crtc: xrandr::XId,
+    fill: impl FnOnce(&mut [&mut [u16]; 3]),
+) -> Result<(), XrandrError> {
+    unsafe {
+        let dpy = dpy.as_ptr();
+
+        let ramp = x11::xrandr::XRRGetCrtcGamma(dpy, crtc);
+        if ramp.is_null() {
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+        if (*ramp).size < 2 {
+            x11::xrandr::XRRFreeGamma(ramp);
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+
+        let size = (*ramp).size as usize;
+        let mut channels = [(*ramp).red, (*ramp).green, (*ramp).blue]
+            .map(|c| std::slice::from_raw_parts_mut(c, size));
+        fill(&mut channels);
+
+        x11::xrandr::XRRSetCrtcGamma(dpy, crtc, ramp);
+        x11::xrandr::XRRFreeGamma(ramp);
+        xlib::XSync(dpy, xlib::False);
+    }
+
+    Ok(())
+}
+
+// Grows the screen to at least `width`x`height`, keeping the DPI that it
+// had. Callers check that this fits in the maximum screen size first, see
+// `Backend::check_screen_size`.
+fn grow_screen(dpy: &Display, width: i64, height: i64) {
+    let (cur_w, cur_h) = current_screen_size(dpy);
+    set_screen_size(dpy, cur_w.max(width), cur_h.max(height));
+}
+
+fn current_screen_size(dpy: &Display) -> (i64, i64) {
+    unsafe {
+        let dpy = dpy.as_ptr();
+        let screen = xlib::XDefaultScreen(dpy);
+
+        let width = i64::from(xlib::XDisplayWidth(dpy, screen));
+        let height = i64::from(xlib::XDisplayHeight(dpy, screen));
+        (width, height)
+    }
+}
+
+// Keeps the DPI that the screen had
+fn set_screen_size(dpy: &Display, width: i64, height: i64) {
+    let (cur_w, cur_h) = current_screen_size(dpy);
+    if (width, height) == (cur_w, cur_h) {
+        return;
+    }
+
+    unsafe {
+        let dpy = dpy.as_ptr();
+        let screen = xlib::XDefaultScreen(dpy);
+
+        // In i64, as the size in mm times the size in px can overflow an int
+        let mm_w =
+            i64::from(xlib::XDisplayWidthMM(dpy, screen)) * width / cur_w;
+        let mm_h =
+            i64::from(xlib::XDisplayHeightMM(dpy, screen)) * height / cur_h;
+        let int = |v: i64| c_int::try_from(v).unwrap_or(c_int::MAX);
+        x11::xrandr::XRRSetScreenSize(
+            dpy,
+            xlib::XDefaultRootWindow(dpy),
+            int(width),
+            int(height),
+            int(mm_w),
+            int(mm_h),
+        );
+    }
+}
+
+// Sets the screen to the size that the crtcs and their panning areas
+// cover, from its top left corner. Unlike `grow_screen`, this also shrinks
+// the screen, e.g. after an output moved in from the edge.
+fn fit_screen(dpy: &Display) -> Result<(), XrandrError> {
+    let (mut width, mut height) = (0_i64, 0_i64);
+    let mut cover = |x: i64, y: i64, w: u32, h: u32| {
+        width = width.max(x + i64::from(w));
+        height = height.max(y + i64::from(h));
+    };
+
+    unsafe {
+        let ptr = dpy.as_ptr();
+        let root = xlib::XDefaultRootWindow(ptr);
+
+        let res = x11::xrandr::XRRGetScreenResourcesCurrent(ptr, root);
+        if res.is_null() {
+            return Err(XrandrError::GetResources);
+        }
+        let ncrtc = usize::try_from((*res).ncrtc).unwrap_or_default();
+        for &crtc in std::slice::from_raw_parts((*res).crtcs, ncrtc) {
+            let info = x11::xrandr::XRRGetCrtcInfo(ptr, res, crtc);
+            if info.is_null() {
+                continue;
+            }
+            // Disabled crtcs have no mode, and cover nothing
+            if (*info).mode != 0 {
+                let (x, y) = (i64::from((*info).x), i64::from((*info).y));
+                cover(x, y, (*info).width, (*info).height);
+            }
+            x11::xrandr::XRRFreeCrtcInfo(info);
+
+            let panning = x11::xrandr::XRRGetPanning(ptr, res, crtc);
+            if !panning.is_null() {
+                let p = &*panning;
+                let (x, y) = (i64::from(p.left), i64::from(p.top));
+                cover(x, y, p.width, p.height);
+                x11::xrandr::XRRFreePanning(panning);
+            }
+        }
+        x11::xrandr::XRRFreeScreenResources(res);
+    }
+
+    // Nothing is on, there is nothing to fit
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+
+    let ((min_w, min_h), _) = screen_size_range(dpy)?;
+    set_screen_size(
+        dpy,
+        width.max(i64::from(min_w)),
+        height.max(i64::from(min_h)),
+    );
+    Ok(())
+}
+
+// The xrandr crate only positions outputs relative to each other, so this
+// moves the crtc itself. The screen is grown first if the crtc would not
+// fit, and fit to all crtcs afterwards, keeping the DPI that the screen had.
+fn set_crtc_origin(
+    display: &Display,
+    crtc: xrandr::XId,
+    x: i32,
+    y: i32,
+) -> Result<(), XrandrError> {
+    let status = unsafe {
+        let dpy = display.as_ptr();
+        let root = xlib::XDefaultRootWindow(dpy);
+
+        let res = x11::xrandr::XRRGetScreenResourcesCurrent(dpy, root);
+        if res.is_null() {
+            return Err(XrandrError::GetResources);
+        }
+        let info = x11::xrandr::XRRGetCrtcInfo(dpy, res, crtc);
+        if info.is_null() {
+            x11::xrandr::XRRFreeScreenResources(res);
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+
+        grow_screen(
+            display,
+            i64::from(x) + i64::from((*info).width),
+            i64::from(y) + i64::from((*info).height),
+        );
+
+        let status = x11::xrandr::XRRSetCrtcConfig(
+            dpy,
+            res,
+            crtc,
+            (*info).timestamp,
+            x,
+            y,
+            (*info).mode,
+            (*info).rotation,
+            (*info).outputs,
+            (*info).noutput,
+        );
+
+        x11::xrandr::XRRFreeCrtcInfo(info);
+        x11::xrandr::XRRFreeScreenResources(res);
+        xlib::XSync(dpy, xlib::False);
+        status
+    };
+
+    // Anything but RRSetConfigSuccess means the config was outdated
+    if status != 0 {
+        return Err(XrandrError::CrtcChanged(crtc));
+    }
+
+    fit_screen(display)?;
+    unsafe { xlib::XSync(display.as_ptr(), xlib::False) };
+    Ok(())
+}
+
+// Has X report changes of the screen or of one of the outputs. Those are
+// the only events this connection receives. Selecting them again does
+// nothing.
+fn select_screen_changes(dpy: &Display) {
+    unsafe {
+        let dpy = dpy.as_ptr();
+
+        let mask = x11::xrandr::RRScreenChangeNotifyMask
+            | x11::xrandr::RROutputChangeNotifyMask;
+        x11::xrandr::XRRSelectInput(dpy, xlib::XDefaultRootWindow(dpy), mask);
+    }
+}
+
+// Blocks until one of the changes selected by `select_screen_changes`,
+// returning right away for one that came in before
+fn wait_for_screen_change(dpy: &Display) {
+    unsafe {
+        let mut event: xlib::XEvent = std::mem::zeroed();
+        xlib::XNextEvent(dpy.as_ptr(), &mut event);
+    }
+}
+
+// DPMS is a separate extension, which is not per output but for the whole
+// screen. Returns whether the server supports it.
+fn force_dpms_level(dpy: &Display, level: u16) -> Result<bool, XrandrError> {
+    unsafe {
+        let dpy = dpy.as_ptr();
+        if dpms::DPMSCapable(dpy) == 0 {
+            return Ok(false);
+        }
+
+        let (mut current, mut enabled) = (0, 0);
+        dpms::DPMSInfo(dpy, &mut current, &mut enabled);
+
+        // Outputs are always on while DPMS is disabled
+        if enabled == 0 && level == dpms::DPMSModeOn {
+            return Ok(true);
+        }
+
+        // Forcing a level needs DPMS enabled. Disabling it again afterwards
+        // would turn the outputs back on, so its timeouts are cleared
+        // instead, which keeps the outputs from blanking by themselves
+        // like they did not before.
+        if enabled == 0 {
+            dpms::DPMSSetTimeouts(dpy, 0, 0, 0);
+            dpms::DPMSEnable(dpy);
+        }
+        dpms::DPMSForceLevel(dpy, level);
+        xlib::XFlush(dpy);
+
+        Ok(true)
+    }
+}
+
+// Xlib is used directly, the xrandr crate does not deal with the pointer
+fn warp_pointer_to(dpy: &Display, x: i32, y: i32) -> Result<(), XrandrError> {
+    unsafe {
+        let dpy = dpy.as_ptr();
+
+        let root = xlib::XDefaultRootWindow(dpy);
+        xlib::XWarpPointer(dpy, 0, root, 0, 0, 0, 0, x, y);
+        xlib::XFlush(dpy);
+    }
+
+    Ok(())
+}
+
+// The panning area has to be on the screen, so the screen is grown to fit
+// it first, like `set_crtc_origin` does. The pointer tracking area and
+// borders are left at 0, which xrandr also does when they are not given.
+fn set_crtc_panning(
+    display: &Display,
+    crtc: xrandr::XId,
+    panning: &Panning,
+) -> Result<(), XrandrError> {
+    let status = unsafe {
+        let dpy = display.as_ptr();
+        let root = xlib::XDefaultRootWindow(dpy);
+
+        let res = x11::xrandr::XRRGetScreenResourcesCurrent(dpy, root);
+        if res.is_null() {
+            return Err(XrandrError::GetResources);
+        }
+        let current = x11::xrandr::XRRGetPanning(dpy, res, crtc);
+        if current.is_null() {
+            x11::xrandr::XRRFreeScreenResources(res);
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+
+        if !panning.is_off() {
+            grow_screen(
+                display,
+                i64::from(panning.x) + i64::from(panning.width),
+                i64::from(panning.y) + i64::from(panning.height),
+            );
+        }
+
+        let mut new = x11::xrandr::XRRPanning {
+            timestamp: (*current).timestamp,
+            left: panning.x,
+            top: panning.y,
+            width: panning.width,
+            height: panning.height,
+            track_left: 0,
+            track_top: 0,
+            track_width: 0,
+            track_height: 0,
+            border_left: 0,
+            border_top: 0,
+            border_right: 0,
+            border_bottom: 0,
+        };
+        let status = x11::xrandr::XRRSetPanning(dpy, res, crtc, &mut new);
+
+        x11::xrandr::XRRFreePanning(current);
+        x11::xrandr::XRRFreeScreenResources(res);
+        xlib::XSync(dpy, xlib::False);
+        status
+    };
+
+    // Like `XRRSetCrtcConfig`, anything else means the config was outdated
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(XrandrError::CrtcChanged(crtc))
+    }
+}
+
+// Sets the transform of the crtc, which only takes effect with the next
+// crtc config, so the current one is set again. `size` is how much of the
+// screen the crtc covers with the transform, which is grown to fit it like
+// `set_crtc_origin` does.
+fn set_crtc_transform(
+    display: &Display,
+    crtc: xrandr::XId,
+    matrix: &[f64; 9],
+    size: (u32, u32),
+) -> Result<(), XrandrError> {
+    // 16.16 fixed point, like `XDoubleToFixed`
+    let fixed = |v: f64| (v * 65536.0).round() as c_int;
+    let mut transform = XTransform {
+        matrix: [
+            [fixed(matrix[0]), fixed(matrix[1]), fixed(matrix[2])],
+            [fixed(matrix[3]), fixed(matrix[4]), fixed(matrix[5])],
+            [fixed(matrix[6]), fixed(matrix[7]), fixed(matrix[8])],
+        ],
+    };
+    // Scaled output looks better filtered, xrandr picks the same
+    let identity = *matrix == [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+    let filter = match identity {
+        true => c"nearest",
+        false => c"bilinear",
+    };
+
+    let status = unsafe {
+        let dpy = display.as_ptr();
+        let root = xlib::XDefaultRootWindow(dpy);
+
+        let res = x11::xrandr::XRRGetScreenResourcesCurrent(dpy, root);
+        if res.is_null() {
+            return Err(XrandrError::GetResources);
+        }
+        let info = x11::xrandr::XRRGetCrtcInfo(dpy, res, crtc);
+        if info.is_null() {
+            x11::xrandr::XRRFreeScreenResources(res);
+            return Err(XrandrError::GetCrtcInfo(crtc));
+        }
+
+        grow_screen(
+            display,
+            i64::from((*info).x) + i64::from(size.0),
+            i64::from((*info).y) + i64::from(size.1),
+        );
+
+        x11::xrandr::XRRSetCrtcTransform(
+            dpy,
+            crtc,
+            &mut transform,
+            filter.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+        );
+        let status = x11::xrandr::XRRSetCrtcConfig(
+            dpy,
+            res,
+            crtc,
+            (*info).timestamp,
+            (*info).x,
+            (*info).y,
+            (*info).mode,
+            (*info).rotation,
+            (*info).outputs,
+            (*info).noutput,
+        );
+
+        x11::xrandr::XRRFreeCrtcInfo(info);
+        x11::xrandr::XRRFreeScreenResources(res);
+        xlib::XSync(dpy, xlib::False);
+        status
+    };
+
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(XrandrError::CrtcChanged(crtc))
+    }
+}
+
+impl Backend {
+    // The crtc that drives the output. The errors are those of the
+    // operation it is for, through `err_f` and `no_output`.
+    fn output_crtc(
+        &mut self,
+        output_name: &str,
+        err_f: fn(XrandrError) -> BackendError,
+        no_output: fn(String) -> BackendError,
+    ) -> Result<xrandr::XId, BackendError> {
+        let outputs = self.res.outputs(&mut self.handle).map_err(err_f)?;
+
+        let output = find_output(&outputs, output_name)
+            .ok_or_else(|| no_output(output_name.to_string()))?;
+
+        output.crtc.ok_or_else(|| {
+            err_f(XrandrError::OutputDisabled(output_name.to_string()))
+        })
+    }
+
+    fn update_gamma(
+        &mut self,
+        output_name: &str,
+        update: impl FnOnce(Gamma, f64) -> (Gamma, f64),
+    ) -> Result<(), BackendError> {
+        let crtc = self.output_crtc(
+            output_name,
+            |e| backend_call_err!(SetGamma, LibXrandr, e),
+            |o| super::err::SetGamma::NoOutput(o).into(),
+        )?;
+        update_crtc_gamma(&self.dpy, crtc, update)
+            .map_err(|e| backend_call_err!(SetGamma, LibXrandr, e))
+    }
+
+    fn check_screen_size(
+        &mut self,
+        output_name: &str,
+        change: LayoutChange,
+    ) -> Result<(), BackendError> {
+        let (_, max) = screen_size_range(&self.dpy)
+            .map_err(|e| backend_call_err!(GetOutputs, LibXrandr, e))?;
+        screen_size::check(&self.get_outputs()?, output_name, change, max)
+    }
+}
+
+impl DisplayBackend for Backend {
+    fn capabilities(&mut self) -> Capabilities {
+        Capabilities {
+            backend: "libxrandr",
+            primary: true,
+            mirror: true,
+            color_depth: true,
+            gamma: true,
+            power: true,
+            power_all: true,
+            panning: true,
+            projector_fit: true,
+            color_profile: true,
+            export: true,
+            edid: true,
+            ..Default::default()
         }
     }
 
@@ -59,6 +803,7 @@ impl super::DisplayBackend for Backend {
             Relation::Below,
             Relation::Above,
             Relation::SameAs,
+            Relation::CenteredOn,
         ]
     }
 
@@ -67,19 +812,57 @@ impl super::DisplayBackend for Backend {
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(GetOutputs, LibXrandr, e))?;
+        let crtcs = self
+            .res
+            .crtcs(&mut self.handle)
+            .map_err(|e| backend_call_err!(GetOutputs, LibXrandr, e))?;
+        let providers = output_providers(&self.dpy);
 
         let entries = outputs
             .iter()
-            .map(|o| OutputEntry {
-                name: o.name.clone(),
+            .zip(unique_names(&outputs))
+            .map(|(o, name)| OutputEntry {
+                name,
                 connected: o.connected,
                 enabled: o.current_mode.is_some(),
+                primary: o.is_primary,
+                edid_hash: o.edid().map(|edid| edid::hash(&edid)),
+                description: None,
+                provider: providers.get(&o.xid).cloned(),
+                adaptive_sync: None,
+                geometry: o
+                    .crtc
+                    .and_then(|id| crtcs.iter().find(|c| c.xid == id))
+                    .map(crtc_geometry),
             })
             .collect();
 
         Ok(entries)
     }
 
+    fn get_geometry(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Geometry, BackendError> {
+        let outputs = self
+            .res
+            .outputs(&mut self.handle)
+            .map_err(|e| backend_call_err!(GetGeometry, LibXrandr, e))?;
+
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::GetGeometry::NoOutput(output_name.to_string()),
+        )?;
+        let crtc_id = output.crtc.ok_or(super::err::GetGeometry::Disabled(
+            output_name.to_string(),
+        ))?;
+
+        let crtc = self
+            .res
+            .crtc(&mut self.handle, crtc_id)
+            .map_err(|e| backend_call_err!(GetGeometry, LibXrandr, e))?;
+        Ok(crtc_geometry(&crtc))
+    }
+
     fn get_resolutions(
         &mut self,
         output: &str,
@@ -89,9 +872,7 @@ impl super::DisplayBackend for Backend {
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(GetResolutions, LibXrandr, e))?;
 
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output)
+        let output = find_output(&outputs, output)
             .ok_or(super::err::GetResolutions::NoOutput(output.to_string()))?;
 
         let current_mode_id = output
@@ -103,7 +884,12 @@ impl super::DisplayBackend for Backend {
             .mode(current_mode_id)
             .map_err(|_| super::err::GetResolutions::GetCurrent)?;
 
-        let mut entries = self
+        let preferred_mode = output
+            .preferred_modes
+            .first()
+            .and_then(|xid| self.res.mode(*xid).ok());
+
+        let entries = self
             .res
             .modes()
             .iter()
@@ -115,17 +901,14 @@ impl super::DisplayBackend for Backend {
                 },
                 current: m.width == current_mode.width
                     && m.height == current_mode.height,
+                preferred: preferred_mode.as_ref().is_some_and(|p| {
+                    m.width == p.width && m.height == p.height
+                }),
+                interlaced: false,
             })
             .collect::<Vec<ResolutionEntry>>();
 
-        entries.sort_by(|a, b| {
-            u32::cmp(
-                &(b.val.width * b.val.height),
-                &(a.val.width * a.val.height),
-            )
-        });
-        entries.dedup();
-        Ok(entries)
+        Ok(ResolutionEntry::merge(entries))
     }
 
     fn set_resolution(
@@ -133,12 +916,14 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         res: &Resolution,
     ) -> Result<(), BackendError> {
+        self.check_screen_size(output_name, LayoutChange::Resize(res))?;
+
         let outputs = self
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(SetResolution, LibXrandr, e))?;
 
-        let output = outputs.iter().find(|o| o.name == output_name).ok_or(
+        let output = find_output(&outputs, output_name).ok_or(
             super::err::SetResolution::NoOutput(output_name.to_string()),
         )?;
 
@@ -166,9 +951,7 @@ impl super::DisplayBackend for Backend {
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(GetRates, LibXrandr, e))?;
 
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::GetRates::NoOutput(output_name.to_string()))?;
 
         let current_mode_id = output
@@ -191,9 +974,9 @@ impl super::DisplayBackend for Backend {
                 val: m.rate,
                 current: (m.rate - current_mode.rate).abs() < RATE_EPSILON,
             })
-            .collect();
+            .collect::<Vec<RateEntry>>();
 
-        Ok(entries)
+        Ok(RateEntry::merge(entries))
     }
 
     fn set_rate(
@@ -205,9 +988,7 @@ impl super::DisplayBackend for Backend {
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(SetRate, LibXrandr, e))?;
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::SetRate::NoOutput(output_name.to_string()))?;
 
         let current_mode_id = output
@@ -223,11 +1004,15 @@ impl super::DisplayBackend for Backend {
             .modes
             .iter()
             .filter(|m| output.modes.contains(&m.xid))
-            .find(|m| {
+            .filter(|m| {
                 m.width == current_mode.width
                     && m.height == current_mode.height
                     && (m.rate - rate).abs() < RATE_EPSILON
             })
+            // Rates can be closer together than the epsilon
+            .min_by(|a, b| {
+                f64::total_cmp(&(a.rate - rate).abs(), &(b.rate - rate).abs())
+            })
             .ok_or(super::err::SetRate::NoRate(rate))?;
 
         self.handle
@@ -242,12 +1027,14 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         rotation: &Rotation,
     ) -> Result<(), BackendError> {
+        self.check_screen_size(output_name, LayoutChange::Rotate(rotation))?;
+
         let outputs = self
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(SetRotation, LibXrandr, e))?;
 
-        let output = outputs.iter().find(|o| o.name == output_name).ok_or(
+        let output = find_output(&outputs, output_name).ok_or(
             super::err::SetRotation::NoOutput(output_name.to_string()),
         )?;
 
@@ -263,59 +1050,308 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         pos: &Position,
     ) -> Result<(), BackendError> {
-        let Position {
-            output_s: rel_output,
-            relation,
-            ..
-        } = pos;
+        self.check_screen_size(output_name, LayoutChange::Place(pos))?;
+
+        let Some(xrel) = pos.relation.xrandr() else {
+            let (x, y) = super::centered_on(self, output_name, &pos.output_s)?;
+            return self.set_coordinates(output_name, x, y);
+        };
+        let rel_output = &pos.output_s;
 
         let outputs = self
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(SetPosition, LibXrandr, e))?;
 
-        let output = outputs.iter().find(|o| o.name == output_name).ok_or(
+        let output = find_output(&outputs, output_name).ok_or(
             super::err::SetPosition::NoOutput(output_name.to_string()),
         )?;
 
-        let rel_output = outputs.iter().find(|o| &o.name == rel_output).ok_or(
-            super::err::SetPosition::NoOutput(output_name.to_string()),
-        )?;
+        let rel_output = find_output(&outputs, rel_output)
+            .ok_or(super::err::SetPosition::NoOutput(rel_output.to_string()))?;
 
         assert!(output.name != rel_output.name, "UI should prohibit this");
+        // The UI does not offer these either, see `relatives_list`
+        if rel_output.current_mode.is_none() {
+            let name = rel_output.name.clone();
+            Err(super::err::SetPosition::RelativeDisabled(name))?;
+        }
 
-        let xrel = &xrandr::Relation::from(relation);
         self.handle
-            .set_position(output, xrel, rel_output)
+            .set_position(output, &xrel, rel_output)
             .map_err(|e| backend_call_err!(SetPosition, LibXrandr, e))?;
 
         Ok(())
     }
 
+    fn set_coordinates(
+        &mut self,
+        output_name: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .res
+            .outputs(&mut self.handle)
+            .map_err(|e| backend_call_err!(SetCoordinates, LibXrandr, e))?;
+
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetCoordinates::NoOutput(output_name.to_string()),
+        )?;
+
+        let crtc = output.crtc.ok_or_else(|| {
+            let e = XrandrError::OutputDisabled(output_name.to_string());
+            backend_call_err!(SetCoordinates, LibXrandr, e)
+        })?;
+
+        set_crtc_origin(&self.dpy, crtc, x, y)
+            .map_err(|e| backend_call_err!(SetCoordinates, LibXrandr, e))
+    }
+
     fn set_primary(&mut self, output_name: &str) -> Result<(), BackendError> {
         let outputs = self
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(SetPrimary, LibXrandr, e))?;
 
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::SetPrimary::NoOutput(output_name.to_string()))?;
 
         self.handle.set_primary(output);
         Ok(())
     }
 
+    fn set_color_depth(
+        &mut self,
+        output_name: &str,
+        depth: Depth,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .res
+            .outputs(&mut self.handle)
+            .map_err(|e| backend_call_err!(SetColorDepth, LibXrandr, e))?;
+
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetColorDepth::NoOutput(output_name.to_string()),
+        )?;
+
+        set_max_bpc(&self.dpy, output, bpc(depth))
+    }
+
+    fn set_hdr(
+        &mut self,
+        _output_name: &str,
+        _on: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_tearing(
+        &mut self,
+        _output_name: &str,
+        _allow: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale_filter(
+        &mut self,
+        _output_name: &str,
+        _filter: &Filter,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_panning(
+        &mut self,
+        output_name: &str,
+        panning: &Panning,
+    ) -> Result<(), BackendError> {
+        self.check_screen_size(output_name, LayoutChange::Pan(panning))?;
+
+        let crtc = self.output_crtc(
+            output_name,
+            |e| backend_call_err!(SetPanning, LibXrandr, e),
+            |o| super::err::SetPanning::NoOutput(o).into(),
+        )?;
+        set_crtc_panning(&self.dpy, crtc, panning)
+            .map_err(|e| backend_call_err!(SetPanning, LibXrandr, e))
+    }
+
+    fn set_projector_fit(
+        &mut self,
+        output_name: &str,
+        source: &Resolution,
+        fit: &FitMode,
+    ) -> Result<(), BackendError> {
+        let target = self
+            .get_resolutions(output_name)?
+            .into_iter()
+            .find(|r| r.current)
+            .map(|r| r.val)
+            .ok_or(super::err::SetProjectorFit::NoMode(
+                output_name.to_string(),
+            ))?;
+        let matrix = super::xrandr_cli::fit_matrix(fit, source, &target);
+
+        // The far corner of the output, on the screen. Casting saturates,
+        // so this is 0 rather than negative.
+        let corner = |len: u32, scale: f64, offset: f64| {
+            (f64::from(len) * scale + offset).ceil() as u32
+        };
+        let size = (
+            corner(target.width, matrix[0], matrix[2]),
+            corner(target.height, matrix[4], matrix[5]),
+        );
+        let cover = LayoutChange::Cover(size.0, size.1);
+        self.check_screen_size(output_name, cover)?;
+
+        let crtc = self.output_crtc(
+            output_name,
+            |e| backend_call_err!(SetProjectorFit, LibXrandr, e),
+            |o| super::err::SetProjectorFit::NoOutput(o).into(),
+        )?;
+        set_crtc_transform(&self.dpy, crtc, &matrix, size)
+            .map_err(|e| backend_call_err!(SetProjectorFit, LibXrandr, e))
+    }
+
+    // A transform of the whole output, like `xrandr --scale`
+    fn set_scale(
+        &mut self,
+        output_name: &str,
+        scale: f64,
+    ) -> Result<(), BackendError> {
+        let mode = self
+            .get_resolutions(output_name)?
+            .into_iter()
+            .find(|r| r.current)
+            .map(|r| r.val)
+            .ok_or(super::err::SetScale::NoMode(output_name.to_string()))?;
+
+        let scaled = |len: u32| (f64::from(len) * scale).ceil() as u32;
+        let size = (scaled(mode.width), scaled(mode.height));
+        let cover = LayoutChange::Cover(size.0, size.1);
+        self.check_screen_size(output_name, cover)?;
+
+        let matrix = [scale, 0.0, 0.0, 0.0, scale, 0.0, 0.0, 0.0, 1.0];
+        let crtc = self.output_crtc(
+            output_name,
+            |e| backend_call_err!(SetScale, LibXrandr, e),
+            |o| super::err::SetScale::NoOutput(o).into(),
+        )?;
+        set_crtc_transform(&self.dpy, crtc, &matrix, size)
+            .map_err(|e| backend_call_err!(SetScale, LibXrandr, e))
+    }
+
+    fn set_subpixel(
+        &mut self,
+        _output_name: &str,
+        _subpixel: &Subpixel,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    // DPMS is not per output in X, this affects all of them
+    fn set_power(
+        &mut self,
+        output_name: &str,
+        power: &PowerState,
+    ) -> Result<(), BackendError> {
+        if !self.get_outputs()?.iter().any(|o| o.name == output_name) {
+            let name = output_name.to_string();
+            return Err(super::err::SetPower::NoOutput(name).into());
+        }
+
+        let level = match power {
+            PowerState::On => dpms::DPMSModeOn,
+            PowerState::Standby => dpms::DPMSModeStandby,
+            PowerState::Off => dpms::DPMSModeOff,
+        };
+
+        match force_dpms_level(&self.dpy, level)
+            .map_err(|e| backend_call_err!(SetPower, LibXrandr, e))?
+        {
+            true => Ok(()),
+            false => Err(BackendError::Unsupported),
+        }
+    }
+
+    fn set_gamma(
+        &mut self,
+        output_name: &str,
+        gamma: &Gamma,
+    ) -> Result<(), BackendError> {
+        self.update_gamma(output_name, |_, brightness| (*gamma, brightness))
+    }
+
+    fn set_brightness(
+        &mut self,
+        output_name: &str,
+        brightness: f64,
+    ) -> Result<(), BackendError> {
+        self.update_gamma(output_name, |gamma, _| (gamma, brightness))
+    }
+
+    fn set_color_profile(
+        &mut self,
+        output_name: &str,
+        vcgt: &Vcgt,
+    ) -> Result<(), BackendError> {
+        let crtc = self.output_crtc(
+            output_name,
+            |e| backend_call_err!(SetColorProfile, LibXrandr, e),
+            |o| super::err::SetColorProfile::NoOutput(o).into(),
+        )?;
+        set_crtc_curves(&self.dpy, crtc, vcgt)
+            .map_err(|e| backend_call_err!(SetColorProfile, LibXrandr, e))
+    }
+
+    fn get_gamma(
+        &mut self,
+        output_name: &str,
+    ) -> Result<(Gamma, f64), BackendError> {
+        let crtc = self.output_crtc(
+            output_name,
+            |e| backend_call_err!(GetGamma, LibXrandr, e),
+            |o| super::err::GetGamma::NoOutput(o).into(),
+        )?;
+        crtc_gamma(&self.dpy, crtc)
+            .map_err(|e| backend_call_err!(GetGamma, LibXrandr, e))
+    }
+
+    fn get_edid(&mut self, output_name: &str) -> Result<Vec<u8>, BackendError> {
+        let outputs = self
+            .res
+            .outputs(&mut self.handle)
+            .map_err(|e| backend_call_err!(GetEdid, LibXrandr, e))?;
+
+        let output = find_output(&outputs, output_name)
+            .ok_or(super::err::GetEdid::NoOutput(output_name.to_string()))?;
+        let edid = output
+            .edid()
+            .ok_or(super::err::GetEdid::NoEdid(output_name.to_string()))?;
+        Ok(edid)
+    }
+
+    fn get_workspaces(&mut self) -> Result<Vec<WorkspaceEntry>, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_workspace(
+        &mut self,
+        _output_name: &str,
+        _workspace: &str,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
     fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
         let outputs = self
             .res
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(Enable, LibXrandr, e))?;
 
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::Enable::NoOutput(output_name.to_string()))?;
 
         self.handle
@@ -331,9 +1367,7 @@ impl super::DisplayBackend for Backend {
             .outputs(&mut self.handle)
             .map_err(|e| backend_call_err!(Disable, LibXrandr, e))?;
 
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::Disable::NoOutput(output_name.to_string()))?;
 
         self.handle
@@ -342,4 +1376,31 @@ impl super::DisplayBackend for Backend {
 
         Ok(())
     }
+
+    // Hotplugging can add outputs and modes, so the resources are renewed
+    fn watch_changes(&mut self) -> Result<(), BackendError> {
+        select_screen_changes(&self.dpy);
+        Ok(())
+    }
+
+    fn wait_for_change(&mut self) -> Result<(), BackendError> {
+        // In case `watch_changes` was not called
+        select_screen_changes(&self.dpy);
+        wait_for_screen_change(&self.dpy);
+
+        self.res = ScreenResources::new(&mut self.handle)
+            .map_err(|e| backend_call_err!(WaitForChange, LibXrandr, e))?;
+        Ok(())
+    }
+
+    fn layout_command(&mut self) -> Result<String, BackendError> {
+        Ok(super::xrandr_cli::xrandr_command(&self.get_layout()?))
+    }
+
+    fn warp_pointer(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let (x, y) = self.get_geometry(output_name)?.center();
+
+        warp_pointer_to(&self.dpy, x, y)
+            .map_err(|e| backend_call_err!(WarpPointer, LibXrandr, e))
+    }
 }
@@ -1,46 +1,312 @@
+mod edid;
 pub mod err;
 mod libxrandr;
+mod mutter;
+mod screen_size;
 mod sway;
+mod wlr_randr;
 mod xrandr_cli;
 
 pub(crate) use self::err::Error;
+use crate::action::depth::Depth;
+use crate::action::fit::FitMode;
+use crate::action::gamma::Gamma;
+use crate::action::icc::Vcgt;
+use crate::action::mirror;
+use crate::action::panning::Panning;
 use crate::action::position::{Position, Relation};
+use crate::action::power::PowerState;
 use crate::action::rate::Rate;
 use crate::action::resolution::Resolution;
-use crate::action::rotate::Rotation;
+use crate::action::rotate::{Rotation, Transform};
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
 use crate::action::Operation;
-use std::env;
+use crate::config::Config;
+use std::{env, fmt, mem};
 
-pub(crate) fn from_name(name: &str) -> Result<Box<dyn DisplayBackend>, Error> {
+pub(crate) fn from_name(
+    name: &str,
+    config: &Config,
+) -> Result<Box<dyn DisplayBackend>, Error> {
     match name {
         "libxrandr" => Ok(Box::new(libxrandr::Backend::new()?)),
         "xrandr_cli" => Ok(Box::new(xrandr_cli::Backend::new()?)),
-        "swayipc" => Ok(Box::new(sway::Backend::new()?)),
-        _ => Err(Error::GetBackend),
+        "swayipc" => Ok(Box::new(sway::Backend::new(config.sway_ipc_retries)?)),
+        "mutter" => Ok(Box::new(mutter::Backend::new()?)),
+        "wlr_randr" => Ok(Box::new(wlr_randr::Backend::new()?)),
+        _ => Err(Error::UnknownBackend(name.to_string())),
+    }
+}
+
+/// The parts of the environment that tell us which display server is running
+#[derive(Debug, Default)]
+pub struct Detected {
+    session_type: Option<String>,
+    wayland_display: Option<String>,
+    swaysock: Option<String>,
+    gnome_setup_display: Option<String>,
+    desktop_session: Option<String>,
+    current_desktop: Option<String>,
+    // Not used to choose, but the X backends connect to it
+    display: Option<String>,
+}
+
+impl Detected {
+    pub fn from_env() -> Self {
+        Self {
+            session_type: env::var("XDG_SESSION_TYPE").ok(),
+            wayland_display: env::var("WAYLAND_DISPLAY").ok(),
+            swaysock: env::var("SWAYSOCK").ok(),
+            gnome_setup_display: env::var("GNOME_SETUP_DISPLAY").ok(),
+            desktop_session: env::var("DESKTOP_SESSION").ok(),
+            current_desktop: env::var("XDG_CURRENT_DESKTOP").ok(),
+            display: env::var("DISPLAY").ok(),
+        }
+    }
+
+    fn is_wayland(&self) -> bool {
+        // Under XWayland, XDG_SESSION_TYPE can read `x11` while the
+        // compositor is really a wayland one, so WAYLAND_DISPLAY wins
+        self.wayland_display.is_some()
+            || self.session_type.as_deref() == Some("wayland")
+    }
+
+    fn desktop_mentions(&self, name: &str) -> bool {
+        let mentions = |var: &Option<String>| {
+            var.as_ref()
+                .is_some_and(|s| s.to_lowercase().contains(name))
+        };
+
+        mentions(&self.desktop_session) || mentions(&self.current_desktop)
+    }
+
+    fn is_gnome(&self) -> bool {
+        self.gnome_setup_display.is_some() || self.desktop_mentions("gnome")
+    }
+
+    /// The name of the backend that fits this environment, if any.
+    /// Wayland compositors are tried first, then X.
+    pub fn backend_name(&self) -> Option<&'static str> {
+        if self.is_wayland() {
+            if self.swaysock.is_some() {
+                return Some("swayipc");
+            }
+            if self.is_gnome() {
+                return Some("mutter");
+            }
+            // Other wlroots compositors, like river or Hyprland. KDE does
+            // not implement the protocol that wlr-randr uses.
+            if !self.desktop_mentions("kde") {
+                return Some("wlr_randr");
+            }
+        }
+
+        match self.session_type.as_deref() {
+            Some("x11") => Some("libxrandr"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Detected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let vars = [
+            ("XDG_SESSION_TYPE", &self.session_type),
+            ("WAYLAND_DISPLAY", &self.wayland_display),
+            ("SWAYSOCK", &self.swaysock),
+            ("GNOME_SETUP_DISPLAY", &self.gnome_setup_display),
+            ("DESKTOP_SESSION", &self.desktop_session),
+            ("XDG_CURRENT_DESKTOP", &self.current_desktop),
+            ("DISPLAY", &self.display),
+        ];
+
+        for (name, val) in vars {
+            writeln!(f, "{name}={}", val.as_deref().unwrap_or("<unset>"))?;
+        }
+        Ok(())
     }
 }
 
-// TODO: this is a bit hacky atm
 /// Gets the appropriate backend based on environment variables
-pub(crate) fn determine() -> Result<Box<dyn DisplayBackend>, Error> {
-    match env::var("XDG_SESSION_TYPE") {
-        Ok(name) => match name.as_str() {
-            "x11" => from_name("libxrandr"),
-            "wayland" => match env::var("SWAYSOCK") {
-                Ok(_) => from_name("swayipc"),
-                Err(_) => Err(Error::GetBackend),
-            },
-            _ => Err(Error::GetBackend),
-        },
-        Err(_) => Err(Error::GetBackend),
+pub(crate) fn determine(
+    config: &Config,
+) -> Result<Box<dyn DisplayBackend>, Error> {
+    let detected = Detected::from_env();
+    match detected.backend_name() {
+        // The CLI still works on X servers that the library can not talk to,
+        // e.g. with an older RandR version. If it does not either, the
+        // library's error is the one that explains the problem.
+        Some("libxrandr") => from_name("libxrandr", config).or_else(|e| {
+            log::debug!("Falling back to xrandr_cli, libxrandr failed: {e}");
+            from_name("xrandr_cli", config).map_err(|_| e)
+        }),
+        Some(name) => from_name(name, config),
+        None => Err(Error::NoBackend(Box::new(detected))),
+    }
+}
+
+/// Like `determine`, unless overridden through `DISPLAY_SERVER_OVERRIDE`
+pub(crate) fn from_env(
+    config: &Config,
+) -> Result<Box<dyn DisplayBackend>, Error> {
+    match env::var("DISPLAY_SERVER_OVERRIDE") {
+        Ok(name) => from_name(&name, config),
+        Err(_) => determine(config),
+    }
+}
+
+/// The operations that only some backends support. The others are
+/// supported everywhere. Unsupported ones return `Error::Unsupported`.
+#[derive(Debug, Default, Clone)]
+pub struct Capabilities {
+    // Name of the backend, to explain missing operations with
+    pub backend: &'static str,
+    pub primary: bool,
+    pub mirror: bool,
+    pub color_depth: bool,
+    // Gamma and brightness
+    pub gamma: bool,
+    pub hdr: bool,
+    pub tearing: bool,
+    pub scale_filter: bool,
+    pub subpixel: bool,
+    pub power: bool,
+    // Power states are set for all outputs at once, as DPMS does on X
+    pub power_all: bool,
+    pub panning: bool,
+    // Scaling mirrored outputs with a transform matrix
+    pub projector_fit: bool,
+    pub max_render_time: bool,
+    // The flipped transforms, besides the four rotations
+    pub flip: bool,
+    // Setting the calibration curves of an ICC profile as gamma ramps
+    pub color_profile: bool,
+    pub workspaces: bool,
+    // Binding an output to one of several seats
+    pub seats: bool,
+    // Can write the layout as a command, see `layout_command`
+    pub export: bool,
+    // Can read the raw EDID of an output, see `get_edid`
+    pub edid: bool,
+    // Can add headless outputs, see `create_output`
+    pub virtual_outputs: bool,
+}
+
+impl Capabilities {
+    fn operations(&self) -> [(bool, Operation); 17] {
+        [
+            (self.power, Operation::Power(PowerState::default())),
+            (self.primary, Operation::SetPrimary),
+            (self.panning, Operation::Panning(Panning::default())),
+            (
+                self.projector_fit,
+                Operation::ProjectorFit(FitMode::default()),
+            ),
+            (self.mirror, Operation::MirrorTo(String::default())),
+            (self.color_depth, Operation::ColorDepth(Depth::default())),
+            (self.gamma, Operation::Gamma(Gamma::default())),
+            (self.gamma, Operation::Brightness(1.0)),
+            (
+                self.color_profile,
+                Operation::ColorProfile(String::default()),
+            ),
+            (self.scale_filter, Operation::ScaleFilter(Filter::default())),
+            (self.subpixel, Operation::Subpixel(Subpixel::default())),
+            (self.workspaces, Operation::Workspace(String::default())),
+            (self.seats, Operation::Seat(String::default())),
+            (self.tearing, Operation::AllowTearing(bool::default())),
+            (self.max_render_time, Operation::MaxRenderTime(None)),
+            (self.hdr, Operation::Hdr(bool::default())),
+            (self.edid, Operation::ExportEdid),
+        ]
+    }
+
+    // Whether the backend can do `op`. Operations that are not in
+    // `operations` are supported by every backend.
+    pub fn supports(&self, op: &Operation) -> bool {
+        let kind = mem::discriminant(op);
+        self.operations()
+            .iter()
+            .all(|(supported, o)| *supported || mem::discriminant(o) != kind)
+    }
+
+    pub fn unsupported(&self) -> Vec<Operation> {
+        self.operations()
+            .into_iter()
+            .filter_map(|(supported, op)| (!supported).then_some(op))
+            .collect()
     }
 }
 
 /// Defines the API that this application wants with the display server
 pub trait DisplayBackend {
-    // The supported operations for this backend
-    // Takes output as argument because ops might change depending on its state
-    fn supported_operations(&mut self, output: &OutputEntry) -> Vec<Operation>;
+    // What this backend can do, regardless of the state of its outputs
+    fn capabilities(&mut self) -> Capabilities;
+
+    // The operations that apply to `output` in its current state
+    fn supported_operations(&mut self, output: &OutputEntry) -> Vec<Operation> {
+        match (output.connected, output.enabled) {
+            // X allows you to unplug an output while it is still active,
+            // so the only thing to do with it is to disable it
+            (false, _) => vec![Operation::Disable],
+
+            // If the output is connected but disabled, only show enable option
+            (_, false) => vec![Operation::Enable],
+
+            _ => {
+                let caps = self.capabilities();
+                let mirrored = self.get_outputs().is_ok_and(|outputs| {
+                    !mirror::mirrored_with(&outputs, output).is_empty()
+                });
+
+                // In menu order, the capabilities filter them
+                [
+                    Operation::Disable,
+                    Operation::Power(PowerState::On),
+                    Operation::SetPrimary,
+                    Operation::ChangeRes(Resolution::default()),
+                    Operation::Position(Position::default()),
+                    Operation::Panning(Panning::default()),
+                    Operation::Swap(String::default()),
+                    Operation::MirrorTo(String::default()),
+                    Operation::Unmirror,
+                    Operation::ProjectorFit(FitMode::default()),
+                    Operation::ChangeRate(Rate::default()),
+                    Operation::LimitRate(Rate::default()),
+                    Operation::Rotate(Transform::default()),
+                    Operation::Reset,
+                    Operation::ColorDepth(Depth::default()),
+                    Operation::Gamma(Gamma::default()),
+                    Operation::Brightness(1.0),
+                    Operation::ColorProfile(String::default()),
+                    Operation::ScaleFilter(Filter::default()),
+                    Operation::Subpixel(Subpixel::default()),
+                    Operation::Workspace(String::default()),
+                    Operation::Seat(String::default()),
+                    Operation::AllowTearing(false),
+                    Operation::MaxRenderTime(None),
+                    Operation::Hdr(false),
+                    Operation::ExportEdid,
+                ]
+                .into_iter()
+                .filter(|op| caps.supports(op))
+                .filter(|op| match op {
+                    Operation::Unmirror | Operation::ProjectorFit(_) => {
+                        mirrored
+                    }
+                    _ => true,
+                })
+                .collect()
+            }
+        }
+    }
+
+    // Outputs without a list of modes, like headless ones in sway, take
+    // any resolution that is typed in
+    fn accepts_any_mode(&mut self, _output_name: &str) -> Result<bool, Error> {
+        Ok(false)
+    }
 
     // This is needed because sway does not really support mirroring
     fn supported_relations(&mut self) -> Vec<Relation>;
@@ -63,23 +329,312 @@ pub trait DisplayBackend {
 
     fn set_rate(&mut self, output_name: &str, rate: Rate) -> Result<(), Error>;
 
+    // Fails for disabled outputs, which are not on the screen
+    fn get_geometry(&mut self, output_name: &str) -> Result<Geometry, Error>;
+
+    fn get_rotation(&mut self, output_name: &str) -> Result<Rotation, Error> {
+        Ok(self.get_geometry(output_name)?.rotation)
+    }
+
     fn set_rotation(
         &mut self,
         output_name: &str,
         rotation: &Rotation,
     ) -> Result<(), Error>;
 
+    fn get_transform(&mut self, output_name: &str) -> Result<Transform, Error> {
+        Ok(Transform::from(self.get_rotation(output_name)?))
+    }
+
+    // Unlike `set_rotation`, this also sets whether the output is flipped,
+    // which only backends with `Capabilities::flip` can
+    fn set_transform(
+        &mut self,
+        output_name: &str,
+        transform: &Transform,
+    ) -> Result<(), Error> {
+        if transform.flipped {
+            return Err(Error::Unsupported);
+        }
+        self.set_rotation(output_name, &transform.rotation)
+    }
+
     fn set_position(
         &mut self,
         output_name: &str,
         pos: &Position,
     ) -> Result<(), Error>;
 
+    // Puts the top left corner of the output at (x, y). The menus mostly
+    // position relative to other outputs, this is for replaying profiles
+    // and arrangements.
+    fn set_coordinates(
+        &mut self,
+        output_name: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), Error>;
+
     fn set_primary(&mut self, output_name: &str) -> Result<(), Error>;
 
+    // Depth is a property of the X screen, see `action::depth`
+    fn set_color_depth(
+        &mut self,
+        output_name: &str,
+        depth: Depth,
+    ) -> Result<(), Error>;
+
+    fn set_hdr(&mut self, output_name: &str, on: bool) -> Result<(), Error>;
+
+    fn set_tearing(
+        &mut self,
+        output_name: &str,
+        allow: bool,
+    ) -> Result<(), Error>;
+
+    fn set_scale_filter(
+        &mut self,
+        output_name: &str,
+        filter: &Filter,
+    ) -> Result<(), Error>;
+
+    // Draws the output `scale` times its size. On X that is a transform,
+    // like `xrandr --scale`.
+    fn set_scale(
+        &mut self,
+        _output_name: &str,
+        _scale: f64,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Switches to the preferred mode, which has resolution `res`. Backends
+    // that can ask for the preferred mode itself, rate and all, do that.
+    fn set_preferred_mode(
+        &mut self,
+        output_name: &str,
+        res: &Resolution,
+    ) -> Result<(), Error> {
+        self.set_resolution(output_name, res)
+    }
+
+    fn set_subpixel(
+        &mut self,
+        output_name: &str,
+        subpixel: &Subpixel,
+    ) -> Result<(), Error>;
+
+    fn set_power(
+        &mut self,
+        output_name: &str,
+        power: &PowerState,
+    ) -> Result<(), Error>;
+
+    // Gamma and brightness are both part of the gamma ramp. Setting
+    // one keeps the other.
+    fn set_gamma(
+        &mut self,
+        output_name: &str,
+        gamma: &Gamma,
+    ) -> Result<(), Error>;
+
+    fn set_brightness(
+        &mut self,
+        output_name: &str,
+        brightness: f64,
+    ) -> Result<(), Error>;
+
+    // Panning over an area of 0x0 turns it off
+    fn set_panning(
+        &mut self,
+        _output_name: &str,
+        _panning: &Panning,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Scales the output so that `source`, the size of what it mirrors,
+    // covers it the way `fit` says
+    fn set_projector_fit(
+        &mut self,
+        _output_name: &str,
+        _source: &Resolution,
+        _fit: &FitMode,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    // `None` lets the compositor render as early as it can
+    fn set_max_render_time(
+        &mut self,
+        _output_name: &str,
+        _ms: Option<u32>,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Replaces the gamma ramps, and so the gamma and brightness
+    fn set_color_profile(
+        &mut self,
+        _output_name: &str,
+        _vcgt: &Vcgt,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
+    // The current gamma and brightness, as they would be set
+    fn get_gamma(&mut self, _output_name: &str) -> Result<(Gamma, f64), Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Only sway has workspaces that belong to an output
+    fn get_workspaces(&mut self) -> Result<Vec<WorkspaceEntry>, Error>;
+
+    fn set_workspace(
+        &mut self,
+        output_name: &str,
+        workspace: &str,
+    ) -> Result<(), Error>;
+
+    // As the monitor reports it, for debugging
+    fn get_edid(&mut self, _output_name: &str) -> Result<Vec<u8>, Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Only sway has several seats, each with their own input devices
+    fn get_seats(&mut self) -> Result<Vec<SeatEntry>, Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Maps the pointer and touch devices of the seat to the output, and
+    // moves its cursor there
+    fn set_seat(
+        &mut self,
+        _output_name: &str,
+        _seat: &str,
+    ) -> Result<(), Error> {
+        Err(Error::Unsupported)
+    }
+
     fn enable(&mut self, output_name: &str) -> Result<(), Error>;
 
     fn disable(&mut self, output_name: &str) -> Result<(), Error>;
+
+    // Starts listening for changes, so that `wait_for_change` also returns
+    // for the ones made between this and calling it. Backends that poll
+    // compare against what they read last, and have nothing to start.
+    fn watch_changes(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // Blocks until an output is plugged in, unplugged or reconfigured
+    fn wait_for_change(&mut self) -> Result<(), Error>;
+
+    // Moves the mouse pointer to the center of the output
+    fn warp_pointer(&mut self, output_name: &str) -> Result<(), Error>;
+
+    // The current state of every connected output
+    fn get_layout(&mut self) -> Result<Vec<OutputLayout>, Error> {
+        let outputs = self.get_outputs()?;
+        let mut layout = Vec::new();
+
+        for o in outputs.into_iter().filter(|o| o.connected) {
+            if !o.enabled {
+                layout.push(OutputLayout {
+                    name: o.name,
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            let resolution = self
+                .get_resolutions(&o.name)?
+                .into_iter()
+                .find(|r| r.current)
+                .map(|r| r.val);
+            let rate = self
+                .get_rates(&o.name)?
+                .into_iter()
+                .find(|r| r.current)
+                .map(|r| r.val);
+            let geometry = self.get_geometry(&o.name)?;
+            let (gamma, brightness) = match self.get_gamma(&o.name) {
+                Ok((gamma, brightness)) => (Some(gamma), Some(brightness)),
+                Err(Error::Unsupported) => (None, None),
+                Err(e) => return Err(e),
+            };
+
+            layout.push(OutputLayout {
+                name: o.name,
+                enabled: true,
+                primary: o.primary,
+                resolution,
+                rate,
+                x: geometry.x,
+                y: geometry.y,
+                rotation: geometry.rotation,
+                scale: geometry.scale,
+                gamma,
+                brightness,
+            });
+        }
+
+        Ok(layout)
+    }
+
+    // Adds a headless output, for remote desktops. Returns its name.
+    fn create_output(&mut self) -> Result<String, Error> {
+        Err(Error::Unsupported)
+    }
+
+    // A shell command that recreates the current layout
+    fn layout_command(&mut self) -> Result<String, Error> {
+        Err(Error::Unsupported)
+    }
+
+    // Changes made after this may be held back until `commit_batch`, to
+    // apply them all at once. Backends that can not do that apply every
+    // change right away, and ignore these. Use `batch` to call them.
+    fn begin_batch(&mut self) {}
+
+    fn commit_batch(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // Drops the changes that were held back
+    fn discard_batch(&mut self) {}
+}
+
+// Where `output` goes to be centered on `other`. Neither X backend can
+// center with a relation, so they set these coordinates instead.
+fn centered_on(
+    backend: &mut impl DisplayBackend,
+    output: &str,
+    other: &str,
+) -> Result<(i32, i32), Error> {
+    let current = backend.get_geometry(output)?;
+    let other = backend.get_geometry(other)?;
+    let new = current.placed(&Relation::CenteredOn, &other);
+    Ok((new.x, new.y))
+}
+
+// Makes the changes of `f` in one go, when the backend supports that. If
+// `f` fails, none of its held back changes are made.
+pub fn batch<T, E: From<Error>>(
+    backend: &mut Box<dyn DisplayBackend>,
+    f: impl FnOnce(&mut Box<dyn DisplayBackend>) -> Result<T, E>,
+) -> Result<T, E> {
+    backend.begin_batch();
+    match f(backend) {
+        Ok(res) => {
+            backend.commit_batch()?;
+            Ok(res)
+        }
+        Err(e) => {
+            backend.discard_batch();
+            Err(e)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -87,12 +642,150 @@ pub struct OutputEntry {
     pub name: String,
     pub connected: bool,
     pub enabled: bool,
+    // Always false on backends without the concept, like sway
+    pub primary: bool,
+    // Hash of the make/model/serial, which stays the same across connectors
+    pub edid_hash: Option<String>,
+    // "make model serial", on backends that list them
+    pub description: Option<String>,
+    // The GPU driving the output, only set if there are several
+    pub provider: Option<String>,
+    // Whether variable refresh rate is on, if the backend can tell
+    pub adaptive_sync: Option<bool>,
+    // Where the output currently is on the screen, if it is enabled
+    pub geometry: Option<Geometry>,
+}
+
+#[cfg(test)]
+impl OutputEntry {
+    // A connected and enabled output, for tests to change what they need
+    pub fn test(name: &str, geometry: Option<Geometry>) -> Self {
+        Self {
+            name: name.to_string(),
+            connected: true,
+            enabled: true,
+            primary: false,
+            edid_hash: None,
+            description: None,
+            provider: None,
+            adaptive_sync: None,
+            geometry,
+        }
+    }
+}
+
+// Some multiplexed setups and driver bugs report several outputs under one
+// name. The ones after the first are listed as `NAME#2`, `NAME#3` and so
+// on, and backends find them back through `find_output`.
+pub trait Named {
+    fn name(&self) -> &str;
+}
+
+// The name each output is listed under, in the same order
+pub fn unique_names<T: Named>(outputs: &[T]) -> Vec<String> {
+    outputs
+        .iter()
+        .enumerate()
+        .map(|(i, o)| {
+            let before = outputs[..i].iter().filter(|p| p.name() == o.name());
+            match before.count() {
+                0 => o.name().to_string(),
+                n => format!("{}#{}", o.name(), n + 1),
+            }
+        })
+        .collect()
+}
+
+// The output listed under `name`, see `unique_names`
+pub fn find_output<'a, T: Named>(
+    outputs: &'a [T],
+    name: &str,
+) -> Option<&'a T> {
+    let i = unique_names(outputs).iter().position(|n| n == name)?;
+    outputs.get(i)
+}
+
+// What it takes to put an output back the way it is now
+#[derive(Debug, Default, Clone)]
+pub struct OutputLayout {
+    pub name: String,
+    pub enabled: bool,
+    // The rest only matters for enabled outputs
+    pub primary: bool,
+    pub resolution: Option<Resolution>,
+    pub rate: Option<Rate>,
+    pub x: i32,
+    pub y: i32,
+    pub rotation: Rotation,
+    pub scale: f64,
+    // Only for backends that can read them back
+    pub gamma: Option<Gamma>,
+    pub brightness: Option<f64>,
+}
+
+// Position and size are in the (logical) screen space,
+// so width and height are after rotating and scaling.
+#[derive(Debug, Clone)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub rotation: Rotation,
+    pub scale: f64,
+}
+
+impl Geometry {
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.x + self.width as i32 / 2,
+            self.y + self.height as i32 / 2,
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ResolutionEntry {
     pub val: Resolution,
     pub current: bool,
+    // Not every backend knows this, see the respective `get_resolutions`
+    pub preferred: bool,
+    // Only available as an interlaced mode (only known to xrandr_cli)
+    pub interlaced: bool,
+}
+
+impl ResolutionEntry {
+    // Largest first, with the entries of the same resolution merged. Modes
+    // are listed once per rate, sometimes with other resolutions in between.
+    pub fn merge(entries: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        let mut merged: Vec<Self> = Vec::new();
+        for entry in entries {
+            match merged.iter_mut().find(|m| m.val == entry.val) {
+                Some(m) => {
+                    m.current |= entry.current;
+                    m.preferred |= entry.preferred;
+                    m.interlaced &= entry.interlaced;
+                }
+                None => merged.push(entry),
+            }
+        }
+
+        merged.sort_by(|a, b| b.val.cmp(&a.val));
+        merged
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceEntry {
+    pub name: String,
+    pub output: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeatEntry {
+    pub name: String,
+    // Those that can be mapped to an output, see `set_seat`
+    pub devices: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -100,3 +793,172 @@ pub struct RateEntry {
     pub val: Rate,
     pub current: bool,
 }
+
+// Rates closer than this are the same rate listed twice. Backends report at
+// most mHz, so distinct rates are at least 0.001 apart.
+const SAME_RATE: f64 = 0.0005;
+
+impl RateEntry {
+    // Highest first, with the entries of the same rate merged
+    pub fn merge(entries: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        let mut merged: Vec<Self> = entries.into_iter().collect();
+        merged.sort_by(|a, b| b.val.total_cmp(&a.val));
+        merged.dedup_by(|a, b| {
+            let same = (a.val - b.val).abs() < SAME_RATE;
+            b.current |= same && a.current;
+            same
+        });
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn res(width: u32, height: u32) -> ResolutionEntry {
+        ResolutionEntry {
+            val: Resolution { width, height },
+            current: false,
+            preferred: false,
+            interlaced: false,
+        }
+    }
+
+    fn rate(val: Rate, current: bool) -> RateEntry {
+        RateEntry { val, current }
+    }
+
+    fn sizes(entries: &[ResolutionEntry]) -> Vec<(u32, u32)> {
+        entries
+            .iter()
+            .map(|e| (e.val.width, e.val.height))
+            .collect()
+    }
+
+    #[test]
+    fn resolutions_largest_first() {
+        let merged = ResolutionEntry::merge([
+            res(1280, 720),
+            res(3840, 2160),
+            res(1920, 1080),
+        ]);
+        assert_eq!(sizes(&merged), [(3840, 2160), (1920, 1080), (1280, 720)]);
+    }
+
+    #[test]
+    fn equal_pixel_counts_wider_first() {
+        // Both are 1440000 px
+        let merged = ResolutionEntry::merge([res(1440, 1000), res(1600, 900)]);
+        assert_eq!(sizes(&merged), [(1600, 900), (1440, 1000)]);
+    }
+
+    #[test]
+    fn duplicates_merged_apart() {
+        let current = ResolutionEntry {
+            current: true,
+            ..res(1920, 1080)
+        };
+        let preferred = ResolutionEntry {
+            preferred: true,
+            ..res(1920, 1080)
+        };
+        let merged =
+            ResolutionEntry::merge([current, res(1280, 720), preferred]);
+
+        assert_eq!(sizes(&merged), [(1920, 1080), (1280, 720)]);
+        assert!(merged[0].current && merged[0].preferred);
+        assert!(!merged[1].current && !merged[1].preferred);
+    }
+
+    #[test]
+    fn interlaced_only_without_progressive() {
+        let interlaced = ResolutionEntry {
+            interlaced: true,
+            ..res(1920, 1080)
+        };
+        let only = ResolutionEntry {
+            interlaced: true,
+            ..res(1280, 720)
+        };
+        let merged = ResolutionEntry::merge([
+            interlaced.clone(),
+            res(1920, 1080),
+            only.clone(),
+            only,
+        ]);
+
+        assert!(!merged[0].interlaced);
+        assert!(merged[1].interlaced);
+    }
+
+    #[test]
+    fn rates_highest_first() {
+        let merged = RateEntry::merge([
+            rate(59.94, false),
+            rate(144.0, false),
+            rate(60.0, true),
+        ]);
+        let vals: Vec<Rate> = merged.iter().map(|r| r.val).collect();
+        assert_eq!(vals, [144.0, 60.0, 59.94]);
+        assert!(merged[1].current);
+    }
+
+    #[test]
+    fn same_rate_merged_and_keeps_current() {
+        let merged = RateEntry::merge([
+            rate(60.0, false),
+            rate(59.999, false),
+            rate(60.0001, true),
+        ]);
+        let vals: Vec<Rate> = merged.iter().map(|r| r.val).collect();
+        assert_eq!(vals, [60.0001, 59.999]);
+        assert!(merged[0].current);
+        assert!(!merged[1].current);
+    }
+
+    impl Named for &str {
+        fn name(&self) -> &str {
+            self
+        }
+    }
+
+    #[test]
+    fn shared_names_numbered() {
+        let outputs = ["HDMI-1", "DP-1", "HDMI-1", "HDMI-1"];
+        assert_eq!(
+            unique_names(&outputs),
+            ["HDMI-1", "DP-1", "HDMI-1#2", "HDMI-1#3"]
+        );
+    }
+
+    #[test]
+    fn find_by_unique_name() {
+        let outputs = ["HDMI-1", "DP-1", "HDMI-1"];
+        let found = |name| {
+            let o = find_output(&outputs, name)?;
+            outputs.iter().position(|p| std::ptr::eq(p, o))
+        };
+        assert_eq!(found("HDMI-1"), Some(0));
+        assert_eq!(found("HDMI-1#2"), Some(2));
+        assert_eq!(found("DP-1"), Some(1));
+        assert_eq!(found("DP-1#2"), None);
+    }
+
+    #[test]
+    fn capabilities_filter_operations() {
+        let caps = Capabilities {
+            gamma: true,
+            ..Default::default()
+        };
+        assert!(caps.supports(&Operation::Reset));
+        assert!(caps.supports(&Operation::Brightness(0.5)));
+        assert!(caps.supports(&Operation::Gamma(Gamma::default())));
+        assert!(!caps.supports(&Operation::Power(PowerState::Off)));
+        assert!(!caps.supports(&Operation::ExportEdid));
+        assert!(!caps
+            .unsupported()
+            .iter()
+            .any(|op| matches!(op, Operation::Brightness(_))));
+    }
+}
@@ -0,0 +1,933 @@
+use itertools::Itertools;
+use std::collections::HashMap;
+
+use crate::action::depth::Depth;
+use crate::action::gamma::Gamma;
+use crate::action::position::Position;
+use crate::action::position::Relation;
+use crate::action::power::PowerState;
+use crate::action::rate::Rate;
+use crate::action::resolution::Resolution;
+use crate::action::rotate::{Rotation, Transform};
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
+use crate::backend::Error as BackendError;
+use crate::backend_call as backend_call_err;
+use zbus::blocking::{Connection, Proxy, SignalIterator};
+use zbus::zvariant::{OwnedValue, Value};
+
+use super::edid;
+use super::{
+    Capabilities, Geometry, OutputEntry, RateEntry, ResolutionEntry,
+    WorkspaceEntry,
+};
+
+const DESTINATION: &str = "org.gnome.Mutter.DisplayConfig";
+const PATH: &str = "/org/gnome/Mutter/DisplayConfig";
+const INTERFACE: &str = "org.gnome.Mutter.DisplayConfig";
+
+// ApplyMonitorsConfig methods: 0 = verify, 1 = temporary, 2 = persistent
+const METHOD_PERSISTENT: u32 = 2;
+
+// layout-mode property: 1 = logical (scaled), 2 = physical
+const LAYOUT_PHYSICAL: u32 = 2;
+
+// Mutter rates are not rounded, but rofi shows them with 2 decimals
+const RATE_EPSILON: f64 = 0.01;
+
+// The raw types as they come in over D-Bus, see:
+// https://gitlab.gnome.org/GNOME/mutter/-/blob/main/data/dbus-interfaces/org.gnome.Mutter.DisplayConfig.xml
+type Props = HashMap<String, OwnedValue>;
+type RawSpec = (String, String, String, String);
+type RawMode = (String, i32, i32, f64, f64, Vec<f64>, Props);
+type RawMonitor = (RawSpec, Vec<RawMode>, Props);
+type RawLogical = (i32, i32, f64, u32, bool, Vec<RawSpec>, Props);
+type RawState = (u32, Vec<RawMonitor>, Vec<RawLogical>, Props);
+
+// The types that ApplyMonitorsConfig expects
+type ApplyMonitor<'a> = (String, String, HashMap<&'a str, Value<'a>>);
+type ApplyLogical<'a> = (i32, i32, f64, u32, bool, Vec<ApplyMonitor<'a>>);
+
+fn prop_bool(props: &Props, key: &str) -> bool {
+    props
+        .get(key)
+        .and_then(|v| v.downcast_ref::<bool>())
+        .copied()
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone)]
+struct Mode {
+    id: String,
+    width: i32,
+    height: i32,
+    rate: f64,
+    current: bool,
+    preferred: bool,
+}
+
+// A physical monitor, identified by its connector
+#[derive(Debug, Clone)]
+struct Monitor {
+    connector: String,
+    modes: Vec<Mode>,
+}
+
+impl Monitor {
+    fn current_mode(&self) -> Option<&Mode> {
+        self.modes.iter().find(|m| m.current)
+    }
+
+    fn preferred_mode(&self) -> Option<&Mode> {
+        self.modes
+            .iter()
+            .find(|m| m.preferred)
+            .or(self.modes.first())
+    }
+}
+
+// A region of the screen that shows one or more (mirrored) monitors
+#[derive(Debug, Clone)]
+struct LogicalMonitor {
+    x: i32,
+    y: i32,
+    scale: f64,
+    transform: u32,
+    primary: bool,
+    connectors: Vec<String>,
+}
+
+/// A snapshot of the full monitor configuration. Mutter does not allow
+/// changing single outputs: every change is made by altering this
+/// snapshot and sending the whole thing back.
+#[derive(Debug, Clone)]
+struct State {
+    serial: u32,
+    monitors: Vec<Monitor>,
+    logical: Vec<LogicalMonitor>,
+    physical_layout: bool,
+}
+
+impl From<RawState> for State {
+    fn from(raw: RawState) -> Self {
+        let (serial, raw_monitors, raw_logical, props) = raw;
+
+        let monitors = raw_monitors
+            .into_iter()
+            .map(|((connector, _, _, _), modes, _)| Monitor {
+                connector,
+                modes: modes
+                    .into_iter()
+                    .map(|(id, width, height, rate, _, _, props)| Mode {
+                        id,
+                        width,
+                        height,
+                        rate,
+                        current: prop_bool(&props, "is-current"),
+                        preferred: prop_bool(&props, "is-preferred"),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let logical = raw_logical
+            .into_iter()
+            .map(
+                |(x, y, scale, transform, primary, specs, _)| LogicalMonitor {
+                    x,
+                    y,
+                    scale,
+                    transform,
+                    primary,
+                    connectors: specs.into_iter().map(|s| s.0).collect(),
+                },
+            )
+            .collect();
+
+        let physical_layout = props
+            .get("layout-mode")
+            .and_then(|v| v.downcast_ref::<u32>())
+            .is_some_and(|m| *m == LAYOUT_PHYSICAL);
+
+        State {
+            serial,
+            monitors,
+            logical,
+            physical_layout,
+        }
+    }
+}
+
+impl State {
+    fn monitor(&self, connector: &str) -> Option<&Monitor> {
+        self.monitors.iter().find(|m| m.connector == connector)
+    }
+
+    fn logical_idx(&self, connector: &str) -> Option<usize> {
+        self.logical
+            .iter()
+            .position(|l| l.connectors.iter().any(|c| c == connector))
+    }
+
+    fn geometry(&self, connector: &str) -> Option<Geometry> {
+        let logical = &self.logical[self.logical_idx(connector)?];
+        let (width, height) = self.logical_size(logical);
+
+        Some(Geometry {
+            x: logical.x,
+            y: logical.y,
+            width: width as u32,
+            height: height as u32,
+            rotation: Rotation::from_quarter_turns(logical.transform),
+            scale: logical.scale,
+        })
+    }
+
+    // The size a logical monitor takes up in the layout
+    fn logical_size(&self, logical: &LogicalMonitor) -> (i32, i32) {
+        let mode = logical
+            .connectors
+            .first()
+            .and_then(|c| self.monitor(c))
+            .and_then(Monitor::current_mode);
+
+        let (w, h) = mode.map_or((0, 0), |m| (m.width, m.height));
+        // Odd transforms are rotated by 90 or 270 degrees
+        let (w, h) = if logical.transform % 2 == 1 {
+            (h, w)
+        } else {
+            (w, h)
+        };
+
+        if self.physical_layout {
+            (w, h)
+        } else {
+            let scale = |v: i32| (f64::from(v) / logical.scale).round() as i32;
+            (scale(w), scale(h))
+        }
+    }
+
+    // Makes the top left of the layout (0,0), like mutter expects
+    fn normalize(&mut self) {
+        let left = self.logical.iter().map(|l| l.x).min().unwrap_or(0);
+        let top = self.logical.iter().map(|l| l.y).min().unwrap_or(0);
+
+        for l in &mut self.logical {
+            l.x -= left;
+            l.y -= top;
+        }
+    }
+
+    // The right edge of the current layout, to append new outputs at
+    fn right_edge(&self) -> i32 {
+        self.logical
+            .iter()
+            .map(|l| l.x + self.logical_size(l).0)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // The monitors of the first logical monitor whose `modes` differ in
+    // size. Mirrored monitors need the same size.
+    fn mismatched_mirror(
+        &self,
+        modes: &HashMap<String, String>,
+    ) -> Option<&[String]> {
+        let size = |c: &String| {
+            let id = modes.get(c)?;
+            let mode = self.monitor(c)?.modes.iter().find(|m| &m.id == id)?;
+            Some((mode.width, mode.height))
+        };
+
+        self.logical
+            .iter()
+            .find(|l| l.connectors.iter().filter_map(size).unique().count() > 1)
+            .map(|l| l.connectors.as_slice())
+    }
+
+    // Mode ids for every monitor that is in some logical monitor
+    fn mode_ids(&self) -> HashMap<String, String> {
+        self.monitors
+            .iter()
+            .filter_map(|m| {
+                m.current_mode()
+                    .map(|mode| (m.connector.clone(), mode.id.clone()))
+            })
+            .collect()
+    }
+}
+
+pub struct Backend {
+    conn: Connection,
+    // MonitorsChanged signals, see `watch_changes`
+    signals: Option<SignalIterator<'static>>,
+}
+
+impl Backend {
+    pub fn new() -> Result<Self, BackendError> {
+        let conn = Connection::session()
+            .map_err(|e| backend_call_err!(GetOutputs, Mutter, e))?;
+
+        Ok(Self {
+            conn,
+            signals: None,
+        })
+    }
+
+    fn state(
+        &mut self,
+        err_f: fn(zbus::Error) -> BackendError,
+    ) -> Result<State, BackendError> {
+        let msg = self
+            .conn
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "GetCurrentState",
+                &(),
+            )
+            .map_err(err_f)?;
+
+        let raw: RawState = msg.body().map_err(err_f)?;
+        Ok(State::from(raw))
+    }
+
+    // Sets the transform that `make` picks, given the current one. Both come
+    // from the same state, so nothing changes in between.
+    fn transform_logical(
+        &mut self,
+        output_name: &str,
+        make: impl FnOnce(&Transform) -> Transform,
+    ) -> Result<(), BackendError> {
+        let mut state =
+            self.state(|e| backend_call_err!(SetRotation, Mutter, e))?;
+        let idx = state.logical_idx(output_name).ok_or(
+            super::err::SetRotation::NoOutput(output_name.to_string()),
+        )?;
+
+        // Same angles as sway: rotating left is 90 degrees counterclockwise
+        let current = Transform::from_wayland(state.logical[idx].transform);
+        state.logical[idx].transform = make(&current).wayland();
+
+        let modes = state.mode_ids();
+        let err_f = |e| backend_call_err!(SetRotation, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    // Sends the full configuration in `state` to mutter. `modes` holds the
+    // mode id to use for each connector that should be enabled.
+    fn apply(
+        &mut self,
+        mut state: State,
+        modes: &HashMap<String, String>,
+        err_f: fn(zbus::Error) -> BackendError,
+    ) -> Result<(), BackendError> {
+        state.normalize();
+
+        // Mutter would reject it without saying which monitors
+        if let Some(connectors) = state.mismatched_mirror(modes) {
+            let msg = format!(
+                "mirrored outputs {} need modes of the same size",
+                connectors.join(", ")
+            );
+            return Err(err_f(zbus::Error::Failure(msg)));
+        }
+
+        let logical: Vec<ApplyLogical> = state
+            .logical
+            .iter()
+            .map(|l| {
+                let monitors = l
+                    .connectors
+                    .iter()
+                    .filter_map(|c| {
+                        let mode_id = modes.get(c)?.clone();
+                        Some((c.clone(), mode_id, HashMap::new()))
+                    })
+                    .collect();
+                (l.x, l.y, l.scale, l.transform, l.primary, monitors)
+            })
+            .collect();
+
+        let props: HashMap<&str, Value> = HashMap::new();
+        self.conn
+            .call_method(
+                Some(DESTINATION),
+                PATH,
+                Some(INTERFACE),
+                "ApplyMonitorsConfig",
+                &(state.serial, METHOD_PERSISTENT, logical, props),
+            )
+            .map_err(err_f)?;
+
+        Ok(())
+    }
+}
+
+impl super::DisplayBackend for Backend {
+    fn capabilities(&mut self) -> Capabilities {
+        Capabilities {
+            backend: "mutter",
+            primary: true,
+            mirror: true,
+            flip: true,
+            ..Default::default()
+        }
+    }
+
+    fn supported_relations(&mut self) -> Vec<Relation> {
+        vec![
+            Relation::LeftOf,
+            Relation::RightOf,
+            Relation::Below,
+            Relation::Above,
+            Relation::SameAs,
+            Relation::CenteredOn,
+        ]
+    }
+
+    fn get_outputs(&mut self) -> Result<Vec<OutputEntry>, BackendError> {
+        let state = self.state(|e| backend_call_err!(GetOutputs, Mutter, e))?;
+
+        let entries = state
+            .monitors
+            .iter()
+            .map(|m| OutputEntry {
+                name: m.connector.clone(),
+                connected: true, // mutter only lists connected monitors
+                enabled: state.logical_idx(&m.connector).is_some(),
+                primary: state
+                    .logical_idx(&m.connector)
+                    .is_some_and(|i| state.logical[i].primary),
+                // Mutter only has the decoded vendor, product and serial
+                edid_hash: edid::from_sysfs(&m.connector)
+                    .map(|e| edid::hash(&e)),
+                description: None,
+                provider: None,
+                adaptive_sync: None,
+                geometry: state.geometry(&m.connector),
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn get_geometry(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Geometry, BackendError> {
+        let state =
+            self.state(|e| backend_call_err!(GetGeometry, Mutter, e))?;
+
+        if state.monitor(output_name).is_none() {
+            Err(super::err::GetGeometry::NoOutput(output_name.to_string()))?;
+        }
+        let geometry = state.geometry(output_name).ok_or(
+            super::err::GetGeometry::Disabled(output_name.to_string()),
+        )?;
+        Ok(geometry)
+    }
+
+    fn get_resolutions(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Vec<ResolutionEntry>, BackendError> {
+        let state =
+            self.state(|e| backend_call_err!(GetResolutions, Mutter, e))?;
+        let monitor = state.monitor(output_name).ok_or(
+            super::err::GetResolutions::NoOutput(output_name.to_string()),
+        )?;
+
+        let current = monitor
+            .current_mode()
+            .ok_or(super::err::GetResolutions::GetCurrent)?;
+
+        let preferred = monitor.modes.iter().find(|m| m.preferred);
+
+        let entries = monitor
+            .modes
+            .iter()
+            .map(|m| ResolutionEntry {
+                val: Resolution {
+                    width: m.width as u32,
+                    height: m.height as u32,
+                },
+                current: m.width == current.width && m.height == current.height,
+                preferred: preferred.is_some_and(|p| {
+                    m.width == p.width && m.height == p.height
+                }),
+                interlaced: false,
+            })
+            .collect::<Vec<ResolutionEntry>>();
+
+        Ok(ResolutionEntry::merge(entries))
+    }
+
+    fn set_resolution(
+        &mut self,
+        output_name: &str,
+        res: &Resolution,
+    ) -> Result<(), BackendError> {
+        let state =
+            self.state(|e| backend_call_err!(SetResolution, Mutter, e))?;
+        let monitor = state.monitor(output_name).ok_or(
+            super::err::SetResolution::NoOutput(output_name.to_string()),
+        )?;
+
+        // Mutter lists the modes of a resolution by descending rate
+        let target_mode = monitor
+            .modes
+            .iter()
+            .find(|m| {
+                m.width as u32 == res.width && m.height as u32 == res.height
+            })
+            .ok_or(super::err::SetResolution::NoMode(res.clone()))?;
+
+        let mut modes = state.mode_ids();
+        modes.insert(output_name.to_string(), target_mode.id.clone());
+
+        let err_f = |e| backend_call_err!(SetResolution, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    fn get_rates(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Vec<RateEntry>, BackendError> {
+        let state = self.state(|e| backend_call_err!(GetRates, Mutter, e))?;
+        let monitor = state
+            .monitor(output_name)
+            .ok_or(super::err::GetRates::NoOutput(output_name.to_string()))?;
+
+        let current = monitor
+            .current_mode()
+            .ok_or(super::err::GetRates::GetCurrent)?;
+
+        let entries = monitor
+            .modes
+            .iter()
+            .filter(|m| m.width == current.width && m.height == current.height)
+            .map(|m| RateEntry {
+                val: m.rate,
+                current: m.id == current.id,
+            })
+            .collect::<Vec<RateEntry>>();
+
+        Ok(RateEntry::merge(entries))
+    }
+
+    fn set_rate(
+        &mut self,
+        output_name: &str,
+        rate: Rate,
+    ) -> Result<(), BackendError> {
+        let state = self.state(|e| backend_call_err!(SetRate, Mutter, e))?;
+        let monitor = state
+            .monitor(output_name)
+            .ok_or(super::err::SetRate::NoOutput(output_name.to_string()))?;
+
+        let current = monitor
+            .current_mode()
+            .ok_or(super::err::SetRate::NoMode(output_name.to_string()))?;
+
+        let target_mode = monitor
+            .modes
+            .iter()
+            .filter(|m| {
+                m.width == current.width
+                    && m.height == current.height
+                    && (m.rate - rate).abs() < RATE_EPSILON
+            })
+            // Rates can be closer together than the epsilon
+            .min_by(|a, b| {
+                f64::total_cmp(&(a.rate - rate).abs(), &(b.rate - rate).abs())
+            })
+            .ok_or(super::err::SetRate::NoRate(rate))?;
+
+        let mut modes = state.mode_ids();
+        modes.insert(output_name.to_string(), target_mode.id.clone());
+
+        let err_f = |e| backend_call_err!(SetRate, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    fn set_rotation(
+        &mut self,
+        output_name: &str,
+        rotation: &Rotation,
+    ) -> Result<(), BackendError> {
+        // A flipped output stays flipped
+        self.transform_logical(output_name, |current| Transform {
+            rotation: rotation.clone(),
+            flipped: current.flipped,
+        })
+    }
+
+    fn get_transform(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Transform, BackendError> {
+        let state =
+            self.state(|e| backend_call_err!(GetGeometry, Mutter, e))?;
+
+        if state.monitor(output_name).is_none() {
+            Err(super::err::GetGeometry::NoOutput(output_name.to_string()))?;
+        }
+        let idx = state.logical_idx(output_name).ok_or(
+            super::err::GetGeometry::Disabled(output_name.to_string()),
+        )?;
+        Ok(Transform::from_wayland(state.logical[idx].transform))
+    }
+
+    fn set_transform(
+        &mut self,
+        output_name: &str,
+        t: &Transform,
+    ) -> Result<(), BackendError> {
+        self.transform_logical(output_name, |_| t.clone())
+    }
+
+    fn set_position(
+        &mut self,
+        output_name: &str,
+        pos: &Position,
+    ) -> Result<(), BackendError> {
+        let Position {
+            output_s: rel_output,
+            relation,
+        } = pos;
+
+        let mut state =
+            self.state(|e| backend_call_err!(SetPosition, Mutter, e))?;
+
+        let idx = state.logical_idx(output_name).ok_or(
+            super::err::SetPosition::NoOutput(output_name.to_string()),
+        )?;
+        let rel_idx = state
+            .logical_idx(rel_output)
+            .ok_or(super::err::SetPosition::NoOutput(rel_output.to_string()))?;
+
+        if idx == rel_idx {
+            return Ok(()); // Already mirroring, nothing to position
+        }
+
+        // Take the output out of its current logical monitor, because it
+        // might be mirroring some other output at the moment.
+        let mut moved = state.logical[idx].clone();
+        state.logical[idx].connectors.retain(|c| c != output_name);
+        moved.connectors = vec![output_name.to_string()];
+
+        let rel = state.logical[rel_idx].clone();
+        let (w, h) = state.logical_size(&moved);
+        let (rel_w, rel_h) = state.logical_size(&rel);
+
+        match relation {
+            // Mirrored monitors share a single logical monitor
+            Relation::SameAs => {
+                state.logical[rel_idx]
+                    .connectors
+                    .push(output_name.to_string());
+            }
+            _ => {
+                (moved.x, moved.y) = match relation {
+                    Relation::LeftOf => (rel.x - w, rel.y),
+                    Relation::RightOf => (rel.x + rel_w, rel.y),
+                    Relation::Above => (rel.x, rel.y - h),
+                    Relation::Below => (rel.x, rel.y + rel_h),
+                    Relation::CenteredOn => {
+                        (rel.x + (rel_w - w) / 2, rel.y + (rel_h - h) / 2)
+                    }
+                    Relation::SameAs => unreachable!("Handled above"),
+                };
+                state.logical.push(moved);
+            }
+        }
+
+        state.logical.retain(|l| !l.connectors.is_empty());
+
+        let modes = state.mode_ids();
+        let err_f = |e| backend_call_err!(SetPosition, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    // Mirrored outputs share a logical monitor, so they move along
+    fn set_coordinates(
+        &mut self,
+        output_name: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), BackendError> {
+        let mut state =
+            self.state(|e| backend_call_err!(SetCoordinates, Mutter, e))?;
+        let idx = state.logical_idx(output_name).ok_or(
+            super::err::SetCoordinates::NoOutput(output_name.to_string()),
+        )?;
+
+        (state.logical[idx].x, state.logical[idx].y) = (x, y);
+
+        let modes = state.mode_ids();
+        let err_f = |e| backend_call_err!(SetCoordinates, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    fn set_primary(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let mut state =
+            self.state(|e| backend_call_err!(SetPrimary, Mutter, e))?;
+        let idx = state
+            .logical_idx(output_name)
+            .ok_or(super::err::SetPrimary::NoOutput(output_name.to_string()))?;
+
+        for (i, l) in state.logical.iter_mut().enumerate() {
+            l.primary = i == idx;
+        }
+
+        let modes = state.mode_ids();
+        let err_f = |e| backend_call_err!(SetPrimary, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    // Color depth is an X concept
+    fn set_color_depth(
+        &mut self,
+        _output_name: &str,
+        _depth: Depth,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_hdr(
+        &mut self,
+        _output_name: &str,
+        _on: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_tearing(
+        &mut self,
+        _output_name: &str,
+        _allow: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale_filter(
+        &mut self,
+        _output_name: &str,
+        _filter: &Filter,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale(
+        &mut self,
+        output_name: &str,
+        scale: f64,
+    ) -> Result<(), BackendError> {
+        let mut state =
+            self.state(|e| backend_call_err!(SetScale, Mutter, e))?;
+        let idx = state
+            .logical_idx(output_name)
+            .ok_or(super::err::SetScale::NoOutput(output_name.to_string()))?;
+
+        state.logical[idx].scale = scale;
+
+        let modes = state.mode_ids();
+        let err_f = |e| backend_call_err!(SetScale, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    fn set_subpixel(
+        &mut self,
+        _output_name: &str,
+        _subpixel: &Subpixel,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_power(
+        &mut self,
+        _output_name: &str,
+        _power: &PowerState,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_gamma(
+        &mut self,
+        _output_name: &str,
+        _gamma: &Gamma,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_brightness(
+        &mut self,
+        _output_name: &str,
+        _brightness: f64,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn get_workspaces(&mut self) -> Result<Vec<WorkspaceEntry>, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_workspace(
+        &mut self,
+        _output_name: &str,
+        _workspace: &str,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let mut state = self.state(|e| backend_call_err!(Enable, Mutter, e))?;
+        if state.logical_idx(output_name).is_some() {
+            return Ok(()); // Already enabled
+        }
+
+        let monitor = state
+            .monitor(output_name)
+            .ok_or(super::err::Enable::NoOutput(output_name.to_string()))?;
+        let mode = monitor
+            .preferred_mode()
+            .ok_or(super::err::Enable::NoOutput(output_name.to_string()))?
+            .id
+            .clone();
+
+        let mut modes = state.mode_ids();
+        modes.insert(output_name.to_string(), mode);
+
+        // Newly enabled outputs are appended to the right of the layout
+        state.logical.push(LogicalMonitor {
+            x: state.right_edge(),
+            y: 0,
+            scale: 1.0,
+            transform: 0,
+            primary: state.logical.is_empty(),
+            connectors: vec![output_name.to_string()],
+        });
+
+        let err_f = |e| backend_call_err!(Enable, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    fn disable(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let mut state =
+            self.state(|e| backend_call_err!(Disable, Mutter, e))?;
+        if state.monitor(output_name).is_none() {
+            Err(super::err::Disable::NoOutput(output_name.to_string()))?;
+        }
+
+        // Outputs that are left out of the configuration are disabled
+        for l in &mut state.logical {
+            l.connectors.retain(|c| c != output_name);
+        }
+        state.logical.retain(|l| !l.connectors.is_empty());
+
+        // There always needs to be a primary monitor
+        if !state.logical.iter().any(|l| l.primary) {
+            if let Some(l) = state.logical.first_mut() {
+                l.primary = true;
+            }
+        }
+
+        let modes = state.mode_ids();
+        let err_f = |e| backend_call_err!(Disable, Mutter, e);
+        self.apply(state, &modes, err_f)
+    }
+
+    // Mutter signals every change to the monitor configuration,
+    // including hotplugs
+    fn watch_changes(&mut self) -> Result<(), BackendError> {
+        let err_f = |e| backend_call_err!(WaitForChange, Mutter, e);
+
+        let proxy = Proxy::new(&self.conn, DESTINATION, PATH, INTERFACE)
+            .map_err(err_f)?;
+        let signals = proxy.receive_signal("MonitorsChanged").map_err(err_f)?;
+        self.signals = Some(signals);
+        Ok(())
+    }
+
+    fn wait_for_change(&mut self) -> Result<(), BackendError> {
+        let signals = match &mut self.signals {
+            Some(signals) => signals,
+            None => {
+                self.watch_changes()?;
+                self.signals.as_mut().expect("Set by watch_changes")
+            }
+        };
+
+        signals.next();
+        Ok(())
+    }
+
+    // The pointer is not part of the display configuration API
+    fn warp_pointer(&mut self, _output_name: &str) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(connector: &str, sizes: &[(i32, i32)]) -> Monitor {
+        Monitor {
+            connector: connector.to_string(),
+            modes: sizes
+                .iter()
+                .map(|&(width, height)| Mode {
+                    id: format!("{width}x{height}"),
+                    width,
+                    height,
+                    rate: 60.0,
+                    current: false,
+                    preferred: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn mirrored(connectors: &[&str]) -> State {
+        let sizes = [(1920, 1080), (1280, 720)];
+        State {
+            serial: 0,
+            monitors: connectors.iter().map(|c| monitor(c, &sizes)).collect(),
+            logical: vec![LogicalMonitor {
+                x: 0,
+                y: 0,
+                scale: 1.0,
+                transform: 0,
+                primary: true,
+                connectors: connectors.iter().map(|c| c.to_string()).collect(),
+            }],
+            physical_layout: false,
+        }
+    }
+
+    fn modes(ids: &[(&str, &str)]) -> HashMap<String, String> {
+        ids.iter()
+            .map(|(c, id)| (c.to_string(), id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn mirror_with_same_size() {
+        let state = mirrored(&["eDP-1", "HDMI-1"]);
+        let modes = modes(&[("eDP-1", "1920x1080"), ("HDMI-1", "1920x1080")]);
+        assert_eq!(state.mismatched_mirror(&modes), None);
+    }
+
+    #[test]
+    fn mirror_with_different_sizes() {
+        let state = mirrored(&["eDP-1", "HDMI-1"]);
+        let modes = modes(&[("eDP-1", "1920x1080"), ("HDMI-1", "1280x720")]);
+        assert_eq!(
+            state.mismatched_mirror(&modes),
+            Some(["eDP-1".to_string(), "HDMI-1".to_string()].as_slice())
+        );
+    }
+}
@@ -0,0 +1,269 @@
+// X screens have a maximum size. When a layout change would grow the screen
+// beyond it, xrandr just fails with "cannot set CRTC for output", so the X
+// backends check the resulting layout against it beforehand.
+use crate::action::panning::Panning;
+use crate::action::position::{Position, Relation};
+use crate::action::resolution::Resolution;
+use crate::action::rotate::Rotation;
+
+use super::{Error, Geometry, OutputEntry};
+
+pub enum LayoutChange<'a> {
+    Resize(&'a Resolution),
+    Place(&'a Position),
+    Rotate(&'a Rotation),
+    // Panning over an area of the screen, which the output then covers
+    Pan(&'a Panning),
+    // Covering this much of the screen from where it is, as a transform does
+    Cover(u32, u32),
+}
+
+fn is_sideways(rotation: &Rotation) -> bool {
+    matches!(rotation, Rotation::Left | Rotation::Right)
+}
+
+impl Geometry {
+    // This geometry with its mode changed to `res`
+    fn resized(&self, res: &Resolution) -> Self {
+        let (width, height) = match is_sideways(&self.rotation) {
+            true => (res.height, res.width),
+            false => (res.width, res.height),
+        };
+
+        Self {
+            width,
+            height,
+            ..self.clone()
+        }
+    }
+
+    // This geometry turned to `rotation`. xrandr keeps the top left corner
+    // where it is, so only the size changes.
+    fn rotated(&self, rotation: &Rotation) -> Self {
+        let (width, height) =
+            match is_sideways(&self.rotation) == is_sideways(rotation) {
+                true => (self.width, self.height),
+                false => (self.height, self.width),
+            };
+
+        Self {
+            width,
+            height,
+            rotation: rotation.clone(),
+            ..self.clone()
+        }
+    }
+
+    // This geometry moved to `relation` of `other`, like xrandr does it.
+    // xrandr has no centering, the X backends place those by coordinates.
+    pub fn placed(&self, relation: &Relation, other: &Geometry) -> Self {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let (x, y) = match relation {
+            Relation::LeftOf => (other.x - w, other.y),
+            Relation::RightOf => (other.x + other.width as i32, other.y),
+            Relation::Above => (other.x, other.y - h),
+            Relation::Below => (other.x, other.y + other.height as i32),
+            Relation::SameAs => (other.x, other.y),
+            Relation::CenteredOn => (
+                other.x + (other.width as i32 - w) / 2,
+                other.y + (other.height as i32 - h) / 2,
+            ),
+        };
+
+        Self {
+            x,
+            y,
+            ..self.clone()
+        }
+    }
+
+    // Whether the two share any pixels. Touching edges do not count.
+    pub fn overlaps(&self, other: &Geometry) -> bool {
+        let right = |g: &Geometry| g.x + g.width as i32;
+        let bottom = |g: &Geometry| g.y + g.height as i32;
+
+        self.x < right(other)
+            && other.x < right(self)
+            && self.y < bottom(other)
+            && other.y < bottom(self)
+    }
+}
+
+// The size of the smallest box that contains all of `geometries`
+pub fn bounding_box<'a>(
+    geometries: impl IntoIterator<Item = &'a Geometry>,
+) -> (u32, u32) {
+    let mut geometries = geometries.into_iter().peekable();
+    if geometries.peek().is_none() {
+        return (0, 0);
+    }
+
+    let (mut left, mut top) = (i32::MAX, i32::MAX);
+    let (mut right, mut bottom) = (i32::MIN, i32::MIN);
+    for g in geometries {
+        left = left.min(g.x);
+        top = top.min(g.y);
+        right = right.max(g.x + g.width as i32);
+        bottom = bottom.max(g.y + g.height as i32);
+    }
+
+    ((right - left) as u32, (bottom - top) as u32)
+}
+
+// Checks whether the layout still fits in a screen of at most `max` after
+// applying `change` to the output called `name`. Outputs that are not on
+// the screen (yet) are not checked: enabling them is left to xrandr.
+pub fn check(
+    outputs: &[OutputEntry],
+    name: &str,
+    change: LayoutChange,
+    max: (u32, u32),
+) -> Result<(), Error> {
+    let too_large = |width, height| Error::ScreenSize {
+        width,
+        height,
+        max_width: max.0,
+        max_height: max.1,
+    };
+    let geometry_of = |name: &str| {
+        outputs
+            .iter()
+            .find(|o| o.name == name)
+            .and_then(|o| o.geometry.as_ref())
+    };
+
+    let Some(current) = geometry_of(name) else {
+        return Ok(());
+    };
+
+    let new = match change {
+        LayoutChange::Cover(width, height) => Geometry {
+            width,
+            height,
+            ..current.clone()
+        },
+        LayoutChange::Pan(pan) => match pan.end() {
+            _ if pan.is_off() => return Ok(()),
+            Some(_) => Geometry {
+                x: pan.x as i32,
+                y: pan.y as i32,
+                width: pan.width,
+                height: pan.height,
+                ..current.clone()
+            },
+            None => {
+                let width = pan.x.saturating_add(pan.width);
+                let height = pan.y.saturating_add(pan.height);
+                return Err(too_large(width, height));
+            }
+        },
+        LayoutChange::Resize(res) => current.resized(res),
+        LayoutChange::Rotate(rotation) => current.rotated(rotation),
+        LayoutChange::Place(pos) => match geometry_of(&pos.output_s) {
+            Some(other) => current.placed(&pos.relation, other),
+            None => return Ok(()),
+        },
+    };
+
+    let others = outputs
+        .iter()
+        .filter(|o| o.name != name)
+        .filter_map(|o| o.geometry.as_ref());
+    let (width, height) = bounding_box(others.chain([&new]));
+
+    if width > max.0 || height > max.1 {
+        return Err(too_large(width, height));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> Geometry {
+        Geometry {
+            x,
+            y,
+            width,
+            height,
+            rotation: Rotation::Normal,
+            scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn bounding_box_of_nothing_is_empty() {
+        assert_eq!(bounding_box([]), (0, 0));
+    }
+
+    #[test]
+    fn bounding_box_of_side_by_side() {
+        let left = geometry(0, 0, 1920, 1080);
+        let right = geometry(1920, 0, 2560, 1440);
+        assert_eq!(bounding_box([&left, &right]), (4480, 1440));
+    }
+
+    #[test]
+    fn bounding_box_with_negative_coordinates() {
+        let above = geometry(-100, -1080, 1920, 1080);
+        let below = geometry(0, 0, 1920, 1080);
+        assert_eq!(bounding_box([&above, &below]), (2020, 2160));
+    }
+
+    #[test]
+    fn bounding_box_of_overlapping() {
+        let big = geometry(0, 0, 3840, 2160);
+        let inside = geometry(960, 540, 1920, 1080);
+        assert_eq!(bounding_box([&big, &inside]), (3840, 2160));
+    }
+
+    #[test]
+    fn placing_beyond_the_maximum_is_rejected() {
+        let outputs = [
+            OutputEntry::test("DP-1", Some(geometry(0, 0, 3840, 2160))),
+            OutputEntry::test("DP-2", Some(geometry(3840, 0, 3840, 2160))),
+        ];
+        let pos = Position {
+            relation: Relation::Below,
+            output_s: "DP-1".to_string(),
+        };
+
+        let change = LayoutChange::Place(&pos);
+        match check(&outputs, "DP-2", change, (4096, 4096)) {
+            Err(Error::ScreenSize { width, height, .. }) => {
+                assert_eq!((width, height), (3840, 4320));
+            }
+            res => panic!("expected ScreenSize, got {res:?}"),
+        }
+
+        let change = LayoutChange::Place(&pos);
+        assert!(check(&outputs, "DP-2", change, (8192, 8192)).is_ok());
+    }
+
+    #[test]
+    fn outputs_off_the_screen_are_not_checked() {
+        let off = OutputEntry::test("DP-1", None);
+
+        let res = Resolution {
+            width: 7680,
+            height: 4320,
+        };
+        let change = LayoutChange::Resize(&res);
+        assert!(check(&[off], "DP-1", change, (4096, 4096)).is_ok());
+    }
+
+    #[test]
+    fn rotating_swaps_the_size() {
+        let outputs = [
+            OutputEntry::test("DP-1", Some(geometry(0, 0, 1920, 1080))),
+            OutputEntry::test("DP-2", Some(geometry(1920, 0, 1920, 1080))),
+        ];
+
+        let change = LayoutChange::Rotate(&Rotation::Left);
+        assert!(check(&outputs, "DP-2", change, (3840, 1080)).is_err());
+        let change = LayoutChange::Rotate(&Rotation::Inverted);
+        assert!(check(&outputs, "DP-2", change, (3840, 1080)).is_ok());
+    }
+}
@@ -1,55 +1,411 @@
+use crate::action::depth::Depth;
+use crate::action::gamma::Gamma;
+use crate::action::position::Position;
 use crate::action::position::Relation;
+use crate::action::power::PowerState;
 use crate::action::rate::Rate;
 use crate::action::resolution::Resolution;
-use crate::action::rotate::Rotation;
-use crate::action::{position::Position, Operation};
+use crate::action::rotate::{Rotation, Transform};
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
 use crate::backend::Error as BackendError;
 use crate::backend_call as backend_call_err;
-use swayipc::Connection;
+use std::env;
+use std::time::Duration;
+use swayipc::{Connection, EnabledOrDisabled, EventStream, EventType};
 
-use super::{OutputEntry, RateEntry, ResolutionEntry};
+use super::edid;
+use super::{
+    find_output, unique_names, Capabilities, Geometry, Named, OutputEntry,
+    OutputLayout, RateEntry, ResolutionEntry, SeatEntry, WorkspaceEntry,
+};
 
 pub struct Backend {
     conn: Connection,
+    // Refer to outputs by their description rather than their connector
+    by_description: bool,
+    // How often to retry a command that failed to reach sway
+    retries: u32,
+    // Output events, see `watch_changes`
+    events: Option<EventStream>,
+    // Filled in by the first call to `capabilities`
+    capabilities: Option<Capabilities>,
 }
 
 impl Backend {
-    pub fn new() -> Result<Self, BackendError> {
+    // `retries` is how often to resend a command that did not reach sway
+    pub fn new(retries: u32) -> Result<Self, BackendError> {
         let conn =
             swayipc::Connection::new().map_err(|_| BackendError::GetBackend)?;
+        let by_description =
+            env::var_os("SWAY_OUTPUT_BY_DESCRIPTION").is_some();
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            by_description,
+            retries,
+            events: None,
+            capabilities: None,
+        })
+    }
+
+    // Helper function to deal with unwrapping the various layers of errors
+    // that result from swayipc's run_command() function. Maps all the errors
+    // we find to some backend error using the `err_f` function.
+    fn run_cmd(
+        &mut self,
+        cmd: String,
+        err_f: fn(swayipc::Error) -> BackendError,
+    ) -> Result<(), BackendError> {
+        let failed = self.failed_cmds(&cmd).map_err(err_f)?;
+        failures_to_err(failed, err_f)
+    }
+
+    // Runs the commands one at a time, because sway stops at the first
+    // failing command of a `;` separated list. One output that cannot be
+    // changed then does not keep the others from being changed.
+    fn run_cmds(
+        &mut self,
+        cmds: &[String],
+        err_f: fn(swayipc::Error) -> BackendError,
+    ) -> Result<(), BackendError> {
+        let mut failed = Vec::new();
+        for cmd in cmds {
+            failed.extend(self.failed_cmds(cmd).map_err(err_f)?);
+        }
+        failures_to_err(failed, err_f)
+    }
+
+    // The commands in `cmd` that sway rejected, with the reason. Errs if
+    // sway could not be reached at all, after retrying.
+    fn failed_cmds(
+        &mut self,
+        cmd: &str,
+    ) -> Result<Vec<String>, swayipc::Error> {
+        let mut attempt = 0;
+
+        // This first result seems to be whether we could even interface
+        // with sway to execute the command
+        let res = loop {
+            match self.conn.run_command(cmd) {
+                Err(swayipc::Error::Io(e)) if attempt < self.retries => {
+                    attempt += 1;
+                    log::debug!(
+                        "sway IPC failed ({e}), retry {attempt}/{}",
+                        self.retries
+                    );
+                    std::thread::sleep(RETRY_DELAY * attempt);
+
+                    // The old connection may be broken after this
+                    if let Ok(conn) = Connection::new() {
+                        self.conn = conn;
+                    }
+                }
+                res => break res?,
+            }
+        };
+
+        Ok(failures(cmd, res))
+    }
+
+    // Sets the transform that `make` picks, given the output as it is now
+    fn transform_output(
+        &mut self,
+        output_name: &str,
+        make: impl FnOnce(&swayipc::Output) -> Transform,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetRotation, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetRotation::NoOutput(output_name.to_string()),
+        )?;
+
+        let angle_str = transform(&make(output));
+
+        let err_f = |e| backend_call_err!(SetRotation, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} transform {angle_str}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    // Older versions of sway reject commands they do not know about
+    fn version_at_least(&mut self, major: i32, minor: i32) -> bool {
+        self.conn
+            .get_version()
+            .is_ok_and(|v| (v.major, v.minor) >= (major, minor))
+    }
+
+    // How to refer to `output` in a command. Connector names like `DP-3` can
+    // shuffle between reboots and docks, but sway also accepts the stable
+    // "make model serial" description. That is used when asked for, or when
+    // the connector name is ambiguous, as long as the description is unique.
+    fn output_ident(
+        &self,
+        output: &swayipc::Output,
+        outputs: &[swayipc::Output],
+    ) -> String {
+        ident(output, outputs, self.by_description)
     }
 }
 
-// swayipc rates are frames per 1000 seconds with roughly 4 significant digits.
-// Any two rates with less than `RATE_EPSILON` difference are considered to be
-// equivalent
-const RATE_EPSILON: f64 = 0.01;
+fn ident(
+    output: &swayipc::Output,
+    outputs: &[swayipc::Output],
+    by_description: bool,
+) -> String {
+    let desc = description(output);
+    let ambiguous =
+        outputs.iter().filter(|o| o.name == output.name).count() > 1;
+    let unique_desc =
+        outputs.iter().filter(|o| description(o) == desc).count() == 1;
+
+    if (by_description || ambiguous) && unique_desc {
+        format!("\"{desc}\"")
+    } else {
+        output.name.clone()
+    }
+}
 
-// Helper function to deal with unwrapping the various layers of errors
-// that result from swayipc's run_command() function. Maps all the errors we
-// find to some backend error using the `err_f` function.
-fn run_sway_cmd(
-    conn: &mut swayipc::Connection,
-    cmd: String,
+// Sway answers a `;` separated list with a result for each command, in the
+// order they are in `cmd`. Names the failing ones, which include the output.
+fn failures(
+    cmd: &str,
+    results: Vec<Result<(), swayipc::Error>>,
+) -> Vec<String> {
+    cmd.split(';')
+        .zip(results)
+        .filter_map(|(cmd, res)| match res {
+            Err(swayipc::Error::CommandFailed(msg)) => {
+                Some(format!("'{}': {msg}", cmd.trim()))
+            }
+            Err(e) => Some(format!("'{}': {e}", cmd.trim())),
+            Ok(()) => None,
+        })
+        .collect()
+}
+
+// One error for all the commands that sway rejected, if any
+fn failures_to_err(
+    failed: Vec<String>,
     err_f: fn(swayipc::Error) -> BackendError,
 ) -> Result<(), BackendError> {
-    let res = conn.run_command(cmd);
+    match failed.is_empty() {
+        true => Ok(()),
+        false => {
+            let msg = failed.join(", ");
+            Err(err_f(swayipc::Error::CommandFailed(msg)))
+        }
+    }
+}
+
+impl Named for swayipc::Output {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
 
-    // This first result seems to be whether we could even interface
-    // with sway to execute the command
-    let res = res.map_err(err_f)?;
+// Assigns `workspace` to the output with `ident`. An existing workspace is
+// also moved there, which needs it focused, so the one that was focused
+// before is focused again afterwards.
+fn workspace_cmd(
+    workspace: &str,
+    ident: &str,
+    exists: bool,
+    focused: Option<&str>,
+) -> String {
+    let quote = |ws: &str| format!("\"{}\"", ws.replace('"', "\\\""));
+    let ws = quote(workspace);
 
-    // This is a vector of results for each command. For now just fail if any of
-    // them fail. Probably should log what fails
-    let res: Result<Vec<_>, _> = res.into_iter().collect();
-    res.map(|_| ()).map_err(err_f)
+    let mut cmd = format!("workspace {ws} output {ident}");
+    if exists {
+        cmd.push_str(&format!(
+            "; workspace --no-auto-back-and-forth {ws}; \
+            move workspace to output {ident}"
+        ));
+        if let Some(focused) = focused.filter(|f| *f != workspace) {
+            cmd.push_str(&format!(
+                "; workspace --no-auto-back-and-forth {}",
+                quote(focused)
+            ));
+        }
+    }
+    cmd
 }
 
+fn description(output: &swayipc::Output) -> String {
+    format!("{} {} {}", output.make, output.model, output.serial)
+}
+
+// Sway transforms are counterclockwise angles, optionally prefixed by
+// "flipped-". The rect is in layout coordinates, so already scaled.
+fn geometry(output: &swayipc::Output) -> Option<Geometry> {
+    output.current_mode?;
+
+    // "normal" does not parse, and is 0 degrees
+    let angle = output.transform.as_deref().map_or(0, |t| {
+        t.trim_start_matches("flipped-").parse::<u32>().unwrap_or(0)
+    });
+
+    Some(Geometry {
+        x: output.rect.x,
+        y: output.rect.y,
+        width: output.rect.width as u32,
+        height: output.rect.height as u32,
+        rotation: Rotation::from_quarter_turns(angle / 90),
+        scale: output.scale.unwrap_or(1.0),
+    })
+}
+
+// Sway takes rotation and reflection as a single transform, so both are
+// needed to change either one
+// The devices that `input <identifier> map_to_output` applies to
+fn maps_to_output(input: &swayipc::Input) -> bool {
+    matches!(
+        input.input_type.as_str(),
+        "pointer" | "touch" | "tablet_tool"
+    )
+}
+
+fn transform(t: &Transform) -> String {
+    let angle = match t.rotation {
+        Rotation::Normal => "0",
+        Rotation::Left => "90",
+        Rotation::Inverted => "180",
+        Rotation::Right => "270",
+    };
+
+    match (t.flipped, &t.rotation) {
+        (false, _) => angle.to_string(),
+        (true, Rotation::Normal) => String::from("flipped"),
+        (true, _) => format!("flipped-{angle}"),
+    }
+}
+
+fn is_flipped(output: &swayipc::Output) -> bool {
+    output
+        .transform
+        .as_deref()
+        .is_some_and(|t| t.starts_with("flipped"))
+}
+
+// Like `2560x1440@59.951Hz`. Without a rate, sway picks the highest.
+// What `layout_command` prints for `layout`. The layout has no reflection,
+// so `flipped` tells it for each output name.
+fn swaymsg_command(
+    layout: &[OutputLayout],
+    flipped: impl Fn(&str) -> bool,
+) -> String {
+    let commands: Vec<String> = layout
+        .iter()
+        .map(|o| {
+            if !o.enabled {
+                return format!("output {} disable", o.name);
+            }
+
+            let mode = match &o.resolution {
+                Some(res) => format!(
+                    " mode {}",
+                    mode_str(res.width as i32, res.height as i32, o.rate)
+                ),
+                None => String::new(),
+            };
+            format!(
+                "output {}{mode} pos {} {} transform {} scale {}",
+                o.name,
+                o.x,
+                o.y,
+                transform(&Transform {
+                    rotation: o.rotation.clone(),
+                    flipped: flipped(&o.name),
+                }),
+                o.scale
+            )
+        })
+        .collect();
+
+    format!("swaymsg '{}'", commands.join("; "))
+}
+
+fn mode_str(width: i32, height: i32, rate: Option<f64>) -> String {
+    match rate {
+        Some(rate) => format!("{width}x{height}@{rate}Hz"),
+        None => format!("{width}x{height}"),
+    }
+}
+
+fn sway_mode_str(mode: &swayipc::Mode) -> String {
+    mode_str(mode.width, mode.height, Some(hz(mode.refresh)))
+}
+
+// Sway reports rates in mHz
+fn hz(refresh: i32) -> Rate {
+    f64::from(refresh) / 1000.0
+}
+
+// The advertised mode at the resolution of `current` with the rate closest
+// to `rate`, if there is one within `RATE_EPSILON`
+fn mode_at_rate<'a>(
+    output: &'a swayipc::Output,
+    current: &swayipc::Mode,
+    rate: Rate,
+) -> Option<&'a swayipc::Mode> {
+    let rate_diff = |m: &swayipc::Mode| (hz(m.refresh) - rate).abs();
+
+    output
+        .modes
+        .iter()
+        .filter(|m| {
+            m.width == current.width
+                && m.height == current.height
+                && rate_diff(m) < RATE_EPSILON
+        })
+        // Rates can be closer together than the epsilon
+        .min_by(|a, b| f64::total_cmp(&rate_diff(a), &rate_diff(b)))
+}
+
+// Headless outputs advertise no modes, but do have a current one
+fn advertised_modes(output: &swayipc::Output) -> Vec<swayipc::Mode> {
+    match (&output.modes[..], output.current_mode) {
+        ([], Some(current)) => vec![current],
+        _ => output.modes.clone(),
+    }
+}
+
+// The mode to set for resolution `res`. Outputs that advertise no modes,
+// like headless ones, take any as a custom mode.
+fn resolution_mode_str(
+    output: &swayipc::Output,
+    res: &Resolution,
+) -> Result<String, super::err::SetResolution> {
+    if output.modes.is_empty() {
+        let mode_str = mode_str(res.width as i32, res.height as i32, None);
+        return Ok(format!("--custom {mode_str}"));
+    }
+
+    let target_mode = output
+        .modes
+        .iter()
+        .find(|m| m.width as u32 == res.width && m.height as u32 == res.height)
+        .ok_or(super::err::SetResolution::NoMode(res.clone()))?;
+    Ok(sway_mode_str(target_mode))
+}
+
+// swayipc rates are frames per 1000 seconds with roughly 4 significant digits.
+// Any two rates with less than `RATE_EPSILON` difference are considered to be
+// equivalent
+const RATE_EPSILON: f64 = 0.01;
+
+// Right after a hotplug, the socket sometimes fails to answer. Commands
+// that did reach sway and were rejected are not retried.
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
 // Normalizes all output's positions such that the top left is at (0,0)
 fn normalize_all_outputs(outputs: &[&swayipc::Output]) -> Vec<swayipc::Output> {
-    let (left, top): (i32, i32) = outputs.iter()
+    let (left, top): (i32, i32) = outputs
+        .iter()
         .map(|o| (o.rect.x, o.rect.y))
         .reduce(|(x1, y1), (x2, y2)| (i32::min(x1, x2), i32::min(y1, y2)))
         .expect("There should always be at least one output");
@@ -65,23 +421,35 @@ fn normalize_all_outputs(outputs: &[&swayipc::Output]) -> Vec<swayipc::Output> {
 }
 
 impl super::DisplayBackend for Backend {
-    fn supported_operations(&mut self, output: &OutputEntry) -> Vec<Operation> {
-        match (output.connected, output.enabled) {
-            (false, _) => {
-                unreachable!("SwayIPC does not list disconnected outputs")
-            }
-
-            // If the output is connected but disabled, only show enable option
-            (_, false) => vec![Operation::Enable],
-
-            _ => vec![
-                Operation::Disable,
-                Operation::ChangeRes(Resolution::default()),
-                Operation::Position(Position::default()),
-                Operation::ChangeRate(Rate::default()),
-                Operation::Rotate(Rotation::default()),
-            ],
+    // Sway has no primary output, and does not really support mirroring.
+    // Some depend on the sway version and the seats, which are asked for
+    // once: neither changes while the menu is open.
+    fn capabilities(&mut self) -> Capabilities {
+        if let Some(caps) = &self.capabilities {
+            return caps.clone();
         }
+
+        let caps = Capabilities {
+            backend: "sway",
+            scale_filter: true,
+            subpixel: true,
+            max_render_time: true,
+            power: true,
+            flip: true,
+            workspaces: true,
+            // With a single seat, binding to it changes nothing
+            seats: self.get_seats().is_ok_and(|seats| seats.len() > 1),
+            // `output <name> allow_tearing` was added in sway 1.9
+            tearing: self.version_at_least(1, 9),
+            // `output <name> hdr` was added in sway 1.11
+            hdr: self.version_at_least(1, 11),
+            export: true,
+            edid: true,
+            virtual_outputs: true,
+            ..Default::default()
+        };
+        self.capabilities = Some(caps.clone());
+        caps
     }
 
     fn supported_relations(&mut self) -> Vec<Relation> {
@@ -90,6 +458,7 @@ impl super::DisplayBackend for Backend {
             Relation::RightOf,
             Relation::Below,
             Relation::Above,
+            Relation::CenteredOn,
         ]
     }
 
@@ -101,16 +470,43 @@ impl super::DisplayBackend for Backend {
 
         let entries = sway_outputs
             .iter()
-            .map(|o| OutputEntry {
-                name: o.name.clone(),
+            .zip(unique_names(&sway_outputs))
+            .map(|(o, name)| OutputEntry {
+                name,
                 connected: true, // swayipc only lists connected outputs
                 enabled: o.current_mode.is_some(),
+                primary: false,
+                edid_hash: edid::from_sysfs(&o.name).map(|e| edid::hash(&e)),
+                description: Some(description(o)),
+                provider: None,
+                adaptive_sync: o
+                    .adaptive_sync_status
+                    .map(|s| s == EnabledOrDisabled::Enabled),
+                geometry: geometry(o),
             })
             .collect();
 
         Ok(entries)
     }
 
+    fn get_geometry(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Geometry, BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(GetGeometry, SwayIPC, e))?;
+
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::GetGeometry::NoOutput(output_name.to_string()),
+        )?;
+        let geometry = geometry(output).ok_or(
+            super::err::GetGeometry::Disabled(output_name.to_string()),
+        )?;
+        Ok(geometry)
+    }
+
     fn get_resolutions(
         &mut self,
         output_name: &str,
@@ -119,48 +515,46 @@ impl super::DisplayBackend for Backend {
             .conn
             .get_outputs()
             .map_err(|e| backend_call_err!(GetResolutions, SwayIPC, e))?;
-        let output = outputs.iter().find(|o| o.name == output_name).ok_or(
+        let output = find_output(&outputs, output_name).ok_or(
             super::err::GetResolutions::NoOutput(output_name.to_string()),
         )?;
 
-        let current_mode = output
-            .current_mode
-            .ok_or(super::err::GetResolutions::GetCurrent)?;
+        let modes = advertised_modes(output);
+        let is_current = |m: &swayipc::Mode| {
+            output
+                .current_mode
+                .is_some_and(|c| m.width == c.width && m.height == c.height)
+        };
 
-        let mut entries = output
-            .modes
+        let entries = modes
             .iter()
             .map(|m| ResolutionEntry {
                 val: Resolution {
                     width: m.width as u32,
                     height: m.height as u32,
                 },
-                current: m.width == current_mode.width
-                    && m.height == current_mode.height,
+                current: is_current(m),
+                preferred: false, // swayipc does not report preferred modes
+                interlaced: false,
             })
             .collect::<Vec<ResolutionEntry>>();
 
-        // Sort on total pixels, then width.
-        // We need to sort before deduping because apparently the same
-        // resolution can appear twice with another resolution in between.
-        // No need for a height comparison, because heights must be equal if
-        // both px count and width are equal
-        let resolution_ord = |a: &ResolutionEntry, b: &ResolutionEntry| {
-            let px_count_ord = u32::cmp(
-                &(a.val.width * a.val.height),
-                &(b.val.width * b.val.height),
-            );
-            let width_ord = u32::cmp(&a.val.width, &b.val.width);
-
-            px_count_ord.then(width_ord)
-        };
+        Ok(ResolutionEntry::merge(entries))
+    }
 
-        entries.sort_by(resolution_ord);
-        entries.dedup_by(|a, b| {
-            a.val.width == b.val.width && a.val.height == b.val.height
-        });
+    fn accepts_any_mode(
+        &mut self,
+        output_name: &str,
+    ) -> Result<bool, BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(GetResolutions, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::GetResolutions::NoOutput(output_name.to_string()),
+        )?;
 
-        Ok(entries)
+        Ok(output.modes.is_empty())
     }
 
     fn set_resolution(
@@ -172,35 +566,15 @@ impl super::DisplayBackend for Backend {
             .conn
             .get_outputs()
             .map_err(|e| backend_call_err!(SetResolution, SwayIPC, e))?;
-        let output = outputs.iter().find(|o| o.name == output_name).ok_or(
+        let output = find_output(&outputs, output_name).ok_or(
             super::err::SetResolution::NoOutput(output_name.to_string()),
         )?;
 
-        let target_mode = output
-            .modes
-            .iter()
-            .find(|m| {
-                m.width as u32 == res.width && m.height as u32 == res.height
-            })
-            .ok_or(super::err::SetResolution::NoMode(res.clone()))?;
-
-        let mode_str = format!(
-            "{}x{}@{}Hz",
-            target_mode.width,
-            target_mode.height,
-            f64::from(target_mode.refresh) / 1000.0
-        );
-
-        let cmd = format!("output {} mode {}", output.name, mode_str);
-        let mut res = self
-            .conn
-            .run_command(cmd)
-            .map_err(|e| backend_call_err!(SetResolution, SwayIPC, e))?;
-        res.pop()
-            .unwrap()
-            .map_err(|e| backend_call_err!(SetResolution, SwayIPC, e))?;
-
-        Ok(())
+        let mode_str = resolution_mode_str(output, res)?;
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} mode {mode_str}");
+        let err_f = |e| backend_call_err!(SetResolution, SwayIPC, e);
+        self.run_cmd(cmd, err_f)
     }
 
     fn get_rates(
@@ -211,33 +585,28 @@ impl super::DisplayBackend for Backend {
             .conn
             .get_outputs()
             .map_err(|e| backend_call_err!(GetRates, SwayIPC, e))?;
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::GetRates::NoOutput(output_name.to_string()))?;
 
         let current_mode = output
             .current_mode
             .ok_or(super::err::GetRates::GetCurrent)?;
 
-        let mut entries = output
-            .modes
+        let modes = advertised_modes(output);
+        let entries = modes
             .iter()
             .filter(|m| {
                 m.height == current_mode.height && m.width == current_mode.width
             })
             .map(|m| RateEntry {
-                val: f64::from(m.refresh) / 1000.0,
+                val: hz(m.refresh),
                 current: m.refresh == current_mode.refresh,
             })
             .collect::<Vec<RateEntry>>();
 
-        // TODO: why is this needed?
         // swaymsg -t get_outputs seems to have aspect ratios next to the
         // duplicate modes, but swayipc::Mode does not seem to distinguish
-        entries.dedup_by(|a, b| (a.val - b.val).abs() < RATE_EPSILON);
-
-        Ok(entries)
+        Ok(RateEntry::merge(entries))
     }
 
     fn set_rate(
@@ -249,37 +618,28 @@ impl super::DisplayBackend for Backend {
             .conn
             .get_outputs()
             .map_err(|e| backend_call_err!(SetRate, SwayIPC, e))?;
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::SetRate::NoOutput(output_name.to_string()))?;
 
         let current_mode = output
             .current_mode
             .ok_or(super::err::SetRate::NoMode(output_name.to_string()))?;
 
-        let target_mode = output
-            .modes
-            .iter()
-            .find(|m| {
-                m.width as u32 == current_mode.width as u32
-                    && m.height as u32 == current_mode.height as u32
-                    && ((f64::from(m.refresh) / 1000.0) - rate).abs()
-                        < RATE_EPSILON
-            })
-            .ok_or(super::err::SetRate::NoRate(rate))?;
-
-        let mode_str = format!(
-            "{}x{}@{}Hz",
-            target_mode.width,
-            target_mode.height,
-            f64::from(target_mode.refresh) / 1000.0
-        );
+        // Sway takes any rate as a custom mode, e.g. a typed in one for an
+        // overclocked monitor. It fails if the output cannot do it.
+        let mode_str = match mode_at_rate(output, &current_mode, rate) {
+            Some(mode) => sway_mode_str(mode),
+            None => {
+                let (w, h) = (current_mode.width, current_mode.height);
+                format!("--custom {}", mode_str(w, h, Some(rate)))
+            }
+        };
 
         let err_f = |e| backend_call_err!(SetRate, SwayIPC, e);
-        let cmd = format!("output {} mode {}", output.name, mode_str);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} mode {mode_str}");
 
-        run_sway_cmd(&mut self.conn, cmd, err_f)
+        self.run_cmd(cmd, err_f)
     }
 
     fn set_rotation(
@@ -287,26 +647,40 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         rotation: &Rotation,
     ) -> Result<(), BackendError> {
-        let angle_str = match rotation {
-            Rotation::Normal => "0",
-            Rotation::Left => "90",
-            Rotation::Inverted => "180",
-            Rotation::Right => "270",
-        };
+        // A flipped output stays flipped
+        self.transform_output(output_name, |output| Transform {
+            rotation: rotation.clone(),
+            flipped: is_flipped(output),
+        })
+    }
 
+    fn get_transform(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Transform, BackendError> {
         let outputs = self
             .conn
             .get_outputs()
-            .map_err(|e| backend_call_err!(SetRotation, SwayIPC, e))?;
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
-            .ok_or(super::err::Enable::NoOutput(output_name.to_string()))?;
+            .map_err(|e| backend_call_err!(GetGeometry, SwayIPC, e))?;
 
-        let err_f = |e| backend_call_err!(SetRotation, SwayIPC, e);
-        let cmd = format!("output {} transform {}", output.name, angle_str);
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::GetGeometry::NoOutput(output_name.to_string()),
+        )?;
+        let geometry = geometry(output).ok_or(
+            super::err::GetGeometry::Disabled(output_name.to_string()),
+        )?;
+        Ok(Transform {
+            rotation: geometry.rotation,
+            flipped: is_flipped(output),
+        })
+    }
 
-        run_sway_cmd(&mut self.conn, cmd, err_f)
+    fn set_transform(
+        &mut self,
+        output_name: &str,
+        t: &Transform,
+    ) -> Result<(), BackendError> {
+        self.transform_output(output_name, |_| t.clone())
     }
 
     // This is not really supported in sway-output, but it can be easily
@@ -326,15 +700,17 @@ impl super::DisplayBackend for Backend {
             .get_outputs()
             .map_err(|e| backend_call_err!(SetPosition, SwayIPC, e))?;
 
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::Enable::NoOutput(output_name.to_string()))?;
-        let rel_output = outputs
-            .iter()
-            .find(|o| &o.name == rel_output)
+        let rel_output = find_output(&outputs, rel_output)
             .ok_or(super::err::Enable::NoOutput(rel_output.to_string()))?;
 
+        // The UI does not offer these either, see `relatives_list`
+        if rel_output.current_mode.is_none() {
+            let name = rel_output.name.clone();
+            Err(super::err::SetPosition::RelativeDisabled(name))?;
+        }
+
         let (w, h) = (output.rect.width, output.rect.height);
         let (rel_x, rel_y) = (rel_output.rect.x, rel_output.rect.y);
         let (rel_w, rel_h) = (rel_output.rect.width, rel_output.rect.height);
@@ -346,10 +722,14 @@ impl super::DisplayBackend for Backend {
             Relation::Above => (rel_x, rel_y - h),
             Relation::Below => (rel_x, rel_y + rel_h),
             Relation::SameAs => (rel_x, rel_y),
+            Relation::CenteredOn => {
+                (rel_x + (rel_w - w) / 2, rel_y + (rel_h - h) / 2)
+            }
         };
 
         // New iterator of outputs based on the old and the new output
-        let new_outputs: Vec<&swayipc::Output> = outputs.iter()
+        let new_outputs: Vec<&swayipc::Output> = outputs
+            .iter()
             .filter(|o| o.name != new_output.name)
             .chain(std::iter::once(&new_output))
             .collect();
@@ -362,7 +742,8 @@ impl super::DisplayBackend for Backend {
             .zip(normalized_outputs.iter())
             .filter(|(old, new)| old.rect != new.rect)
             .map(|(_, new)| {
-                format!("output {} pos {} {}", new.name, new.rect.x, new.rect.y)
+                let ident = self.output_ident(new, &outputs);
+                format!("output {ident} pos {} {}", new.rect.x, new.rect.y)
             })
             .collect();
 
@@ -372,29 +753,361 @@ impl super::DisplayBackend for Backend {
         }
 
         let err_f = |e| backend_call_err!(SetPosition, SwayIPC, e);
-        let cmd = itertools::Itertools::join(&mut cmds.iter(), ";");
+        self.run_cmds(&cmds, err_f)
+    }
+
+    fn set_coordinates(
+        &mut self,
+        output_name: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetCoordinates, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetCoordinates::NoOutput(output_name.to_string()),
+        )?;
+
+        let err_f = |e| backend_call_err!(SetCoordinates, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} pos {x} {y}");
 
-        run_sway_cmd(&mut self.conn, cmd, err_f)
+        self.run_cmd(cmd, err_f)
     }
 
+    // Sway has no primary output. Focusing an output comes closest, but
+    // that is not persistent, so this is left out of `capabilities`
     fn set_primary(&mut self, _output_name: &str) -> Result<(), BackendError> {
-        unimplemented!("Not supported in swayipc");
+        Err(BackendError::Unsupported)
     }
 
-    fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
+    // Color depth is an X concept
+    fn set_color_depth(
+        &mut self,
+        _output_name: &str,
+        _depth: Depth,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_hdr(
+        &mut self,
+        output_name: &str,
+        on: bool,
+    ) -> Result<(), BackendError> {
         let outputs = self
             .conn
             .get_outputs()
-            .map_err(|e| backend_call_err!(Enable, SwayIPC, e))?;
+            .map_err(|e| backend_call_err!(SetHdr, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name)
+            .ok_or(super::err::SetHdr::NoOutput(output_name.to_string()))?;
+
+        let err_f = |e| backend_call_err!(SetHdr, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let on_s = if on { "on" } else { "off" };
+        let cmd = format!("output {ident} hdr {on_s}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_tearing(
+        &mut self,
+        output_name: &str,
+        allow: bool,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetTearing, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name)
+            .ok_or(super::err::SetTearing::NoOutput(output_name.to_string()))?;
+
+        let err_f = |e| backend_call_err!(SetTearing, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let allow_s = if allow { "yes" } else { "no" };
+        let cmd = format!("output {ident} allow_tearing {allow_s}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_scale_filter(
+        &mut self,
+        output_name: &str,
+        filter: &Filter,
+    ) -> Result<(), BackendError> {
+        let filter_str = match filter {
+            Filter::Linear => "linear",
+            Filter::Nearest => "nearest",
+        };
+
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetScaleFilter, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetScaleFilter::NoOutput(output_name.to_string()),
+        )?;
+
+        let err_f = |e| backend_call_err!(SetScaleFilter, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} scale_filter {filter_str}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_scale(
+        &mut self,
+        output_name: &str,
+        scale: f64,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetScale, SwayIPC, e))?;
         let output = outputs
             .iter()
             .find(|o| o.name == output_name)
+            .ok_or(super::err::SetScale::NoOutput(output_name.to_string()))?;
+
+        let err_f = |e| backend_call_err!(SetScale, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} scale {scale}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_max_render_time(
+        &mut self,
+        output_name: &str,
+        ms: Option<u32>,
+    ) -> Result<(), BackendError> {
+        let time_str = match ms {
+            Some(ms) => ms.to_string(),
+            None => "off".to_string(),
+        };
+
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetMaxRenderTime, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetMaxRenderTime::NoOutput(output_name.to_string()),
+        )?;
+
+        let err_f = |e| backend_call_err!(SetMaxRenderTime, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} max_render_time {time_str}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_subpixel(
+        &mut self,
+        output_name: &str,
+        subpixel: &Subpixel,
+    ) -> Result<(), BackendError> {
+        let subpixel_str = match subpixel {
+            Subpixel::Rgb => "rgb",
+            Subpixel::Bgr => "bgr",
+            Subpixel::Vrgb => "vrgb",
+            Subpixel::Vbgr => "vbgr",
+            Subpixel::None => "none",
+        };
+
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetSubpixel, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetSubpixel::NoOutput(output_name.to_string()),
+        )?;
+
+        let err_f = |e| backend_call_err!(SetSubpixel, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} subpixel {subpixel_str}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_power(
+        &mut self,
+        output_name: &str,
+        power: &PowerState,
+    ) -> Result<(), BackendError> {
+        // There is no standby in sway, only on and off
+        let power_str = match power {
+            PowerState::On => "on",
+            PowerState::Standby | PowerState::Off => "off",
+        };
+        // `output <name> power` replaced `dpms` in sway 1.8
+        let keyword = match self.version_at_least(1, 8) {
+            true => "power",
+            false => "dpms",
+        };
+
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetPower, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name)
+            .ok_or(super::err::SetPower::NoOutput(output_name.to_string()))?;
+
+        let err_f = |e| backend_call_err!(SetPower, SwayIPC, e);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} {keyword} {power_str}");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    fn set_gamma(
+        &mut self,
+        _output_name: &str,
+        _gamma: &Gamma,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_brightness(
+        &mut self,
+        _output_name: &str,
+        _brightness: f64,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn get_workspaces(&mut self) -> Result<Vec<WorkspaceEntry>, BackendError> {
+        let workspaces = self
+            .conn
+            .get_workspaces()
+            .map_err(|e| backend_call_err!(GetWorkspaces, SwayIPC, e))?;
+
+        let entries = workspaces
+            .into_iter()
+            .map(|ws| WorkspaceEntry {
+                name: ws.name,
+                output: ws.output,
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    // Assigns the workspace to the output, so that it opens there from now
+    // on. If the workspace already exists, it is also moved there right away.
+    fn set_workspace(
+        &mut self,
+        output_name: &str,
+        workspace: &str,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetWorkspace, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name).ok_or(
+            super::err::SetWorkspace::NoOutput(output_name.to_string()),
+        )?;
+        let workspaces = self
+            .conn
+            .get_workspaces()
+            .map_err(|e| backend_call_err!(SetWorkspace, SwayIPC, e))?;
+        let exists = workspaces.iter().any(|w| w.name == workspace);
+        let focused = workspaces.iter().find(|w| w.focused);
+
+        let ident = self.output_ident(output, &outputs);
+        let cmd = workspace_cmd(
+            workspace,
+            &ident,
+            exists,
+            focused.map(|w| w.name.as_str()),
+        );
+
+        let err_f = |e| backend_call_err!(SetWorkspace, SwayIPC, e);
+        self.run_cmd(cmd, err_f)
+    }
+
+    // Sway only passes on what it decoded from the EDID, the kernel still
+    // has all of it
+    fn get_edid(&mut self, output_name: &str) -> Result<Vec<u8>, BackendError> {
+        edid::from_sysfs(output_name)
+            .ok_or(super::err::GetEdid::NoEdid(output_name.to_string()).into())
+    }
+
+    fn get_seats(&mut self) -> Result<Vec<SeatEntry>, BackendError> {
+        let seats = self
+            .conn
+            .get_seats()
+            .map_err(|e| backend_call_err!(GetSeats, SwayIPC, e))?;
+
+        let entries = seats
+            .into_iter()
+            .map(|seat| {
+                let devices = seat.devices.iter().filter(|d| maps_to_output(d));
+                SeatEntry {
+                    devices: devices.count(),
+                    name: seat.name,
+                }
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn set_seat(
+        &mut self,
+        output_name: &str,
+        seat_name: &str,
+    ) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(SetSeat, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name)
+            .ok_or(super::err::SetSeat::NoOutput(output_name.to_string()))?;
+        let seats = self
+            .conn
+            .get_seats()
+            .map_err(|e| backend_call_err!(SetSeat, SwayIPC, e))?;
+        let seat = seats
+            .iter()
+            .find(|s| s.name == seat_name)
+            .ok_or(super::err::SetSeat::NoSeat(seat_name.to_string()))?;
+
+        let ident = self.output_ident(output, &outputs);
+        let quote = |s: &str| format!("\"{}\"", s.replace('"', "\\\""));
+        let mut cmds: Vec<_> = seat
+            .devices
+            .iter()
+            .filter(|d| maps_to_output(d))
+            .map(|d| {
+                let device = quote(&d.identifier);
+                format!("input {device} map_to_output {ident}")
+            })
+            .collect();
+        if let Some(g) = geometry(output) {
+            let (x, y) = g.center();
+            let name = quote(seat_name);
+            cmds.push(format!("seat {name} cursor set {x} {y}"));
+        }
+
+        let err_f = |e| backend_call_err!(SetSeat, SwayIPC, e);
+        self.run_cmds(&cmds, err_f)
+    }
+
+    fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(Enable, SwayIPC, e))?;
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::Enable::NoOutput(output_name.to_string()))?;
 
         let err_f = |e| backend_call_err!(Enable, SwayIPC, e);
-        let cmd = format!("output {} enable", output.name);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} enable");
 
-        run_sway_cmd(&mut self.conn, cmd, err_f)
+        self.run_cmd(cmd, err_f)
     }
 
     fn disable(&mut self, output_name: &str) -> Result<(), BackendError> {
@@ -402,14 +1115,347 @@ impl super::DisplayBackend for Backend {
             .conn
             .get_outputs()
             .map_err(|e| backend_call_err!(Disable, SwayIPC, e))?;
-        let output = outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&outputs, output_name)
             .ok_or(super::err::Disable::NoOutput(output_name.to_string()))?;
 
         let err_f = |e| backend_call_err!(Disable, SwayIPC, e);
-        let cmd = format!("output {} disable", output.name);
+        let ident = self.output_ident(output, &outputs);
+        let cmd = format!("output {ident} disable");
+
+        self.run_cmd(cmd, err_f)
+    }
+
+    // A subscribed connection only receives events, so this needs one of
+    // its own
+    fn watch_changes(&mut self) -> Result<(), BackendError> {
+        let events = Connection::new()
+            .and_then(|conn| conn.subscribe([EventType::Output]))
+            .map_err(|e| backend_call_err!(WaitForChange, SwayIPC, e))?;
+        self.events = Some(events);
+        Ok(())
+    }
+
+    fn wait_for_change(&mut self) -> Result<(), BackendError> {
+        let events = match &mut self.events {
+            Some(events) => events,
+            None => {
+                self.watch_changes()?;
+                self.events.as_mut().expect("Set by watch_changes")
+            }
+        };
+
+        // Only output events are subscribed to
+        events
+            .next()
+            .transpose()
+            .map_err(|e| backend_call_err!(WaitForChange, SwayIPC, e))?;
+        Ok(())
+    }
+
+    // One swaymsg call, with a command per output
+    fn layout_command(&mut self) -> Result<String, BackendError> {
+        // The layout has no reflection, so that comes from sway itself
+        let outputs = self
+            .conn
+            .get_outputs()
+            .map_err(|e| backend_call_err!(GetOutputs, SwayIPC, e))?;
+        let flipped =
+            |name: &str| find_output(&outputs, name).is_some_and(is_flipped);
+
+        Ok(swaymsg_command(&self.get_layout()?, flipped))
+    }
+
+    // Sway names these HEADLESS-N, but does not say which N it picked
+    fn create_output(&mut self) -> Result<String, BackendError> {
+        let names = |conn: &mut swayipc::Connection| {
+            conn.get_outputs()
+                .map(|os| os.into_iter().map(|o| o.name).collect::<Vec<_>>())
+                .map_err(|e| backend_call_err!(CreateOutput, SwayIPC, e))
+        };
+
+        let before = names(&mut self.conn)?;
+        let err_f = |e| backend_call_err!(CreateOutput, SwayIPC, e);
+        self.run_cmd("create_output".to_string(), err_f)?;
+
+        names(&mut self.conn)?
+            .into_iter()
+            .find(|name| !before.contains(name))
+            .ok_or(super::err::CreateOutput::NotFound.into())
+    }
+
+    fn warp_pointer(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let (x, y) = self.get_geometry(output_name)?.center();
+
+        let err_f = |e| backend_call_err!(WarpPointer, SwayIPC, e);
+        let cmd = format!("seat - cursor set {x} {y}");
+        self.run_cmd(cmd, err_f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::err;
+    use crate::profile::Profile;
+    use itertools::Itertools;
+
+    fn t(rotation: Rotation, flipped: bool) -> Transform {
+        Transform { rotation, flipped }
+    }
+
+    #[test]
+    fn transform_of_each_rotation() {
+        assert_eq!(transform(&t(Rotation::Normal, false)), "0");
+        assert_eq!(transform(&t(Rotation::Left, false)), "90");
+        assert_eq!(transform(&t(Rotation::Inverted, false)), "180");
+        assert_eq!(transform(&t(Rotation::Right, false)), "270");
+    }
+
+    #[test]
+    fn transform_of_each_flipped_rotation() {
+        assert_eq!(transform(&t(Rotation::Normal, true)), "flipped");
+        assert_eq!(transform(&t(Rotation::Left, true)), "flipped-90");
+        assert_eq!(transform(&t(Rotation::Inverted, true)), "flipped-180");
+        assert_eq!(transform(&t(Rotation::Right, true)), "flipped-270");
+    }
+
+    #[test]
+    fn transforms_are_distinct() {
+        let all = Transform::all(true);
+        let names: Vec<_> = all.iter().map(transform).collect();
+        assert_eq!(all.len(), 8);
+        assert!(names.iter().all_unique());
+    }
+
+    // As sway lists an output made with `create_output`
+    const HEADLESS: &str = r#"{
+        "id": 12, "type": "output", "name": "HEADLESS-1",
+        "make": "headless", "model": "headless", "serial": "",
+        "active": true, "dpms": true, "power": true, "primary": false,
+        "scale": 1.0, "transform": "normal", "current_workspace": "3",
+        "modes": [],
+        "current_mode": {"width": 1920, "height": 1080, "refresh": 60000},
+        "rect": {"x": 3840, "y": 0, "width": 1920, "height": 1080}
+    }"#;
+
+    fn headless() -> swayipc::Output {
+        serde_json::from_str(HEADLESS).unwrap()
+    }
+
+    #[test]
+    fn headless_lists_its_current_mode() {
+        let modes = advertised_modes(&headless());
+        let sizes: Vec<_> = modes
+            .iter()
+            .map(|m| (m.width, m.height, m.refresh))
+            .collect();
+        assert_eq!(sizes, [(1920, 1080, 60000)]);
+    }
+
+    #[test]
+    fn headless_takes_any_resolution() {
+        let res = Resolution {
+            width: 1234,
+            height: 567,
+        };
+        let mode = resolution_mode_str(&headless(), &res).unwrap();
+        assert_eq!(mode, "--custom 1234x567");
+    }
+
+    #[test]
+    fn headless_geometry() {
+        let geometry = geometry(&headless()).unwrap();
+        assert_eq!((geometry.x, geometry.width), (3840, 1920));
+        assert!(matches!(geometry.rotation, Rotation::Normal));
+    }
+
+    fn err_f(e: swayipc::Error) -> BackendError {
+        let e = err::SetPosition::BackendCall(err::BackendCall::SwayIPC(e));
+        BackendError::SetPosition(e)
+    }
+
+    #[test]
+    fn names_the_failing_command_of_a_batch() {
+        let cmd = "output DP-1 pos 0 0; output DP-2 pos 1920 0; \
+                   output HDMI-A-1 pos 3840 0";
+        let results = vec![
+            Ok(()),
+            Err(swayipc::Error::CommandFailed("Invalid output".to_string())),
+            Ok(()),
+        ];
+
+        let failed = failures(cmd, results);
+        assert_eq!(failed, ["'output DP-2 pos 1920 0': Invalid output"]);
+
+        let err = failures_to_err(failed, err_f).unwrap_err().to_string();
+        assert!(err.contains("DP-2"), "{err}");
+        assert!(!err.contains("HDMI-A-1"), "{err}");
+    }
+
+    #[test]
+    fn a_batch_without_failures_is_ok() {
+        let cmd = "output DP-1 pos 0 0; output DP-2 pos 1920 0";
+        let failed = failures(cmd, vec![Ok(()), Ok(())]);
+        assert!(failed.is_empty());
+
+        assert!(failures_to_err(failed, err_f).is_ok());
+    }
+
+    // A monitor with fractional rates, close to each other
+    const MONITOR: &str = r#"{
+        "id": 3, "type": "output", "name": "DP-1",
+        "make": "Dell Inc.", "model": "DELL S2721DGF", "serial": "ABC123",
+        "active": true, "primary": false, "scale": 1.0,
+        "transform": "normal", "current_workspace": "1",
+        "modes": [
+            {"width": 2560, "height": 1440, "refresh": 164999},
+            {"width": 2560, "height": 1440, "refresh": 59951},
+            {"width": 2560, "height": 1440, "refresh": 59940},
+            {"width": 1920, "height": 1080, "refresh": 60000}
+        ],
+        "current_mode": {"width": 2560, "height": 1440, "refresh": 164999},
+        "rect": {"x": 0, "y": 0, "width": 2560, "height": 1440}
+    }"#;
+
+    #[test]
+    fn rates_are_read_in_hz() {
+        let output: swayipc::Output = serde_json::from_str(MONITOR).unwrap();
+        let rates: Vec<_> =
+            output.modes.iter().map(|m| hz(m.refresh)).collect();
+        assert_eq!(rates, [164.999, 59.951, 59.94, 60.0]);
+
+        let mode = output.current_mode.unwrap();
+        assert_eq!(sway_mode_str(&mode), "2560x1440@164.999Hz");
+    }
+
+    #[test]
+    fn rates_as_listed_find_their_mode() {
+        let output: swayipc::Output = serde_json::from_str(MONITOR).unwrap();
+        let current = output.current_mode.unwrap();
+        let refresh =
+            |rate| mode_at_rate(&output, &current, rate).map(|m| m.refresh);
+
+        // As listed with the default two decimals, and as typed in
+        assert_eq!(refresh(59.95), Some(59951));
+        assert_eq!(refresh(59.94), Some(59940));
+        assert_eq!(refresh(165.0), Some(164999));
+        // Only at the current resolution
+        assert_eq!(refresh(60.0), None);
+    }
+
+    // A copy of MONITOR with another serial
+    fn monitor(serial: &str) -> swayipc::Output {
+        let mut output: swayipc::Output =
+            serde_json::from_str(MONITOR).unwrap();
+        output.serial = serial.to_string();
+        output
+    }
+
+    #[test]
+    fn ambiguous_names_use_the_description() {
+        let outputs = [monitor("ABC123"), monitor("DEF456")];
+
+        let idents: Vec<_> =
+            outputs.iter().map(|o| ident(o, &outputs, false)).collect();
+        assert_eq!(
+            idents,
+            [
+                "\"Dell Inc. DELL S2721DGF ABC123\"",
+                "\"Dell Inc. DELL S2721DGF DEF456\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_descriptions_use_the_name() {
+        let outputs = [monitor("ABC123"), monitor("ABC123")];
+        assert_eq!(ident(&outputs[0], &outputs, true), "DP-1");
+    }
+
+    #[test]
+    fn unique_names_use_the_description_when_asked() {
+        let outputs = [monitor("ABC123")];
+        assert_eq!(ident(&outputs[0], &outputs, false), "DP-1");
+        assert_eq!(
+            ident(&outputs[0], &outputs, true),
+            "\"Dell Inc. DELL S2721DGF ABC123\""
+        );
+    }
+
+    #[test]
+    fn moving_a_workspace_focuses_the_previous_one_again() {
+        let cmd = workspace_cmd("2", "DP-1", true, Some("1"));
+        assert_eq!(
+            cmd,
+            "workspace \"2\" output DP-1; \
+             workspace --no-auto-back-and-forth \"2\"; \
+             move workspace to output DP-1; \
+             workspace --no-auto-back-and-forth \"1\""
+        );
+    }
+
+    #[test]
+    fn moving_the_focused_workspace_keeps_it_focused() {
+        let cmd = workspace_cmd("2", "DP-1", true, Some("2"));
+        assert!(cmd.ends_with("move workspace to output DP-1"), "{cmd}");
+    }
+
+    #[test]
+    fn new_workspaces_are_only_assigned() {
+        let cmd = workspace_cmd("web", "DP-1", false, Some("1"));
+        assert_eq!(cmd, "workspace \"web\" output DP-1");
+    }
+
+    #[test]
+    fn layout_command_imports_back() {
+        let layout = [
+            OutputLayout {
+                name: "eDP-1".to_string(),
+                enabled: true,
+                resolution: Some(Resolution {
+                    width: 2880,
+                    height: 1920,
+                }),
+                rate: Some(120.0),
+                scale: 1.5,
+                ..Default::default()
+            },
+            OutputLayout {
+                name: "DP-1".to_string(),
+                enabled: true,
+                resolution: Some(Resolution {
+                    width: 2560,
+                    height: 1440,
+                }),
+                rate: Some(59.951),
+                x: 1920,
+                y: -200,
+                rotation: Rotation::Left,
+                scale: 1.0,
+                ..Default::default()
+            },
+            OutputLayout {
+                name: "HDMI-A-1".to_string(),
+                enabled: false,
+                ..Default::default()
+            },
+        ];
+        let command = swaymsg_command(&layout, |name| name == "DP-1");
+        let profile = Profile::from_command(&command).unwrap();
+
+        let laptop = &profile.outputs["eDP-1"];
+        assert_eq!(laptop.resolution, layout[0].resolution);
+        assert_eq!(laptop.rate, Some(120.0));
+        assert_eq!(laptop.scale, Some(1.5));
+        assert!(laptop.enabled && !laptop.flipped);
+
+        let dp = &profile.outputs["DP-1"];
+        assert_eq!((dp.x, dp.y), (1920, -200));
+        assert_eq!(dp.rate, Some(59.951));
+        assert_eq!(dp.rotation, Rotation::Left);
+        assert_eq!(dp.scale, Some(1.0));
+        assert!(dp.flipped);
 
-        run_sway_cmd(&mut self.conn, cmd, err_f)
+        assert!(!profile.outputs["HDMI-A-1"].enabled);
     }
 }
@@ -0,0 +1,687 @@
+use std::io;
+use std::process::Command;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::action::depth::Depth;
+use crate::action::gamma::Gamma;
+use crate::action::position::{Position, Relation};
+use crate::action::power::PowerState;
+use crate::action::rate::Rate;
+use crate::action::resolution::Resolution;
+use crate::action::rotate::{Rotation, Transform};
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
+use crate::backend::Error as BackendError;
+use crate::backend_call as backend_call_err;
+
+use super::edid;
+use super::xrandr_cli::{missing_binary, run_cmd_and_check};
+use super::{
+    find_output, unique_names, Capabilities, DisplayBackend, Geometry, Named,
+    OutputEntry, RateEntry, ResolutionEntry, WorkspaceEntry,
+};
+
+// wlr-randr can not notify us of changes, so `wait_for_change` polls
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// Structs to parse `wlr-randr --json` into. Only connected outputs are
+// listed, and disabled ones without a position, transform, scale or
+// adaptive sync.
+#[derive(Debug, Clone, Deserialize)]
+struct Mode {
+    width: u32,
+    height: u32,
+    // In Hz
+    refresh: f64,
+    preferred: bool,
+    current: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Output {
+    name: String,
+    make: Option<String>,
+    model: Option<String>,
+    serial: Option<String>,
+    enabled: bool,
+    modes: Vec<Mode>,
+    position: Option<Point>,
+    transform: Option<String>,
+    scale: Option<f64>,
+    adaptive_sync: Option<bool>,
+}
+
+impl Named for Output {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Output {
+    // Like sway's, leaving out what the output does not report
+    fn description(&self) -> Option<String> {
+        let parts: Vec<&str> = [&self.make, &self.model, &self.serial]
+            .into_iter()
+            .flatten()
+            .map(|s| s.as_str())
+            .filter(|s| !s.is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    fn current_mode(&self) -> Option<&Mode> {
+        self.modes.iter().find(|m| m.current)
+    }
+
+    fn is_flipped(&self) -> bool {
+        self.transform
+            .as_deref()
+            .is_some_and(|t| t.starts_with("flipped"))
+    }
+
+    fn get_transform(&self) -> Option<Transform> {
+        Some(Transform {
+            rotation: self.geometry()?.rotation,
+            flipped: self.is_flipped(),
+        })
+    }
+
+    // Width and height are of the mode, the geometry is in logical pixels
+    fn geometry(&self) -> Option<Geometry> {
+        let position = self.position.as_ref()?;
+        let mode = self.current_mode()?;
+
+        // "normal" does not parse, and is 0 degrees
+        let angle = self.transform.as_deref().map_or(0, |t| {
+            t.trim_start_matches("flipped-").parse::<u32>().unwrap_or(0)
+        });
+        let rotation = Rotation::from_quarter_turns(angle / 90);
+        let scale = self.scale.unwrap_or(1.0);
+
+        let (width, height) = match rotation {
+            Rotation::Left | Rotation::Right => (mode.height, mode.width),
+            Rotation::Normal | Rotation::Inverted => (mode.width, mode.height),
+        };
+        let logical = |px: u32| (f64::from(px) / scale).round() as u32;
+
+        Some(Geometry {
+            x: position.x,
+            y: position.y,
+            width: logical(width),
+            height: logical(height),
+            rotation,
+            scale,
+        })
+    }
+}
+
+fn parse(json: &[u8]) -> Result<Vec<Output>, serde_json::Error> {
+    serde_json::from_slice(json)
+}
+
+// wlr-randr takes rotation and reflection as a single transform, like sway
+// does, except that no rotation is "normal" rather than "0"
+fn transform(t: &Transform) -> String {
+    let angle = match t.rotation {
+        Rotation::Normal => None,
+        Rotation::Left => Some("90"),
+        Rotation::Inverted => Some("180"),
+        Rotation::Right => Some("270"),
+    };
+
+    match (t.flipped, angle) {
+        (false, None) => String::from("normal"),
+        (true, None) => String::from("flipped"),
+        (false, Some(angle)) => angle.to_string(),
+        (true, Some(angle)) => format!("flipped-{angle}"),
+    }
+}
+
+fn get_state() -> Result<Vec<Output>, BackendError> {
+    let err_f = |s: String| backend_call_err!(GetOutputs, WlrRandr, s);
+
+    let mut cmd = Command::new("wlr-randr");
+    cmd.arg("--json");
+    let res = cmd.output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => missing_binary(&cmd),
+        _ => err_f(e.to_string()),
+    })?;
+
+    if !res.status.success() {
+        return Err(err_f(String::from_utf8_lossy(&res.stderr).to_string()));
+    }
+    parse(&res.stdout).map_err(|e| err_f(e.to_string()))
+}
+
+/// For wlroots compositors other than sway, like river, Hyprland or
+/// labwc. Calls `wlr-randr` in a subprocess.
+pub struct Backend {
+    outputs: Vec<Output>,
+}
+
+impl Backend {
+    pub fn new() -> Result<Self, BackendError> {
+        Ok(Self {
+            outputs: get_state()?,
+        })
+    }
+
+    fn output(&self, output_name: &str) -> Option<&Output> {
+        find_output(&self.outputs, output_name)
+    }
+
+    // Runs `wlr-randr --output <output_name>` with `args`, and reads the
+    // new state back
+    fn run_output(
+        &mut self,
+        output_name: &str,
+        args: &[&str],
+        err_f: fn(s: String) -> BackendError,
+    ) -> Result<(), BackendError> {
+        // wlr-randr takes outputs by name only, and would change the first
+        // of the outputs that share one
+        let output = self.output(output_name);
+        if output.is_some_and(|o| o.name != output_name) {
+            return Err(err_f(format!(
+                "wlr-randr can not tell {output_name} apart from other \
+                 outputs with the same name"
+            )));
+        }
+
+        let mut cmd = Command::new("wlr-randr");
+        let cmd = cmd.args(["--output", output_name]).args(args);
+        run_cmd_and_check(cmd, err_f)?;
+
+        self.outputs = get_state()?;
+        Ok(())
+    }
+}
+
+impl DisplayBackend for Backend {
+    fn capabilities(&mut self) -> Capabilities {
+        Capabilities {
+            backend: "wlr_randr",
+            flip: true,
+            ..Default::default()
+        }
+    }
+
+    // Outputs can not share a position to mirror, wlroots has no mirroring
+    fn supported_relations(&mut self) -> Vec<Relation> {
+        vec![
+            Relation::LeftOf,
+            Relation::RightOf,
+            Relation::Below,
+            Relation::Above,
+            Relation::CenteredOn,
+        ]
+    }
+
+    fn get_outputs(&mut self) -> Result<Vec<OutputEntry>, BackendError> {
+        let entries = self
+            .outputs
+            .iter()
+            .zip(unique_names(&self.outputs))
+            .map(|(o, name)| OutputEntry {
+                name,
+                connected: true, // wlr-randr only lists connected outputs
+                enabled: o.enabled,
+                primary: false,
+                edid_hash: edid::from_sysfs(&o.name).map(|e| edid::hash(&e)),
+                description: o.description(),
+                provider: None,
+                adaptive_sync: o.adaptive_sync,
+                geometry: o.geometry(),
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn get_geometry(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Geometry, BackendError> {
+        let output = self.output(output_name).ok_or(
+            super::err::GetGeometry::NoOutput(output_name.to_string()),
+        )?;
+
+        let geometry = output.geometry().ok_or(
+            super::err::GetGeometry::Disabled(output_name.to_string()),
+        )?;
+        Ok(geometry)
+    }
+
+    fn get_resolutions(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Vec<ResolutionEntry>, BackendError> {
+        let output = self.output(output_name).ok_or(
+            super::err::GetResolutions::NoOutput(output_name.to_string()),
+        )?;
+
+        // Every rate is its own mode, `merge` makes those one entry
+        let entries = output.modes.iter().map(|m| ResolutionEntry {
+            val: Resolution {
+                width: m.width,
+                height: m.height,
+            },
+            current: m.current,
+            preferred: m.preferred,
+            interlaced: false,
+        });
+
+        Ok(ResolutionEntry::merge(entries))
+    }
+
+    fn set_resolution(
+        &mut self,
+        output_name: &str,
+        res: &Resolution,
+    ) -> Result<(), BackendError> {
+        let mode_s = format!("{}x{}", res.width, res.height);
+        let err_f = |s: String| backend_call_err!(SetResolution, WlrRandr, s);
+        self.run_output(output_name, &["--mode", &mode_s], err_f)
+    }
+
+    fn get_rates(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Vec<RateEntry>, BackendError> {
+        let output = self
+            .output(output_name)
+            .ok_or(super::err::GetRates::NoOutput(output_name.to_string()))?;
+        let current_mode = output
+            .current_mode()
+            .ok_or(super::err::GetRates::GetCurrent)?;
+
+        let entries = output
+            .modes
+            .iter()
+            .filter(|m| {
+                m.width == current_mode.width && m.height == current_mode.height
+            })
+            .map(|m| RateEntry {
+                val: m.refresh,
+                current: m.current,
+            });
+
+        Ok(RateEntry::merge(entries))
+    }
+
+    fn set_rate(
+        &mut self,
+        output_name: &str,
+        rate: Rate,
+    ) -> Result<(), BackendError> {
+        let mode = self
+            .output(output_name)
+            .ok_or(super::err::SetRate::NoOutput(output_name.to_string()))?
+            .current_mode()
+            .ok_or(super::err::SetRate::NoMode(output_name.to_string()))?;
+
+        let mode_s = format!("{}x{}@{rate}Hz", mode.width, mode.height);
+        let err_f = |s: String| backend_call_err!(SetRate, WlrRandr, s);
+        self.run_output(output_name, &["--mode", &mode_s], err_f)
+    }
+
+    // A flipped output stays flipped
+    fn set_rotation(
+        &mut self,
+        output_name: &str,
+        rotation: &Rotation,
+    ) -> Result<(), BackendError> {
+        let flipped = self
+            .output(output_name)
+            .ok_or(super::err::SetRotation::NoOutput(output_name.to_string()))?
+            .is_flipped();
+
+        let t = Transform {
+            rotation: rotation.clone(),
+            flipped,
+        };
+        self.set_transform(output_name, &t)
+    }
+
+    fn get_transform(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Transform, BackendError> {
+        let output = self.output(output_name).ok_or(
+            super::err::GetGeometry::NoOutput(output_name.to_string()),
+        )?;
+
+        let t =
+            output
+                .get_transform()
+                .ok_or(super::err::GetGeometry::Disabled(
+                    output_name.to_string(),
+                ))?;
+        Ok(t)
+    }
+
+    fn set_transform(
+        &mut self,
+        output_name: &str,
+        t: &Transform,
+    ) -> Result<(), BackendError> {
+        let transform_s = transform(t);
+        let err_f = |s: String| backend_call_err!(SetRotation, WlrRandr, s);
+        self.run_output(output_name, &["--transform", &transform_s], err_f)
+    }
+
+    // wlr-randr only takes coordinates, so the relation is worked out here
+    fn set_position(
+        &mut self,
+        output_name: &str,
+        pos: &Position,
+    ) -> Result<(), BackendError> {
+        let current = self.get_geometry(output_name)?;
+        let other = self
+            .output(&pos.output_s)
+            .ok_or(super::err::SetPosition::NoOutput(pos.output_s.clone()))?
+            .geometry()
+            .ok_or(super::err::SetPosition::RelativeDisabled(
+                pos.output_s.clone(),
+            ))?;
+
+        let new = current.placed(&pos.relation, &other);
+        self.set_coordinates(output_name, new.x, new.y)
+    }
+
+    fn set_coordinates(
+        &mut self,
+        output_name: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), BackendError> {
+        let pos_s = format!("{x},{y}");
+        let err_f = |s: String| backend_call_err!(SetCoordinates, WlrRandr, s);
+        self.run_output(output_name, &["--pos", &pos_s], err_f)
+    }
+
+    fn set_primary(&mut self, _output_name: &str) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_color_depth(
+        &mut self,
+        _output_name: &str,
+        _depth: Depth,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_hdr(
+        &mut self,
+        _output_name: &str,
+        _on: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_tearing(
+        &mut self,
+        _output_name: &str,
+        _allow: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale_filter(
+        &mut self,
+        _output_name: &str,
+        _filter: &Filter,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale(
+        &mut self,
+        output_name: &str,
+        scale: f64,
+    ) -> Result<(), BackendError> {
+        let scale_s = scale.to_string();
+        let err_f = |s: String| backend_call_err!(SetScale, WlrRandr, s);
+        self.run_output(output_name, &["--scale", &scale_s], err_f)
+    }
+
+    fn set_preferred_mode(
+        &mut self,
+        output_name: &str,
+        _res: &Resolution,
+    ) -> Result<(), BackendError> {
+        let err_f = |s: String| backend_call_err!(SetResolution, WlrRandr, s);
+        self.run_output(output_name, &["--preferred"], err_f)
+    }
+
+    fn set_subpixel(
+        &mut self,
+        _output_name: &str,
+        _subpixel: &Subpixel,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_power(
+        &mut self,
+        _output_name: &str,
+        _power: &PowerState,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_gamma(
+        &mut self,
+        _output_name: &str,
+        _gamma: &Gamma,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_brightness(
+        &mut self,
+        _output_name: &str,
+        _brightness: f64,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn get_workspaces(&mut self) -> Result<Vec<WorkspaceEntry>, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_workspace(
+        &mut self,
+        _output_name: &str,
+        _workspace: &str,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let err_f = |s: String| backend_call_err!(Enable, WlrRandr, s);
+        self.run_output(output_name, &["--on"], err_f)
+    }
+
+    fn disable(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let err_f = |s: String| backend_call_err!(Disable, WlrRandr, s);
+        self.run_output(output_name, &["--off"], err_f)
+    }
+
+    fn wait_for_change(&mut self) -> Result<(), BackendError> {
+        let names = |outputs: &[Output]| {
+            outputs.iter().map(|o| o.name.clone()).collect::<Vec<_>>()
+        };
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let new_outputs = get_state()?;
+            let changed = names(&new_outputs) != names(&self.outputs);
+            self.outputs = new_outputs;
+
+            if changed {
+                return Ok(());
+            }
+        }
+    }
+
+    // There is no wlroots protocol to move the pointer with
+    fn warp_pointer(&mut self, _output_name: &str) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::mirror;
+
+    // As printed by wlr-randr 0.4. HDMI-A-1 mirrors eDP-1 by sharing its
+    // position, DP-1 is disabled.
+    const OUTPUTS: &str = r#"[
+  {
+    "name": "eDP-1",
+    "description": "Sharp Corporation 0x14F9 (eDP-1)",
+    "make": "Sharp Corporation",
+    "model": "0x14F9",
+    "serial": null,
+    "physical_size": {"width": 290, "height": 190},
+    "enabled": true,
+    "modes": [
+      {"width": 2880, "height": 1920, "refresh": 120.000000,
+       "preferred": true, "current": true},
+      {"width": 2880, "height": 1920, "refresh": 60.000000,
+       "preferred": false, "current": false}
+    ],
+    "position": {"x": 0, "y": 0},
+    "transform": "normal",
+    "scale": 1.500000,
+    "adaptive_sync": true
+  },
+  {
+    "name": "HDMI-A-1",
+    "description": "Dell Inc. DELL P2419H 7KX1T23 (HDMI-A-1)",
+    "make": "Dell Inc.",
+    "model": "DELL P2419H",
+    "serial": "7KX1T23",
+    "physical_size": {"width": 530, "height": 300},
+    "enabled": true,
+    "modes": [
+      {"width": 1920, "height": 1080, "refresh": 60.000000,
+       "preferred": true, "current": true}
+    ],
+    "position": {"x": 0, "y": 0},
+    "transform": "flipped-90",
+    "scale": 1.000000,
+    "adaptive_sync": false
+  },
+  {
+    "name": "DP-1",
+    "description": "Goldstar Company Ltd LG HDR 4K (DP-1)",
+    "make": "Goldstar Company Ltd",
+    "model": "LG HDR 4K",
+    "serial": "0x0000B5E1",
+    "physical_size": {"width": 600, "height": 340},
+    "enabled": false,
+    "modes": [
+      {"width": 3840, "height": 2160, "refresh": 60.000000,
+       "preferred": true, "current": false}
+    ]
+  }
+]"#;
+
+    fn outputs() -> Vec<OutputEntry> {
+        let mut backend = Backend {
+            outputs: parse(OUTPUTS.as_bytes()).unwrap(),
+        };
+        backend.get_outputs().unwrap()
+    }
+
+    #[test]
+    fn parses_scale_and_adaptive_sync() {
+        let outputs = outputs();
+
+        let scales: Vec<_> = outputs
+            .iter()
+            .map(|o| o.geometry.as_ref().map(|g| g.scale))
+            .collect();
+        assert_eq!(scales, [Some(1.5), Some(1.0), None]);
+
+        let vrr: Vec<_> = outputs.iter().map(|o| o.adaptive_sync).collect();
+        assert_eq!(vrr, [Some(true), Some(false), None]);
+    }
+
+    #[test]
+    fn describes_by_make_model_and_serial() {
+        let descriptions: Vec<_> =
+            outputs().into_iter().map(|o| o.description).collect();
+        assert_eq!(
+            descriptions,
+            [
+                Some("Sharp Corporation 0x14F9".to_string()),
+                Some("Dell Inc. DELL P2419H 7KX1T23".to_string()),
+                Some("Goldstar Company Ltd LG HDR 4K 0x0000B5E1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn geometry_is_logical() {
+        let outputs = outputs();
+
+        let edp = outputs[0].geometry.as_ref().unwrap();
+        assert_eq!((edp.width, edp.height), (1920, 1280));
+
+        let hdmi = outputs[1].geometry.as_ref().unwrap();
+        assert_eq!((hdmi.width, hdmi.height), (1080, 1920));
+        assert_eq!(hdmi.rotation, Rotation::Left);
+    }
+
+    #[test]
+    fn disabled_outputs_have_no_geometry() {
+        let outputs = outputs();
+        let dp = &outputs[2];
+
+        assert!(dp.connected && !dp.enabled);
+        assert!(dp.geometry.is_none());
+    }
+
+    #[test]
+    fn outputs_sharing_a_position_are_mirrored() {
+        let outputs = outputs();
+        let mirrored = mirror::mirrored_with(&outputs, &outputs[0]);
+
+        let names: Vec<_> = mirrored.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["HDMI-A-1"]);
+    }
+
+    #[test]
+    fn transform_of_each_rotation() {
+        let t = |rotation, flipped| transform(&Transform { rotation, flipped });
+
+        assert_eq!(t(Rotation::Normal, false), "normal");
+        assert_eq!(t(Rotation::Left, false), "90");
+        assert_eq!(t(Rotation::Normal, true), "flipped");
+        assert_eq!(t(Rotation::Right, true), "flipped-270");
+    }
+
+    #[test]
+    fn reads_flipped_transforms() {
+        let mut backend = Backend {
+            outputs: parse(OUTPUTS.as_bytes()).unwrap(),
+        };
+        let t = backend.get_transform("HDMI-A-1").unwrap();
+
+        assert_eq!(t.rotation, Rotation::Left);
+        assert!(t.flipped);
+    }
+}
@@ -1,16 +1,32 @@
 use std::collections::VecDeque;
-use std::io::BufRead;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::time::Duration;
 
+use crate::action::depth::{bpc, Depth};
+use crate::action::fit::FitMode;
+use crate::action::gamma::Gamma;
+use crate::action::panning::Panning;
 use crate::action::position::Position;
 use crate::action::position::Relation;
+use crate::action::power::PowerState;
 use crate::action::rate::Rate;
 use crate::action::resolution::Resolution;
 use crate::action::rotate::Rotation;
-use crate::action::Operation;
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
 use crate::backend::Error as BackendError;
 use crate::backend_call as backend_call_err;
 
-use super::{OutputEntry, RateEntry, ResolutionEntry};
+use super::edid;
+use super::screen_size::{self, LayoutChange};
+use super::{
+    find_output, unique_names, Capabilities, DisplayBackend, Geometry, Named,
+    OutputEntry, OutputLayout, RateEntry, ResolutionEntry, WorkspaceEntry,
+};
+
+// The CLI can not notify us of changes, so `wait_for_change` polls
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 // Structs to parse the xrandr output into
 #[derive(Debug, Clone)]
@@ -19,19 +35,41 @@ struct Mode {
     height: u32,
     rate: f64,
     current: bool,
+    preferred: bool,
+    interlaced: bool,
+}
+// The second word of an output line. Some drivers (e.g. of VMs and docks)
+// report `unknown connection` for outputs that work fine, so those count as
+// connected, modes and all.
+#[derive(Debug, Clone, PartialEq)]
+enum Connection {
+    Connected,
+    Disconnected,
+    Unknown,
 }
 #[derive(Debug, Clone)]
 struct Output {
     name: String,
-    connected: bool,
+    connection: Connection,
     enabled: bool,
+    primary: bool,
+    geometry: Option<Geometry>,
+    // Raw, as listed by `--props`. Only queried by `get_edid`.
+    edid: Option<Vec<u8>>,
     modes: Vec<Mode>,
 }
 
+impl Named for Output {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 /// **NOTE:** this is an experimental backend for testing and is not
 /// fit for everyday use. The parser it relies on is very unga bunga.
 struct XrandrState {
     outputs: Vec<Output>,
+    max_screen_size: Option<(u32, u32)>,
 }
 
 // The modes are not space-separated, since the preferred marker can be
@@ -45,9 +83,8 @@ fn parse_mode_line(line: &str) -> (&str, Vec<&str>) {
 
     let mut rates: Vec<&str> = Vec::new();
     let line = line.trim();
-    let first_space = line.find(' ').unwrap();
-    let res = line.get(0..first_space).unwrap();
-    let line = line.get(first_space..).unwrap().trim();
+    let (res, line) = line.split_once(' ').unwrap_or((line, ""));
+    let line = line.trim();
 
     let mut start = 0;
     let mut i = 0;
@@ -68,22 +105,107 @@ fn parse_mode_line(line: &str) -> (&str, Vec<&str>) {
     (res, rates)
 }
 
+// Parses what comes after the connection state in the output line, like:
+// `primary 1080x1920+1920+0 left (normal left inverted right ...) ...`
+// The geometry (and rotation, if not normal) is only there when enabled.
+fn parse_geometry(words: &VecDeque<&str>) -> Option<Geometry> {
+    let mut words = words.iter().skip_while(|w| **w == "primary");
+
+    let mut parts = words.next()?.split(['x', '+']);
+    let mut num = || parts.next()?.parse::<i32>().ok();
+    let (width, height) = (num()?, num()?);
+    let (x, y) = (num()?, num()?);
+
+    let rotation = match words.next() {
+        Some(&"left") => Rotation::Left,
+        Some(&"right") => Rotation::Right,
+        Some(&"inverted") => Rotation::Inverted,
+        _ => Rotation::Normal,
+    };
+
+    Some(Geometry {
+        x,
+        y,
+        width: width as u32,
+        height: height as u32,
+        rotation,
+        scale: 1.0,
+    })
+}
+
+// With `--props`, the properties of an output are listed between its line
+// and its modes, indented by a tab. Values that do not fit on the line of
+// their name follow it, indented by two tabs. The EDID is one of those:
+// ```
+// \tEDID:
+// \t\t00ffffffffffff0010ac...
+// ```
+// Takes all property lines, and returns the EDID if there was one.
+fn parse_props(lines: &mut VecDeque<String>) -> Option<Vec<u8>> {
+    let mut hex = String::new();
+    let mut in_edid = false;
+
+    while lines.front().is_some_and(|l| l.starts_with('\t')) {
+        let line = lines.pop_front().unwrap(); // unwrap: checked above
+
+        if !line.starts_with("\t\t") {
+            in_edid = line.trim() == "EDID:";
+        } else if in_edid {
+            hex.push_str(line.trim());
+        }
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()
+        .filter(|bytes| !bytes.is_empty())
+}
+
+// The screen line looks like:
+// `Screen 0: minimum 8 x 8, current 3840 x 1080, maximum 32767 x 32767`
+fn parse_max_screen_size(line: &str) -> Option<(u32, u32)> {
+    let max = line.split("maximum").nth(1)?;
+    let (w, h) = max.split_once('x')?;
+
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
 impl XrandrState {
-    // The new() constructor calls `xrandr` and parses the result
-    // TODO: this is very rough for now, should have many more checks
     fn new() -> Result<Self, BackendError> {
+        Self::query(false)
+    }
+
+    // Also lists the properties of the outputs, which takes a lot longer
+    fn with_props() -> Result<Self, BackendError> {
+        Self::query(true)
+    }
+
+    // Calls `xrandr` and parses the result
+    // TODO: this is very rough for now, should have many more checks
+    fn query(props: bool) -> Result<Self, BackendError> {
         let mut cmd = std::process::Command::new("xrandr");
-        let res = cmd.output().map_err(|e| {
-            backend_call_err!(GetOutputs, XrandrCLI, e.to_string())
+        if props {
+            cmd.arg("--props");
+        }
+        let res = cmd.output().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => missing_binary(&cmd),
+            _ => backend_call_err!(GetOutputs, XrandrCLI, e.to_string()),
         })?;
 
-        let mut lines = res
+        let lines = res
             .stdout
             .lines()
             .collect::<Result<VecDeque<String>, _>>()
             .unwrap(); // unrwap: error if not utf-8, should never happen
 
+        Ok(Self::parse(lines))
+    }
+
+    // Parses the output of `xrandr`, by line
+    fn parse(mut lines: VecDeque<String>) -> Self {
         let mut outputs: Vec<Output> = Vec::new();
+        let mut max_screen_size = None;
         loop {
             let line = lines.pop_front();
             if line.is_none() {
@@ -92,12 +214,23 @@ impl XrandrState {
             let line = line.unwrap(); // see above
 
             if line.get(..6) == Some("Screen") {
+                max_screen_size = parse_max_screen_size(&line);
                 continue;
             }
 
             let mut words = line.split(' ').collect::<VecDeque<&str>>();
             let name = words.pop_front().unwrap().to_string();
-            let connected = words.pop_front() == Some("connected");
+            let connection = match words.pop_front() {
+                Some("connected") => Connection::Connected,
+                Some("unknown") if words.front() == Some(&"connection") => {
+                    words.pop_front();
+                    Connection::Unknown
+                }
+                _ => Connection::Disconnected,
+            };
+            let primary = words.front() == Some(&"primary");
+            let geometry = parse_geometry(&words);
+            let edid = parse_props(&mut lines);
 
             let mut enabled = false;
             let mut modes: Vec<Mode> = Vec::new();
@@ -108,51 +241,130 @@ impl XrandrState {
                 let mode_line = lines.pop_front().unwrap();
                 let (res, rates) = parse_mode_line(&mode_line);
 
-                let width: u32 =
-                    res.split('x').next().unwrap().parse().unwrap();
-                let height: u32 =
-                    res.split('x').nth(1).unwrap().parse().unwrap();
+                // Interlaced modes are named like `1920x1080i`, and
+                // doublescan modes like `320x240d`
+                let res_interlaced = res.ends_with('i');
+                let res = res.trim_end_matches(['i', 'd']);
+
+                // Modes added with `xrandr --newmode` can have any name,
+                // like `1920x1080_60.00` from `cvt`. Those are not listed,
+                // as they can not be set by their size.
+                let size = res.split_once('x').and_then(|(w, h)| {
+                    Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?))
+                });
 
                 for rate_s in rates {
                     let rate_stripped =
-                        rate_s.replace(&['*', '+', ' '][..], "");
-                    let rate: f64 = rate_stripped.parse().unwrap();
+                        rate_s.replace(&['*', '+', ' ', 'i', 'd'][..], "");
                     let current = rate_s.contains('*');
+                    let preferred = rate_s.contains('+');
+                    let interlaced = res_interlaced || rate_s.contains('i');
                     if current {
                         enabled = true;
                     }
 
+                    let (Some((width, height)), Ok(rate)) =
+                        (size, rate_stripped.parse::<f64>())
+                    else {
+                        continue;
+                    };
+
                     modes.push(Mode {
                         width,
                         height,
                         rate,
                         current,
+                        preferred,
+                        interlaced,
                     });
                 }
             }
             outputs.push(Output {
                 name,
-                connected,
+                connection,
                 enabled,
+                primary,
+                geometry,
+                edid,
                 modes,
             });
         }
-        Ok(XrandrState { outputs })
+        XrandrState {
+            outputs,
+            max_screen_size,
+        }
     }
 }
 
 pub struct Backend {
     state: XrandrState,
+    // Changes held back until `commit_batch`
+    batch: Option<XrandrCmd>,
 }
 
 impl Backend {
     pub fn new() -> Result<Self, BackendError> {
         Ok(Self {
             state: XrandrState::new()?,
+            batch: None,
         })
     }
 }
 
+// The `--output` segments of one xrandr call. Changing several outputs in
+// one call does not flicker, and either all of it or none of it happens.
+// Changes to the same output are kept together, in the order they were made.
+#[derive(Debug, Default)]
+pub(super) struct XrandrCmd {
+    outputs: Vec<(String, Vec<String>)>,
+}
+
+impl XrandrCmd {
+    pub fn output(&mut self, name: &str, args: &[&str]) -> &mut Self {
+        let i = match self.outputs.iter().position(|(n, _)| n == name) {
+            Some(i) => i,
+            None => {
+                self.outputs.push((name.to_string(), Vec::new()));
+                self.outputs.len() - 1
+            }
+        };
+        self.outputs[i].1.extend(args.iter().map(|a| a.to_string()));
+        self
+    }
+
+    // Whether `flag` was already given for the output
+    fn sets(&self, name: &str, flag: &str) -> bool {
+        self.outputs
+            .iter()
+            .any(|(n, args)| n == name && args.iter().any(|a| a == flag))
+    }
+
+    fn args(&self) -> Vec<String> {
+        self.outputs
+            .iter()
+            .flat_map(|(name, args)| {
+                ["--output".to_string(), name.clone()]
+                    .into_iter()
+                    .chain(args.iter().cloned())
+            })
+            .collect()
+    }
+
+    fn run(
+        &self,
+        err_f: fn(s: String) -> BackendError,
+    ) -> Result<(), BackendError> {
+        let mut cmd = std::process::Command::new("xrandr");
+        run_cmd_and_check(cmd.args(self.args()), err_f)
+    }
+}
+
+impl fmt::Display for XrandrCmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "xrandr {}", self.args().join(" "))
+    }
+}
+
 // Tranform to a string that can be understood by xrandrs CLI
 pub trait Xcl {
     fn xcl(&self) -> String;
@@ -175,40 +387,140 @@ impl Xcl for Rotation {
     }
 }
 
-impl Xcl for Relation {
+impl Xcl for xrandr::Relation {
     fn xcl(&self) -> String {
         match self {
-            Relation::LeftOf => String::from("--left-of"),
-            Relation::RightOf => String::from("--right-of"),
-            Relation::Above => String::from("--above"),
-            Relation::Below => String::from("--below"),
-            Relation::SameAs => String::from("--same-as"),
+            xrandr::Relation::LeftOf => String::from("--left-of"),
+            xrandr::Relation::RightOf => String::from("--right-of"),
+            xrandr::Relation::Above => String::from("--above"),
+            xrandr::Relation::Below => String::from("--below"),
+            xrandr::Relation::SameAs => String::from("--same-as"),
         }
     }
 }
 
 const RATE_EPSILON: f64 = 0.01; // xrandr rates are rounded to 2 decimals
 
-impl super::DisplayBackend for Backend {
-    fn supported_operations(&mut self, output: &OutputEntry) -> Vec<Operation> {
-        match (output.connected, output.enabled) {
-            // If the output is not connected, just give the option
-            // to disable/enable it. (X allows you to unplug an output
-            // while still having it as active)
-            (false, _) => vec![Operation::Disable],
-
-            // If the output is connected but disabled, only show enable option
-            (_, false) => vec![Operation::Enable],
-
-            // Otherwise, list all except enable
-            _ => vec![
-                Operation::Disable,
-                Operation::SetPrimary,
-                Operation::ChangeRes(Resolution::default()),
-                Operation::Position(Position::default()),
-                Operation::ChangeRate(Rate::default()),
-                Operation::Rotate(Rotation::default()),
-            ],
+// The `--transform` that shows `source` on an output running at `target`,
+// see `FitMode`. It maps the pixels of the output to the screen, relative to
+// where the output is, and is given row by row.
+pub(super) fn fit_matrix(
+    fit: &FitMode,
+    source: &Resolution,
+    target: &Resolution,
+) -> [f64; 9] {
+    let (src_w, src_h) = (f64::from(source.width), f64::from(source.height));
+    let (dst_w, dst_h) = (f64::from(target.width), f64::from(target.height));
+    let (sx, sy) = (src_w / dst_w, src_h / dst_h);
+
+    let (sx, sy) = match fit {
+        FitMode::Off => return [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        FitMode::Stretch => (sx, sy),
+        // Scaling by the larger factor leaves room around the source, by
+        // the smaller one cuts off the source on two sides
+        FitMode::Fit => (sx.max(sy), sx.max(sy)),
+        FitMode::Fill => (sx.min(sy), sx.min(sy)),
+    };
+
+    // Centered, so that the room or the cut is the same on both sides
+    let dx = (src_w - dst_w * sx) / 2.0;
+    let dy = (src_h - dst_h * sy) / 2.0;
+    [sx, 0.0, dx, 0.0, sy, dy, 0.0, 0.0, 1.0]
+}
+
+// One xrandr call that sets up every output. Also used by libxrandr, so
+// that both X backends export the same thing.
+pub(super) fn xrandr_command(layout: &[OutputLayout]) -> String {
+    let mut cmd = XrandrCmd::default();
+
+    for o in layout {
+        if !o.enabled {
+            cmd.output(&o.name, &["--off"]);
+            continue;
+        }
+
+        if let Some(res) = &o.resolution {
+            cmd.output(&o.name, &["--mode", &res.xcl()]);
+        }
+        if let Some(rate) = o.rate {
+            cmd.output(&o.name, &["--rate", &format!("{rate:.2}")]);
+        }
+        let pos_s = format!("{}x{}", o.x, o.y);
+        cmd.output(&o.name, &["--pos", &pos_s]);
+        cmd.output(&o.name, &["--rotate", &o.rotation.xcl()]);
+        if o.primary {
+            cmd.output(&o.name, &["--primary"]);
+        }
+        if let Some(g) = o.gamma {
+            let gamma_s = format!("{}:{}:{}", g.red, g.green, g.blue);
+            cmd.output(&o.name, &["--gamma", &gamma_s]);
+        }
+        if let Some(brightness) = o.brightness {
+            cmd.output(&o.name, &["--brightness", &brightness.to_string()]);
+        }
+    }
+
+    cmd.to_string()
+}
+
+impl Backend {
+    // Runs xrandr with `args` for the output, or holds them back when
+    // batching
+    fn run_output(
+        &mut self,
+        output_name: &str,
+        args: &[&str],
+        err_f: fn(s: String) -> BackendError,
+    ) -> Result<(), BackendError> {
+        // xrandr takes outputs by name only, and would change the first of
+        // the outputs that share one
+        let output = find_output(&self.state.outputs, output_name);
+        if output.is_some_and(|o| o.name != output_name) {
+            return Err(err_f(format!(
+                "xrandr can not tell {output_name} apart from other outputs \
+                 with the same name"
+            )));
+        }
+
+        match &mut self.batch {
+            Some(batch) => {
+                batch.output(output_name, args);
+                Ok(())
+            }
+            None => XrandrCmd::default().output(output_name, args).run(err_f),
+        }
+    }
+
+    fn check_screen_size(
+        &mut self,
+        output_name: &str,
+        change: LayoutChange,
+    ) -> Result<(), BackendError> {
+        match self.state.max_screen_size {
+            Some(max) => {
+                let outputs = self.get_outputs()?;
+                screen_size::check(&outputs, output_name, change, max)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl DisplayBackend for Backend {
+    fn capabilities(&mut self) -> Capabilities {
+        Capabilities {
+            backend: "xrandr_cli",
+            primary: true,
+            mirror: true,
+            color_depth: true,
+            gamma: true,
+            power: true,
+            power_all: true,
+            panning: true,
+            projector_fit: true,
+            export: true,
+            edid: true,
+            ..Default::default()
         }
     }
 
@@ -219,6 +531,7 @@ impl super::DisplayBackend for Backend {
             Relation::Below,
             Relation::Above,
             Relation::SameAs,
+            Relation::CenteredOn,
         ]
     }
 
@@ -227,46 +540,65 @@ impl super::DisplayBackend for Backend {
             .state
             .outputs
             .iter()
-            .map(|o| OutputEntry {
-                name: o.name.clone(),
-                connected: o.connected,
+            .zip(unique_names(&self.state.outputs))
+            .map(|(o, name)| OutputEntry {
+                name,
+                connected: o.connection != Connection::Disconnected,
                 enabled: o.enabled,
+                primary: o.primary,
+                // Listing the EDIDs through xrandr is slow, and this runs
+                // on every refresh. The kernel has the same bytes, when the
+                // driver names its outputs after the DRM connectors.
+                edid_hash: edid::from_sysfs(&o.name).map(|e| edid::hash(&e)),
+                description: None,
+                provider: None,
+                adaptive_sync: None,
+                geometry: o.geometry.clone(),
             })
             .collect();
 
         Ok(entries)
     }
 
+    // The geometry comes from the output line, e.g. `1920x1080+0+0 left`
+    fn get_geometry(
+        &mut self,
+        output_name: &str,
+    ) -> Result<Geometry, BackendError> {
+        let output = find_output(&self.state.outputs, output_name).ok_or(
+            super::err::GetGeometry::NoOutput(output_name.to_string()),
+        )?;
+
+        let geometry = output.geometry.clone().ok_or(
+            super::err::GetGeometry::Disabled(output_name.to_string()),
+        )?;
+        Ok(geometry)
+    }
+
     fn get_resolutions(
         &mut self,
         output_name: &str,
     ) -> Result<Vec<ResolutionEntry>, BackendError> {
-        let output = self
-            .state
-            .outputs
-            .iter()
-            .find(|o| o.name == output_name)
-            .ok_or(super::err::GetResolutions::NoOutput(
-                output_name.to_string(),
-            ))?;
+        let output = find_output(&self.state.outputs, output_name).ok_or(
+            super::err::GetResolutions::NoOutput(output_name.to_string()),
+        )?;
 
-        let mut entries = output
-            .modes
-            .iter()
-            .map(|m| ResolutionEntry {
-                val: Resolution {
-                    width: m.width,
-                    height: m.height,
-                },
-                current: m.current,
-            })
-            .collect::<Vec<ResolutionEntry>>();
+        let preferred = output.modes.iter().find(|m| m.preferred);
 
-        entries.dedup_by(|a, b| {
-            a.val.width == b.val.width && a.val.height == b.val.height
+        // Every rate is its own mode, and the interlaced version of a
+        // resolution is on its own line, `merge` makes those one entry
+        let entries = output.modes.iter().map(|m| ResolutionEntry {
+            val: Resolution {
+                width: m.width,
+                height: m.height,
+            },
+            current: m.current,
+            preferred: preferred
+                .is_some_and(|p| m.width == p.width && m.height == p.height),
+            interlaced: m.interlaced,
         });
 
-        Ok(entries)
+        Ok(ResolutionEntry::merge(entries))
     }
 
     fn set_resolution(
@@ -274,22 +606,35 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         res: &Resolution,
     ) -> Result<(), BackendError> {
-        let mut cmd = std::process::Command::new("xrandr");
-        let cmd = cmd.args(["--output", output_name, "--mode", &res.xcl()]);
+        self.check_screen_size(output_name, LayoutChange::Resize(res))?;
+
+        // Resolutions that only exist interlaced need the `i` in the name
+        let interlaced = self
+            .get_resolutions(output_name)?
+            .iter()
+            .any(|e| &e.val == res && e.interlaced);
+        let mode_s = if interlaced {
+            format!("{}i", res.xcl())
+        } else {
+            res.xcl()
+        };
 
         let err_f = |s: String| backend_call_err!(SetResolution, XrandrCLI, s);
-        run_cmd_and_check(cmd, err_f)
+        self.run_output(output_name, &["--mode", &mode_s], err_f)?;
+
+        // `set_rate` keeps the current resolution, which just changed.
+        // When batching, that happens in `commit_batch`.
+        if self.batch.is_none() {
+            self.state = XrandrState::new()?;
+        }
+        Ok(())
     }
 
     fn get_rates(
         &mut self,
         output_name: &str,
     ) -> Result<Vec<RateEntry>, BackendError> {
-        let output = self
-            .state
-            .outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let output = find_output(&self.state.outputs, output_name)
             .ok_or(super::err::GetRates::NoOutput(output_name.to_string()))?;
 
         let current_mode = output
@@ -308,9 +653,9 @@ impl super::DisplayBackend for Backend {
                 val: m.rate,
                 current: (m.rate - current_mode.rate).abs() < RATE_EPSILON,
             })
-            .collect();
+            .collect::<Vec<RateEntry>>();
 
-        Ok(entries)
+        Ok(RateEntry::merge(entries))
     }
 
     fn set_rate(
@@ -318,29 +663,28 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         rate: Rate,
     ) -> Result<(), BackendError> {
-        let mut cmd = std::process::Command::new("xrandr");
-        let cur_res = self
-            .state
-            .outputs
-            .iter()
-            .find(|o| o.name == output_name)
+        let err_f = |s: String| backend_call_err!(SetRate, XrandrCLI, s);
+        let rate_s = rate.to_string();
+
+        // The mode that is about to be set, rather than the current one
+        if self
+            .batch
+            .as_ref()
+            .is_some_and(|b| b.sets(output_name, "--mode"))
+        {
+            return self.run_output(output_name, &["--rate", &rate_s], err_f);
+        }
+
+        let cur_res = find_output(&self.state.outputs, output_name)
             .ok_or(super::err::SetRate::NoOutput(output_name.to_string()))?
             .modes
             .iter()
             .find(|m| m.current)
             .ok_or(super::err::SetRate::NoMode(output_name.to_string()))?;
 
-        let cmd = cmd.args([
-            "--output",
-            output_name,
-            "--mode",
-            &format!("{}x{}", cur_res.width, cur_res.height),
-            "--rate",
-            &rate.to_string(),
-        ]);
-
-        let err_f = |s: String| backend_call_err!(SetRate, XrandrCLI, s);
-        run_cmd_and_check(cmd, err_f)
+        let mode_s = format!("{}x{}", cur_res.width, cur_res.height);
+        let args = ["--mode", &mode_s, "--rate", &rate_s];
+        self.run_output(output_name, &args, err_f)
     }
 
     fn set_rotation(
@@ -348,12 +692,10 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         rotation: &Rotation,
     ) -> Result<(), BackendError> {
-        let mut cmd = std::process::Command::new("xrandr");
-        let cmd =
-            cmd.args(["--output", output_name, "--rotate", &rotation.xcl()]);
+        self.check_screen_size(output_name, LayoutChange::Rotate(rotation))?;
 
         let err_f = |s: String| backend_call_err!(SetRotation, XrandrCLI, s);
-        run_cmd_and_check(cmd, err_f)
+        self.run_output(output_name, &["--rotate", &rotation.xcl()], err_f)
     }
 
     fn set_position(
@@ -361,39 +703,279 @@ impl super::DisplayBackend for Backend {
         output_name: &str,
         pos: &Position,
     ) -> Result<(), BackendError> {
-        let mut cmd = std::process::Command::new("xrandr");
-        let cmd = cmd.args([
-            "--output",
-            output_name,
-            &pos.relation.xcl(),
-            &pos.output_s,
-        ]);
+        self.check_screen_size(output_name, LayoutChange::Place(pos))?;
+
+        let Some(relation) = pos.relation.xrandr() else {
+            let (x, y) = super::centered_on(self, output_name, &pos.output_s)?;
+            return self.set_coordinates(output_name, x, y);
+        };
 
         let err_f = |s: String| backend_call_err!(SetPosition, XrandrCLI, s);
-        run_cmd_and_check(cmd, err_f)
+        let relation_s = relation.xcl();
+        self.run_output(output_name, &[&relation_s, &pos.output_s], err_f)
+    }
+
+    fn set_coordinates(
+        &mut self,
+        output_name: &str,
+        x: i32,
+        y: i32,
+    ) -> Result<(), BackendError> {
+        let pos_s = format!("{x}x{y}");
+        let err_f = |s: String| backend_call_err!(SetCoordinates, XrandrCLI, s);
+        self.run_output(output_name, &["--pos", &pos_s], err_f)
     }
 
     fn set_primary(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let err_f = |s: String| backend_call_err!(SetPrimary, XrandrCLI, s);
+        self.run_output(output_name, &["--primary"], err_f)
+    }
+
+    fn set_color_depth(
+        &mut self,
+        output_name: &str,
+        depth: Depth,
+    ) -> Result<(), BackendError> {
+        let bpc_s = bpc(depth).to_string();
+        let err_f = |s: String| backend_call_err!(SetColorDepth, XrandrCLI, s);
+        self.run_output(output_name, &["--set", "max bpc", &bpc_s], err_f)
+    }
+
+    fn set_hdr(
+        &mut self,
+        _output_name: &str,
+        _on: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_tearing(
+        &mut self,
+        _output_name: &str,
+        _allow: bool,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale_filter(
+        &mut self,
+        _output_name: &str,
+        _filter: &Filter,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_scale(
+        &mut self,
+        output_name: &str,
+        scale: f64,
+    ) -> Result<(), BackendError> {
         let mut cmd = std::process::Command::new("xrandr");
-        let cmd = cmd.args(["--output", output_name, "--primary"]);
+        let scale_s = format!("{scale}x{scale}");
+        let cmd = cmd.args(["--output", output_name, "--scale", &scale_s]);
 
-        let err_f = |s: String| backend_call_err!(SetPrimary, XrandrCLI, s);
+        let err_f = |s: String| backend_call_err!(SetScale, XrandrCLI, s);
         run_cmd_and_check(cmd, err_f)
     }
 
-    fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
+    // `--auto` picks the preferred mode and rate
+    fn set_preferred_mode(
+        &mut self,
+        output_name: &str,
+        res: &Resolution,
+    ) -> Result<(), BackendError> {
+        self.check_screen_size(output_name, LayoutChange::Resize(res))?;
+
         let mut cmd = std::process::Command::new("xrandr");
         let cmd = cmd.args(["--output", output_name, "--auto"]);
 
-        let err_f = |s: String| backend_call_err!(Enable, XrandrCLI, s);
+        let err_f = |s: String| backend_call_err!(SetResolution, XrandrCLI, s);
         run_cmd_and_check(cmd, err_f)
     }
 
-    fn disable(&mut self, output_name: &str) -> Result<(), BackendError> {
-        let mut cmd = std::process::Command::new("xrandr");
-        let cmd = cmd.args(["--output", output_name, "--off"]);
+    fn set_panning(
+        &mut self,
+        output_name: &str,
+        panning: &Panning,
+    ) -> Result<(), BackendError> {
+        self.check_screen_size(output_name, LayoutChange::Pan(panning))?;
+
+        let err_f = |s: String| backend_call_err!(SetPanning, XrandrCLI, s);
+        let panning_s = panning.to_string();
+        self.run_output(output_name, &["--panning", &panning_s], err_f)
+    }
 
+    fn set_projector_fit(
+        &mut self,
+        output_name: &str,
+        source: &Resolution,
+        fit: &FitMode,
+    ) -> Result<(), BackendError> {
+        let err_f =
+            |s: String| backend_call_err!(SetProjectorFit, XrandrCLI, s);
+        let transform_s = match fit {
+            FitMode::Off => String::from("none"),
+            _ => {
+                let target = self
+                    .get_resolutions(output_name)?
+                    .into_iter()
+                    .find(|r| r.current)
+                    .map(|r| r.val)
+                    .ok_or(super::err::SetProjectorFit::NoMode(
+                        output_name.to_string(),
+                    ))?;
+                let matrix = fit_matrix(fit, source, &target);
+                let values: Vec<_> =
+                    matrix.iter().map(f64::to_string).collect();
+                values.join(",")
+            }
+        };
+
+        self.run_output(output_name, &["--transform", &transform_s], err_f)
+    }
+
+    fn set_subpixel(
+        &mut self,
+        _output_name: &str,
+        _subpixel: &Subpixel,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    // DPMS is not per output in X, this affects all of them
+    fn set_power(
+        &mut self,
+        output_name: &str,
+        power: &PowerState,
+    ) -> Result<(), BackendError> {
+        if !self.get_outputs()?.iter().any(|o| o.name == output_name) {
+            let name = output_name.to_string();
+            return Err(super::err::SetPower::NoOutput(name).into());
+        }
+
+        let level = match power {
+            PowerState::On => "on",
+            PowerState::Standby => "standby",
+            PowerState::Off => "off",
+        };
+
+        let mut cmd = std::process::Command::new("xset");
+        let cmd = cmd.args(["dpms", "force", level]);
+
+        let err_f = |s: String| backend_call_err!(SetPower, XrandrCLI, s);
+        run_cmd_and_check(cmd, err_f)
+    }
+
+    // xrandr keeps the current brightness when setting the gamma,
+    // and the other way around
+    fn set_gamma(
+        &mut self,
+        output_name: &str,
+        gamma: &Gamma,
+    ) -> Result<(), BackendError> {
+        let gamma_s = format!("{}:{}:{}", gamma.red, gamma.green, gamma.blue);
+        let err_f = |s: String| backend_call_err!(SetGamma, XrandrCLI, s);
+        self.run_output(output_name, &["--gamma", &gamma_s], err_f)
+    }
+
+    fn set_brightness(
+        &mut self,
+        output_name: &str,
+        brightness: f64,
+    ) -> Result<(), BackendError> {
+        let brightness_s = brightness.to_string();
+        let err_f = |s: String| backend_call_err!(SetGamma, XrandrCLI, s);
+        self.run_output(output_name, &["--brightness", &brightness_s], err_f)
+    }
+
+    fn get_edid(&mut self, output_name: &str) -> Result<Vec<u8>, BackendError> {
+        let state = XrandrState::with_props()?;
+        let output = find_output(&state.outputs, output_name)
+            .ok_or(super::err::GetEdid::NoOutput(output_name.to_string()))?;
+
+        let edid = output
+            .edid
+            .clone()
+            .ok_or(super::err::GetEdid::NoEdid(output_name.to_string()))?;
+        Ok(edid)
+    }
+
+    fn get_workspaces(&mut self) -> Result<Vec<WorkspaceEntry>, BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn set_workspace(
+        &mut self,
+        _output_name: &str,
+        _workspace: &str,
+    ) -> Result<(), BackendError> {
+        Err(BackendError::Unsupported)
+    }
+
+    fn enable(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let err_f = |s: String| backend_call_err!(Enable, XrandrCLI, s);
+        self.run_output(output_name, &["--auto"], err_f)
+    }
+
+    fn disable(&mut self, output_name: &str) -> Result<(), BackendError> {
         let err_f = |s: String| backend_call_err!(Disable, XrandrCLI, s);
+        self.run_output(output_name, &["--off"], err_f)
+    }
+
+    fn wait_for_change(&mut self) -> Result<(), BackendError> {
+        let connected = |state: &XrandrState| {
+            state
+                .outputs
+                .iter()
+                .map(|o| (o.name.clone(), o.connection.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let new_state = XrandrState::new()?;
+            let changed = connected(&new_state) != connected(&self.state);
+            self.state = new_state;
+
+            if changed {
+                return Ok(());
+            }
+        }
+    }
+
+    fn layout_command(&mut self) -> Result<String, BackendError> {
+        Ok(xrandr_command(&self.get_layout()?))
+    }
+
+    fn begin_batch(&mut self) {
+        self.batch.get_or_insert_with(XrandrCmd::default);
+    }
+
+    fn commit_batch(&mut self) -> Result<(), BackendError> {
+        let Some(batch) = self.batch.take() else {
+            return Ok(());
+        };
+        if batch.outputs.is_empty() {
+            return Ok(());
+        }
+
+        batch.run(|s| backend_call_err!(CommitBatch, XrandrCLI, s))?;
+        self.state = XrandrState::new()?;
+        Ok(())
+    }
+
+    fn discard_batch(&mut self) {
+        self.batch = None;
+    }
+
+    // xrandr itself can not move the pointer
+    fn warp_pointer(&mut self, output_name: &str) -> Result<(), BackendError> {
+        let (x, y) = self.get_geometry(output_name)?.center();
+
+        let mut cmd = std::process::Command::new("xdotool");
+        let cmd = cmd.args(["mousemove", &x.to_string(), &y.to_string()]);
+
+        let err_f = |s: String| backend_call_err!(WarpPointer, XrandrCLI, s);
         run_cmd_and_check(cmd, err_f)
     }
 }
@@ -401,13 +983,20 @@ impl super::DisplayBackend for Backend {
 // Helper function to improve the readibility of the error handling in the
 // interface functions above. Relies on the fact that we only put strings
 // inside the errors for this backend.
-fn run_cmd_and_check(
+// Commands only fail to start like this when the program is not installed
+pub(super) fn missing_binary(cmd: &std::process::Command) -> BackendError {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    BackendError::MissingBinary(program)
+}
+
+pub(super) fn run_cmd_and_check(
     cmd: &mut std::process::Command,
     err_f: fn(s: String) -> BackendError,
 ) -> Result<(), BackendError> {
-    let res = cmd
-        .output()
-        .map_err(|_| err_f("Could not execute command".to_string()))?;
+    let res = cmd.output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => missing_binary(cmd),
+        _ => err_f("Could not execute command".to_string()),
+    })?;
 
     if res.status.success() {
         Ok(())
@@ -417,3 +1006,298 @@ fn run_cmd_and_check(
         Err(err_f(stderr))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::gamma::Gamma;
+    use crate::profile::Profile;
+
+    const LAPTOP: Resolution = Resolution {
+        width: 1280,
+        height: 800,
+    };
+    const PROJECTOR: Resolution = Resolution {
+        width: 1920,
+        height: 1080,
+    };
+
+    fn assert_matrix(actual: [f64; 9], expected: [f64; 9]) {
+        let close = actual
+            .iter()
+            .zip(expected)
+            .all(|(a, e)| (a - e).abs() < 1e-9);
+        assert!(close, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn fit_adds_bars_on_the_sides() {
+        // Scaled to show all 800 rows, leaving about 71 px of bars on both
+        // sides of the 1280 columns
+        let s = 800.0 / 1080.0;
+        let dx = (1280.0 - 1920.0 * s) / 2.0;
+        assert_matrix(
+            fit_matrix(&FitMode::Fit, &LAPTOP, &PROJECTOR),
+            [s, 0.0, dx, 0.0, s, 0.0, 0.0, 0.0, 1.0],
+        );
+        assert!((dx + 71.111).abs() < 0.001);
+    }
+
+    #[test]
+    fn fill_crops_top_and_bottom() {
+        // Scaled to show all 1280 columns, cutting 40 rows off the top and
+        // bottom
+        let s = 1280.0 / 1920.0;
+        assert_matrix(
+            fit_matrix(&FitMode::Fill, &LAPTOP, &PROJECTOR),
+            [s, 0.0, 0.0, 0.0, s, 40.0, 0.0, 0.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn stretch_scales_each_axis() {
+        assert_matrix(
+            fit_matrix(&FitMode::Stretch, &LAPTOP, &PROJECTOR),
+            [
+                1280.0 / 1920.0,
+                0.0,
+                0.0,
+                0.0,
+                800.0 / 1080.0,
+                0.0,
+                0.0,
+                0.0,
+                1.0,
+            ],
+        );
+    }
+
+    #[test]
+    fn off_is_identity() {
+        assert_matrix(
+            fit_matrix(&FitMode::Off, &LAPTOP, &PROJECTOR),
+            [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn same_ratio_only_scales() {
+        let hd = Resolution {
+            width: 1280,
+            height: 720,
+        };
+        for fit in [FitMode::Fit, FitMode::Fill, FitMode::Stretch] {
+            let s = 1280.0 / 1920.0;
+            assert_matrix(
+                fit_matrix(&fit, &hd, &PROJECTOR),
+                [s, 0.0, 0.0, 0.0, s, 0.0, 0.0, 0.0, 1.0],
+            );
+        }
+    }
+
+    fn parse(text: &str) -> XrandrState {
+        XrandrState::parse(text.lines().map(String::from).collect())
+    }
+
+    // As printed by xrandr 1.5, trailing spaces and all
+    const INTERLACED: &str = "\
+Screen 0: minimum 8 x 8, current 1920 x 1080, maximum 16384 x 16384
+HDMI-1 connected primary 1920x1080+0+0 (normal left inverted right \
+    x axis y axis) 531mm x 299mm
+   1920x1080     60.00*+  50.00    59.94  
+   1920x1080i    60.00    50.00    59.94  
+   1280x720      60.00    50.00  
+   720x576i      50.00  
+   320x240d      60.05  
+DP-1 disconnected (normal left inverted right x axis y axis)
+";
+
+    #[test]
+    fn parses_interlaced_modes() {
+        let state = parse(INTERLACED);
+        let modes = &state.outputs[0].modes;
+
+        let interlaced: Vec<_> = modes
+            .iter()
+            .filter(|m| m.interlaced)
+            .map(|m| (m.width, m.height, m.rate))
+            .collect();
+        assert_eq!(
+            interlaced,
+            [
+                (1920, 1080, 60.0),
+                (1920, 1080, 50.0),
+                (1920, 1080, 59.94),
+                (720, 576, 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_doublescan_modes_as_progressive() {
+        let state = parse(INTERLACED);
+        let mode = state.outputs[0].modes.last().unwrap();
+
+        assert_eq!((mode.width, mode.height, mode.rate), (320, 240, 60.05));
+        assert!(!mode.interlaced);
+    }
+
+    #[test]
+    fn parses_current_and_preferred_markers() {
+        let state = parse(INTERLACED);
+        let output = &state.outputs[0];
+
+        let current: Vec<_> =
+            output.modes.iter().filter(|m| m.current).collect();
+        assert_eq!(current.len(), 1);
+        assert!(current[0].preferred && !current[0].interlaced);
+        assert!(output.enabled && output.primary);
+        assert_eq!(state.outputs[1].connection, Connection::Disconnected);
+        assert_eq!(state.max_screen_size, Some((16384, 16384)));
+    }
+
+    // With a mode added by `cvt 2560 1080` and `xrandr --newmode`
+    const CUSTOM: &str = "\
+Screen 0: minimum 8 x 8, current 2560 x 1080, maximum 16384 x 16384
+DP-1 connected primary 2560x1080+0+0 (normal left inverted right \
+    x axis y axis) 673mm x 284mm
+   1920x1080     60.00 +
+   2560x1080_60.00  59.98*
+   1280x720      60.00  
+";
+
+    #[test]
+    fn skips_custom_modes() {
+        let state = parse(CUSTOM);
+        let output = &state.outputs[0];
+
+        let sizes: Vec<_> =
+            output.modes.iter().map(|m| (m.width, m.height)).collect();
+        assert_eq!(sizes, [(1920, 1080), (1280, 720)]);
+        // The custom mode is the current one
+        assert!(output.enabled);
+    }
+
+    const UNKNOWN: &str = "\
+Screen 0: minimum 1 x 1, current 1024 x 768, maximum 8192 x 8192
+Virtual-1 unknown connection 1024x768+0+0 (normal left inverted right \
+    x axis y axis) 0mm x 0mm
+   1024x768      60.00*+
+   800x600       60.32    56.25  
+VGA-1 unknown connection (normal left inverted right x axis y axis)
+   640x480       59.94  
+";
+
+    #[test]
+    fn unknown_connection_is_connected_with_modes() {
+        let state = parse(UNKNOWN);
+        let names: Vec<_> = state.outputs.iter().map(|o| &o.name).collect();
+        assert_eq!(names, ["Virtual-1", "VGA-1"]);
+
+        let virt = &state.outputs[0];
+        assert_eq!(virt.connection, Connection::Unknown);
+        assert!(virt.enabled);
+        assert_eq!(virt.modes.len(), 3);
+        let geometry = virt.geometry.as_ref().unwrap();
+        assert_eq!((geometry.width, geometry.height), (1024, 768));
+
+        let vga = &state.outputs[1];
+        assert_eq!(vga.connection, Connection::Unknown);
+        assert!(!vga.enabled && vga.geometry.is_none());
+        assert_eq!(vga.modes.len(), 1);
+    }
+
+    #[test]
+    fn unknown_connection_is_listed_as_connected() {
+        let mut backend = Backend {
+            state: parse(UNKNOWN),
+            batch: None,
+        };
+        let outputs = backend.get_outputs().unwrap();
+        assert!(outputs.iter().all(|o| o.connected));
+    }
+
+    // Operations are listed by name, and parsed back from it
+    #[test]
+    fn operations_parse_back_from_their_names() {
+        use crate::action::Action;
+        use crate::config::Config;
+        use crate::err::{AppError, ParseError};
+
+        let mut backend: Box<dyn DisplayBackend> = Box::new(Backend {
+            state: parse(INTERLACED),
+            batch: None,
+        });
+        let outputs = backend.get_outputs().unwrap();
+        let ops = backend.supported_operations(&outputs[0]);
+        assert!(ops.len() > 1);
+
+        for op in ops {
+            let name = op.to_string();
+            assert_eq!(name.trim(), name);
+
+            let args = VecDeque::from([outputs[0].name.clone(), name]);
+            let res = Action::parse(&mut backend, &Config::default(), args);
+            let unknown = matches!(
+                res,
+                Err(AppError::Parse {
+                    source: ParseError::Operation(_)
+                })
+            );
+            assert!(!unknown, "{op}");
+        }
+    }
+
+    #[test]
+    fn layout_command_imports_back() {
+        let layout = [
+            OutputLayout {
+                name: "eDP-1".to_string(),
+                enabled: true,
+                primary: true,
+                resolution: Some(Resolution {
+                    width: 1920,
+                    height: 1080,
+                }),
+                rate: Some(59.94),
+                gamma: Some(Gamma::new(1.0, 0.9, 0.8)),
+                brightness: Some(0.7),
+                ..Default::default()
+            },
+            OutputLayout {
+                name: "HDMI-1".to_string(),
+                enabled: true,
+                resolution: Some(Resolution {
+                    width: 1280,
+                    height: 1024,
+                }),
+                x: 1920,
+                y: -56,
+                rotation: Rotation::Right,
+                ..Default::default()
+            },
+            OutputLayout {
+                name: "DP-1".to_string(),
+                enabled: false,
+                ..Default::default()
+            },
+        ];
+        let command = xrandr_command(&layout);
+        let profile = Profile::from_command(&command).unwrap();
+
+        let laptop = &profile.outputs["eDP-1"];
+        assert_eq!(laptop.resolution, layout[0].resolution);
+        assert_eq!(laptop.rate, Some(59.94));
+        assert_eq!(laptop.gamma, layout[0].gamma);
+        assert_eq!(laptop.brightness, Some(0.7));
+        assert!(laptop.enabled && laptop.primary);
+
+        let hdmi = &profile.outputs["HDMI-1"];
+        assert_eq!(hdmi.resolution, layout[1].resolution);
+        assert_eq!((hdmi.x, hdmi.y), (1920, -56));
+        assert_eq!(hdmi.rotation, Rotation::Right);
+        assert!(hdmi.enabled && !hdmi.primary);
+
+        assert!(!profile.outputs["DP-1"].enabled);
+    }
+}
@@ -0,0 +1,204 @@
+// User configuration, read from `$XDG_CONFIG_HOME/rofi-randr/config.toml`
+// (or `~/.config/rofi-randr/config.toml`), or from `$ROFI_RANDR_CONFIG`.
+// Every key is optional, and a missing file just means the defaults.
+use serde::{de, Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use crate::action::resolution::Resolution;
+use crate::action::OPERATION_NAMES;
+use crate::backend::OutputEntry;
+use crate::err::AppError;
+use crate::icon::IconNames;
+use crate::prompt::PromptNames;
+
+// Points at the config file to use instead of the one in the XDG directory
+const CONFIG_VAR: &str = "ROFI_RANDR_CONFIG";
+
+// What to do when positioning an output makes it partly cover another
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    Allow,
+    #[default]
+    Warn,
+    Reject,
+}
+
+// When to ask before disabling an output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmDisable {
+    Always,
+    // Only the last enabled one, which leaves nothing to see
+    #[default]
+    Last,
+    Never,
+}
+
+// What `--watch` does when an output is plugged in and no profile matches
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotplugPolicy {
+    // To the right of the other outputs
+    #[default]
+    Extend,
+    // Mirroring the primary output
+    Mirror,
+    None,
+}
+
+// The order of the outputs in the output list
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSort {
+    // The last used one (see `remember_last_output`), then connected ones
+    #[default]
+    Connected,
+    // Left to right, as they are arranged. Disabled ones come last.
+    Position,
+    Name,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Remember the last output an action was applied to, and list it
+    // first in the output menu on the next launch
+    pub remember_last_output: bool,
+    // Leave disconnected outputs out of the output lists,
+    // unless they are still enabled (and thus need disabling)
+    pub hide_disconnected: bool,
+    pub output_sort: OutputSort,
+    // Decimals to show rates with. The exact rate is always what gets set.
+    pub rate_precision: usize,
+    // Leave rates below this out of the rate lists
+    pub min_rate: Option<f64>,
+    // Aspect ratios like "21:9" to list resolutions in. Outputs that have
+    // none in these ratios list all of theirs.
+    pub aspect_ratios: Vec<String>,
+    // Ask to keep a rotation, and revert it if that is not answered in time
+    pub confirm_rotation: bool,
+    pub confirm_disable: ConfirmDisable,
+    // Seconds to wait for confirmations before reverting
+    pub confirm_timeout: u64,
+    pub overlap: OverlapPolicy,
+    pub hotplug: HotplugPolicy,
+    // Move the mouse pointer to an output when making it primary
+    pub warp_pointer: bool,
+    // Make an output primary when changing its mode or position
+    pub primary_follows_changes: bool,
+    // How often to resend a sway command that did not reach sway
+    pub sway_ipc_retries: u32,
+    // Operation to go to straight after choosing an output, skipping the
+    // operation menu. One of `OPERATION_NAMES`, e.g. "Change resolution".
+    #[serde(deserialize_with = "operation_name")]
+    pub default_operation: Option<String>,
+    // Names to show outputs by, keyed by their connector name
+    pub aliases: HashMap<String, String>,
+    // Connector names of outputs whose configuration must not be changed,
+    // e.g. the screen of a kiosk
+    pub locked: Vec<String>,
+    // Icon names to use instead of the defaults, see `icon::IconNames`
+    pub icons: IconNames,
+    // Prompts to use instead of the defaults, see `prompt::PromptNames`
+    pub prompts: PromptNames,
+}
+
+// `$XDG_<var>/rofi-randr`, falling back on `~/<fallback>/rofi-randr`
+pub fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let base = match env::var_os(var).filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(fallback),
+    };
+
+    Some(base.join("rofi-randr"))
+}
+
+// Only accepts the names in the operation menu, so that a typo is reported
+// when loading instead of showing up as an invalid operation later
+fn operation_name<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    if !OPERATION_NAMES.contains(&name.as_str()) {
+        return Err(de::Error::unknown_variant(&name, OPERATION_NAMES));
+    }
+    Ok(Some(name))
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            remember_last_output: false,
+            hide_disconnected: false,
+            output_sort: OutputSort::default(),
+            rate_precision: 2,
+            min_rate: None,
+            aspect_ratios: Vec::new(),
+            confirm_rotation: false,
+            confirm_disable: ConfirmDisable::default(),
+            confirm_timeout: 15,
+            overlap: OverlapPolicy::default(),
+            hotplug: HotplugPolicy::default(),
+            warp_pointer: false,
+            primary_follows_changes: false,
+            sway_ipc_retries: 2,
+            default_operation: None,
+            aliases: HashMap::new(),
+            locked: Vec::new(),
+            icons: IconNames::default(),
+            prompts: PromptNames::default(),
+        }
+    }
+}
+
+impl Config {
+    // What `output` is shown as: its alias, or else its connector name
+    pub fn label<'a>(&'a self, output: &'a str) -> &'a str {
+        self.aliases.get(output).map_or(output, String::as_str)
+    }
+
+    pub fn is_locked(&self, output_name: &str) -> bool {
+        self.locked.iter().any(|l| l == output_name)
+    }
+
+    pub fn lists_output(&self, output: &OutputEntry) -> bool {
+        !self.hide_disconnected || output.connected || output.enabled
+    }
+
+    pub fn lists_rate(&self, rate: f64) -> bool {
+        self.min_rate.is_none_or(|min| rate >= min)
+    }
+
+    // Only tells whether the ratio is one of `aspect_ratios`, see
+    // `List::resolution_list` for when that is used
+    pub fn lists_aspect_ratio(&self, res: &Resolution) -> bool {
+        self.aspect_ratios.contains(&res.aspect_ratio())
+    }
+
+    pub fn load() -> Result<Self, AppError> {
+        // A file that is asked for explicitly has to be there
+        let (path, required) =
+            match env::var_os(CONFIG_VAR).filter(|p| !p.is_empty()) {
+                Some(path) => (PathBuf::from(path), true),
+                None => match xdg_dir("XDG_CONFIG_HOME", ".config") {
+                    Some(dir) => (dir.join("config.toml"), false),
+                    None => return Ok(Self::default()),
+                },
+            };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(AppError::ConfigRead { path, source: e }),
+        };
+
+        toml::from_str(&content)
+            .map_err(|e| AppError::ConfigParse { path, source: e })
+    }
+}
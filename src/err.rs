@@ -1,4 +1,5 @@
 // Top level errors
+use std::path::PathBuf;
 use thiserror::Error;
 use xrandr::XrandrError;
 
@@ -7,18 +8,57 @@ pub enum ParseError {
     #[error("Invalid resolution: {0}")]
     Resolution(String),
 
-    #[error("Invalid position: {0}")]
-    Position(String),
-
     #[error("Invalid direction: {0}")]
     Relation(String),
 
+    #[error("Resolution {0} is not available on {1}")]
+    Unavailable(String, String),
+
     #[error("Invalid rotaiton: {0}")]
     Rotation(String),
 
     #[error("Invalid rate: {0}")]
     Rate(String),
 
+    #[error("Invalid color depth: {0}")]
+    Depth(String),
+
+    #[error("Expected On or Off, got: {0}")]
+    Toggle(String),
+
+    #[error("Invalid gamma: {0}")]
+    Gamma(String),
+
+    #[error("Invalid brightness: {0}")]
+    Brightness(String),
+
+    #[error("Invalid scale filter: {0}")]
+    ScaleFilter(String),
+
+    #[error("Invalid subpixel order: {0}")]
+    Subpixel(String),
+
+    #[error("Invalid power state: {0}")]
+    Power(String),
+
+    #[error("Invalid arrangement: {0}")]
+    Arrangement(String),
+
+    #[error("Invalid panning area, expected WxH+X+Y: {0}")]
+    Panning(String),
+
+    #[error("Invalid fit, expected Fit, Fill, Stretch or Off: {0}")]
+    FitMode(String),
+
+    #[error("Invalid max render time, expected Off or milliseconds: {0}")]
+    MaxRenderTime(String),
+
+    #[error(
+        "Invalid layout command, expected one like --export-layout \
+             prints: {0}"
+    )]
+    LayoutCommand(String),
+
     #[error("Invalid operation: '{0}'")]
     Operation(String),
 }
@@ -38,21 +78,186 @@ pub enum AppError {
         source: XrandrError,
     },
 
-    #[error("Call to libxrandr failed")]
-    Cmd,
-
     #[error("{source}")]
     Parse {
         #[from]
         source: ParseError,
     },
 
-    #[error("No modes for requested resolution found")]
-    NoModes,
+    #[error("No usable modes: {0}")]
+    NoModes(String),
 
     #[error("No output found for the name {0}")]
     NoOuput(String),
 
+    #[error("{0} would partly cover {}", .1.join(", "))]
+    Overlap(String, Vec<String>),
+
+    #[error("{0} and {1} overlap, there is nothing to swap")]
+    SwapOverlap(String, String),
+
+    #[error("No internal panel found")]
+    NoInternal,
+
+    #[error("No external output connected, keeping the internal panel on")]
+    NoExternal,
+
+    #[error("No enabled output")]
+    NoEnabled,
+
+    #[error("{0} is locked in the config, its configuration can not change")]
+    Locked(String),
+
+    #[error("{0} does not mirror another output")]
+    NotMirrored(String),
+
     #[error("Invalid operation '{0}' on disabled display")]
     Disabled(String),
+
+    #[error("Operation not supported for output {0}")]
+    Unsupported(String),
+
+    #[error("Could not read config file {}:\n{source}", path.display())]
+    ConfigRead {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid config file {}:\n{source}", path.display())]
+    ConfigParse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    // Rofi reads the menus from stdout
+    #[error("Could not write to rofi:\n{source}")]
+    Output {
+        #[from]
+        source: std::io::Error,
+    },
+
+    #[error("Could not start the timer to revert with:\n{source}")]
+    Timer { source: std::io::Error },
+
+    #[error("Invalid profile name '{0}'")]
+    ProfileName(String),
+
+    #[error("Could not read profile {}:\n{source}", path.display())]
+    ProfileRead {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid profile {}:\n{source}", path.display())]
+    ProfileParse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("Could not write profile {}:\n{source}", path.display())]
+    ProfileWrite {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Could not write EDID to {}:\n{source}", path.display())]
+    EdidWrite {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+// Exit codes, so that scripts can tell kinds of failures apart.
+// These are documented in the README, so do not renumber them.
+pub const EXIT_OTHER: i32 = 1;
+pub const EXIT_PARSE: i32 = 2;
+pub const EXIT_NO_OUTPUT: i32 = 3;
+pub const EXIT_NO_BACKEND: i32 = 4;
+pub const EXIT_BACKEND: i32 = 5;
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        use crate::backend::Error as BackendError;
+
+        match self {
+            Self::Parse { .. }
+            | Self::ConfigParse { .. }
+            | Self::ProfileParse { .. }
+            | Self::ProfileName(_) => EXIT_PARSE,
+
+            Self::NoOuput(_) => EXIT_NO_OUTPUT,
+            Self::BackendErr { source } if source.is_no_output() => {
+                EXIT_NO_OUTPUT
+            }
+
+            Self::BackendErr { source } => match source {
+                BackendError::GetBackend
+                | BackendError::NoBackend(_)
+                | BackendError::UnknownBackend(_)
+                | BackendError::GetHandle(_)
+                | BackendError::MissingBinary(_) => EXIT_NO_BACKEND,
+                // Asking for something that will never work
+                BackendError::Unsupported | BackendError::ScreenSize { .. } => {
+                    EXIT_OTHER
+                }
+                _ => EXIT_BACKEND,
+            },
+            Self::Lib { .. } => EXIT_BACKEND,
+
+            _ => EXIT_OTHER,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::err::*;
+    use crate::backend::Error as BackendError;
+
+    #[test]
+    fn missing_outputs_exit_with_no_output() {
+        let name = || "DP-1".to_string();
+        let errors: Vec<BackendError> = vec![
+            GetResolutions::NoOutput(name()).into(),
+            SetResolution::NoOutput(name()).into(),
+            GetRates::NoOutput(name()).into(),
+            SetRate::NoOutput(name()).into(),
+            GetGeometry::NoOutput(name()).into(),
+            SetRotation::NoOutput(name()).into(),
+            SetPosition::NoOutput(name()).into(),
+            SetCoordinates::NoOutput(name()).into(),
+            SetPrimary::NoOutput(name()).into(),
+            SetColorDepth::NoOutput(name()).into(),
+            SetHdr::NoOutput(name()).into(),
+            SetTearing::NoOutput(name()).into(),
+            SetScaleFilter::NoOutput(name()).into(),
+            SetScale::NoOutput(name()).into(),
+            SetSubpixel::NoOutput(name()).into(),
+            SetProjectorFit::NoOutput(name()).into(),
+            SetMaxRenderTime::NoOutput(name()).into(),
+            SetPanning::NoOutput(name()).into(),
+            SetPower::NoOutput(name()).into(),
+            SetColorProfile::NoOutput(name()).into(),
+            SetGamma::NoOutput(name()).into(),
+            GetGamma::NoOutput(name()).into(),
+            GetEdid::NoOutput(name()).into(),
+            SetSeat::NoOutput(name()).into(),
+            SetWorkspace::NoOutput(name()).into(),
+            Enable::NoOutput(name()).into(),
+            Disable::NoOutput(name()).into(),
+        ];
+
+        for e in errors {
+            let code = AppError::from(e).exit_code();
+            assert_eq!(code, EXIT_NO_OUTPUT);
+        }
+        assert_eq!(AppError::NoOuput(name()).exit_code(), EXIT_NO_OUTPUT);
+    }
+
+    #[test]
+    fn other_backend_errors_exit_with_backend() {
+        let e = BackendError::from(SetRate::NoMode("DP-1".into()));
+        assert_eq!(AppError::from(e).exit_code(), EXIT_BACKEND);
+    }
 }
@@ -1,6 +1,13 @@
 use crate::action::{position::Relation, rotate::Rotation, Operation};
+use serde::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Debug, Default)]
+// User chosen icon names, from the `[icons]` table in the config file.
+// Keys are the variant names in snake_case, e.g. `rot_left = "..."`.
+pub type IconNames = HashMap<Icon, String>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Icon {
     Connected,
     Disabled,
@@ -8,30 +15,64 @@ pub enum Icon {
 
     Primary,
     Disable,
+    Toggle,
     Rotate,
     Upright,
     RotLeft,
     RotRight,
     Flipped,
     Rate,
+    PowerSaver,
     Mode,
     Fitsize,
     Position,
+    Panning,
+    RenderTime,
     Left,
     Right,
     Above,
     Below,
     Duplicate,
+    Unmirror,
+    ProjectorFit,
+    Arrange,
+    Identify,
+    Centered,
+    Swap,
+    Workspace,
+    Seat,
+    Reset,
+    Gamma,
+    ColorProfile,
+    Brightness,
+    ColorDepth,
+    Hdr,
+    Tearing,
+    ScaleFilter,
+    Subpixel,
+    Power,
+    Laptop,
+    External,
+    Rescan,
+    Export,
+    Create,
+    On,
+    Off,
 
     Apply,
     Cancel,
     Back,
     #[default]
+    #[serde(skip)]
     None,
 }
 
 impl Icon {
-    pub fn name(&self) -> String {
+    pub fn name(&self, names: &IconNames) -> String {
+        if let Some(name) = names.get(self) {
+            return name.clone();
+        }
+
         match self {
             Self::Connected => "desktopconnected",
             Self::Disabled => "desktoptrusted",
@@ -39,7 +80,9 @@ impl Icon {
 
             Self::Primary => "video-single-display-symbolic",
             Self::Disable => "error",
+            Self::Toggle => "system-shutdown",
             Self::Rate => "backup",
+            Self::PowerSaver => "battery-profile-powersave",
 
             // Rotation related
             Self::Rotate => "rotation-allowed-symbolic",
@@ -54,11 +97,39 @@ impl Icon {
 
             // Positioning related
             Self::Position => "fitbest",
+            Self::Panning => "transform-move",
             Self::Left => "gtk-goto-first-ltr",
             Self::Right => "gtk-goto-first-rtl",
             Self::Above => "gtk-goto-top",
             Self::Below => "gtk-goto-bottom",
             Self::Duplicate => "video-joined-displays-symbolic",
+            Self::Unmirror => "video-single-display-symbolic",
+            Self::ProjectorFit => "zoom-fit-best",
+            Self::Arrange => "view-grid-symbolic",
+            Self::Identify => "dialog-information",
+            Self::Centered => "format-justify-center",
+            Self::Swap => "exchange-positions",
+            Self::Workspace => "preferences-desktop-workspaces",
+            Self::Seat => "input-mouse",
+            Self::Reset => "edit-undo",
+            Self::Gamma => "redshift",
+            Self::ColorProfile => "preferences-color",
+            Self::Brightness => "display-brightness-symbolic",
+
+            Self::ColorDepth => "color-management",
+            Self::Hdr => "hdr",
+            Self::Tearing => "games-config-options",
+            Self::RenderTime => "chronometer",
+            Self::ScaleFilter => "zoom-in",
+            Self::Subpixel => "preferences-desktop-font",
+            Self::Power => "system-suspend",
+            Self::Laptop => "computer-laptop",
+            Self::External => "video-display",
+            Self::Rescan => "view-refresh",
+            Self::Export => "document-export",
+            Self::Create => "list-add",
+            Self::On => "checkbox-checked-symbolic",
+            Self::Off => "checkbox-symbolic",
 
             // Confirmation
             Self::Apply => "dialog-apply",
@@ -78,6 +149,7 @@ impl From<Relation> for Icon {
             Relation::RightOf => Icon::Right,
             Relation::Above => Icon::Above,
             Relation::Below => Icon::Below,
+            Relation::CenteredOn => Icon::Centered,
         }
     }
 }
@@ -98,11 +170,39 @@ impl From<Operation> for Icon {
         match op {
             Operation::Enable => Icon::Connected,
             Operation::Disable => Icon::Disable,
+            Operation::Toggle => Icon::Toggle,
             Operation::SetPrimary => Icon::Primary,
-            Operation::ChangeRes(_) => Icon::Mode,
+            Operation::ChangeRes(_) | Operation::ChangeMode(..) => Icon::Mode,
             Operation::Position(_) => Icon::Position,
+            Operation::Panning(_) => Icon::Panning,
+            Operation::MaxRenderTime(_) => Icon::RenderTime,
             Operation::ChangeRate(..) => Icon::Rate,
+            Operation::LimitRate(..) => Icon::PowerSaver,
             Operation::Rotate(_) => Icon::Rotate,
+            Operation::ColorDepth(_) => Icon::ColorDepth,
+            Operation::Hdr(_) => Icon::Hdr,
+            Operation::AllowTearing(_) => Icon::Tearing,
+            Operation::ScaleFilter(_) => Icon::ScaleFilter,
+            Operation::Subpixel(_) => Icon::Subpixel,
+            Operation::Power(_) => Icon::Power,
+            Operation::MirrorTo(_) => Icon::Duplicate,
+            Operation::Unmirror => Icon::Unmirror,
+            Operation::ProjectorFit(_) => Icon::ProjectorFit,
+            Operation::Swap(_) => Icon::Swap,
+            Operation::Workspace(_) => Icon::Workspace,
+            Operation::Seat(_) => Icon::Seat,
+            Operation::Reset => Icon::Reset,
+            Operation::Gamma(_) => Icon::Gamma,
+            Operation::ColorProfile(_) => Icon::ColorProfile,
+            Operation::Brightness(_) => Icon::Brightness,
+            Operation::ExportEdid => Icon::Export,
+            Operation::LaptopOnly => Icon::Laptop,
+            Operation::ExternalOnly => Icon::External,
+            Operation::RotateAll(_) => Icon::Rotate,
+            Operation::RevertRotations(_) => Icon::Reset,
+            Operation::Arrange(_) => Icon::Arrange,
+            Operation::BrightnessAll(_) => Icon::Brightness,
+            Operation::GammaAll(_) => Icon::Gamma,
         }
     }
 }
@@ -1,76 +1,824 @@
 mod action;
 mod backend;
+mod config;
 mod err;
 mod icon;
+mod profile;
+mod prompt;
 mod rofi;
+mod state;
 
-use action::{Action, ParseResult};
+use action::hotplug;
+use action::shortcut::{self, Shortcut};
+use action::{position, Action, Operation, ParseResult, CREATE_OUTPUT, RESCAN};
+use backend::DisplayBackend;
+use config::{Config, OverlapPolicy};
 use err::AppError;
 
 use itertools::Itertools;
+use profile::Profile;
 use rofi::List;
+use state::{PendingRotation, State};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{collections::VecDeque, env};
 
+// Separates the arguments in ROFI_DATA. This is the same unit separator
+// that rofi uses between row options, so no typed in value can contain it.
+const DATA_SEPARATOR: char = '\x1f';
+
+// The latest argument, which is passed as arg to this program
+#[derive(Debug, PartialEq)]
+enum Input {
+    Selected(String),
+    // Taken as it is: there is no info field or markup, and it may well
+    // contain a `<` (e.g. in a path) or be the word "Back"
+    Typed(String),
+}
+
+fn get_input() -> Option<Input> {
+    let arg = env::args().nth(1)?;
+    let info = env::var("ROFI_INFO").ok();
+    Some(read_input(arg, shortcut::typed(), info))
+}
+
+// The input from `arg`, with the `info` of the selected row if it has one
+fn read_input(arg: String, typed: bool, info: Option<String>) -> Input {
+    if typed {
+        return Input::Typed(arg.trim().to_string());
+    }
+
+    // Rows carry their value in their info field, which rofi passes back
+    // as `ROFI_INFO`. That way the text does not need to be the value.
+    // Otherwise, split on start of first pango tag:
+    // - only comments have markup, so all that comes before is unput
+    // Unwrap: first element of a split always exists
+    Input::Selected(match info {
+        Some(info) if !info.is_empty() => rofi::info_value(&info).to_string(),
+        _ => arg.split('<').next().unwrap().trim().to_string(),
+    })
+}
+
 fn get_args() -> VecDeque<String> {
     // ROFI_DATA env var contains the chosen arguments to the script so far
-    let mut rofi_data: VecDeque<String> = match env::var("ROFI_DATA") {
-        Err(_) => VecDeque::new(), // no args yet
-        Ok(data_s) => data_s
-            .split(':')
+    let data = env::var("ROFI_DATA").ok();
+    next_args(data.as_deref(), get_input())
+}
+
+// The arguments stored in `data`, updated with the latest `input`
+fn next_args(data: Option<&str>, input: Option<Input>) -> VecDeque<String> {
+    let mut rofi_data: VecDeque<String> = match data {
+        None => VecDeque::new(), // no args yet
+        Some(data_s) => data_s
+            .split(DATA_SEPARATOR)
             .filter(|s| !s.is_empty())
-            .map(String::from).collect(),
+            .map(String::from)
+            .collect(),
     };
 
-    // The latest chosen argument is passed as arg to this program
-    let arg = env::args().nth(1);
-    if let Some(a) = arg {
-        // Split on start of first pango tag:
-        // - only comments have markup, so all that comes before is unput
-        // Unwrap: first element of a split always exists
-        let input = a.split('<').next().unwrap().trim().to_string();
-
+    // Every menu stores exactly one entry, also when it is one step of a
+    // longer drilldown (like relation, then output for positioning). That
+    // is what lets `Back` unwind a single menu at a time.
+    match input {
         // If the user chose back, keep the data as it was the before
-        if input == "Back" {
+        Some(Input::Selected(input)) if input == "Back" => {
             rofi_data.pop_back();
-        } else {
+        }
+        Some(Input::Selected(input) | Input::Typed(input)) => {
             rofi_data.push_back(input);
         }
+        None => (),
     }
 
+    rofi_data
+}
+
+// What `set_data` stores, and rofi passes back in `ROFI_DATA`
+fn data_string(rofi_data: &VecDeque<String>) -> String {
+    rofi_data.iter().join(&DATA_SEPARATOR.to_string())
+}
+
+// Store choices made for next iteration.
+// When there are none, this resets the data in case of `Back`
+fn set_data(
+    out: &mut impl Write,
+    rofi_data: &VecDeque<String>,
+) -> io::Result<()> {
+    writeln!(out, "\0data\x1f{}", data_string(rofi_data))
+}
+
+// Runs `--revert-rotation` in the background. Rofi waits until the output
+// of the script is closed, so the timer must not inherit it. Returns the id
+// of the timer, which only reverts the rotations that carry it.
+fn spawn_revert_timer() -> std::io::Result<u64> {
+    let timer = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+
+    Command::new(env::current_exe()?)
+        .args(["--revert-rotation", &timer.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(timer)
+}
+
+// Rofi only reads the output of a script once it exits, so there is no way
+// to show a message in rofi while the backend is busy. When applying takes
+// this long, a notification is sent instead.
+const SLOW_APPLY: Duration = Duration::from_millis(500);
 
-    // Store choices made for next iteration
-    if !rofi_data.is_empty() {
-        println!("\0data\x1f{}", rofi_data.iter().join(":"));
-    } else {
-        println!("\0data\x1f"); // Reset in case of `Back`
+// Optional, without it slow changes are just not announced
+const NOTIFY_SEND: &str = "notify-send";
+
+fn on_path(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| dir.join(program).is_file())
+    })
+}
+
+// Sends a notification that `action` is being applied, unless the returned
+// sender is dropped (i.e. applying finished) within `SLOW_APPLY`
+fn notify_when_slow(action: &Action) -> mpsc::Sender<()> {
+    let (done, wait) = mpsc::channel::<()>();
+    if !on_path(NOTIFY_SEND) {
+        return done;
     }
-    
-    rofi_data
+    let body = format!("Applying {} to {}...", action.op(), action.output());
+
+    std::thread::spawn(move || {
+        if let Err(mpsc::RecvTimeoutError::Timeout) =
+            wait.recv_timeout(SLOW_APPLY)
+        {
+            // Not worth failing over, like when there is no daemon
+            let _ = Command::new(NOTIFY_SEND)
+                .args(["--expire-time=2000", "rofi-randr", &body])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    });
+
+    done
 }
 
-fn run() -> Result<(), AppError> {
-    // Allow override of automatic backend trough env var
-    let mut backend = match env::var("DISPLAY_SERVER_OVERRIDE") {
-        Ok(name) => backend::from_name(&name)?,
-        Err(_) => backend::determine()?,
+// Applies the action. Returns a list to show if it still needs confirming.
+fn apply(
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+    action: &Action,
+) -> Result<Option<List>, AppError> {
+    let output = action.output();
+    let mut state = State::load();
+    let mut state_changed = false;
+
+    // Rotating the output that is waiting for confirmation is the answer
+    // to it, which does not need confirming again. Only the rotations that
+    // an answer is about stop waiting, others may still be.
+    let previous = match action.op() {
+        Operation::Rotate(_) if config.confirm_rotation => {
+            let answer =
+                state.pending_rotations.iter().any(|p| p.output == output);
+
+            if answer {
+                state.pending_rotations.retain(|p| p.output != output);
+                state_changed = true;
+                Vec::new()
+            } else {
+                vec![PendingRotation {
+                    output: output.to_string(),
+                    previous: backend.get_transform(output)?,
+                    timer: 0,
+                }]
+            }
+        }
+        // The answer when every output that it rotates is waiting for one
+        Operation::RotateAll(_) if config.confirm_rotation => {
+            let current = action::rotate::rotations(backend)?;
+            let is_pending = |output: &str| {
+                state.pending_rotations.iter().any(|p| p.output == output)
+            };
+
+            let answer = !current.is_empty()
+                && current.iter().all(|c| is_pending(&c.output));
+
+            if answer {
+                state
+                    .pending_rotations
+                    .retain(|p| !current.iter().any(|c| c.output == p.output));
+                state_changed = true;
+                Vec::new()
+            } else {
+                current
+            }
+        }
+        Operation::RevertRotations(reverted) => {
+            state
+                .pending_rotations
+                .retain(|p| !reverted.iter().any(|r| r.output == p.output));
+            state_changed = true;
+            Vec::new()
+        }
+        _ => Vec::new(),
+    };
+
+    // Outputs that were still waiting go back to where they were before
+    // that, not to the rotation that was not confirmed
+    let previous: Vec<PendingRotation> = previous
+        .into_iter()
+        .map(|p| {
+            state
+                .pending_rotations
+                .iter()
+                .find(|pending| pending.output == p.output)
+                .cloned()
+                .unwrap_or(p)
+        })
+        .collect();
+
+    let overlapping = match action.op() {
+        Operation::Position(pos) if config.overlap != OverlapPolicy::Allow => {
+            position::overlapping(&backend.get_outputs()?, output, pos)
+        }
+        _ => Vec::new(),
+    };
+    if !overlapping.is_empty() {
+        let err = AppError::Overlap(output.to_string(), overlapping);
+        if config.overlap == OverlapPolicy::Reject {
+            return Err(err);
+        }
+        state.message = Some(format!("Warning: {err}"));
+        state_changed = true;
+    }
+
+    // Remembered for `Swap primary` to go back to
+    if matches!(action.op(), Operation::SetPrimary) {
+        let outputs = backend.get_outputs()?;
+        let current = outputs.into_iter().find(|o| o.primary);
+        if let Some(current) = current.filter(|o| o.name != output) {
+            state.previous_primary = Some(current.name);
+            state_changed = true;
+        }
+    }
+
+    let applying = notify_when_slow(action);
+    let result = action.apply(backend, config)?;
+    drop(applying);
+
+    let changed = matches!(
+        action.op(),
+        Operation::ChangeRes(_)
+            | Operation::ChangeMode(..)
+            | Operation::ChangeRate(_)
+            | Operation::LimitRate(_)
+            | Operation::Position(_)
+    );
+    let follow = config.primary_follows_changes
+        && changed
+        && backend.capabilities().primary;
+    if follow {
+        backend.set_primary(output)?;
+    }
+
+    // Not every backend can, but the output is primary either way
+    let made_primary = follow || matches!(action.op(), Operation::SetPrimary);
+    if config.warp_pointer && made_primary {
+        match backend.warp_pointer(output) {
+            Err(backend::Error::Unsupported) => (),
+            res => res?,
+        }
+    }
+
+    if config.remember_last_output {
+        state.last_output = Some(output.to_string());
+        state_changed = true;
+    }
+
+    let confirmation = match action.op() {
+        Operation::Rotate(transform) | Operation::RotateAll(transform)
+            if !previous.is_empty() =>
+        {
+            // Reverting to one rotation is the same as rotating (all) again
+            let single = previous
+                .iter()
+                .map(|p| &p.previous)
+                .all_equal()
+                .then_some(&previous[0].previous);
+            let list = List::confirm_rotation(
+                transform,
+                single,
+                config.confirm_timeout,
+            );
+            let timer = spawn_revert_timer()
+                .map_err(|e| AppError::Timer { source: e })?;
+            state
+                .pending_rotations
+                .retain(|p| !previous.iter().any(|q| q.output == p.output));
+            state.pending_rotations.extend(
+                previous.into_iter().map(|p| PendingRotation { timer, ..p }),
+            );
+            state_changed = true;
+            Some(list)
+        }
+        _ => result,
     };
 
-    match Action::parse(&mut backend, get_args())? {
+    // Not worth failing over, the action itself did succeed
+    if state_changed {
+        if let Err(e) = state.save() {
+            eprintln!("Could not save state: {e}");
+        }
+    }
+
+    Ok(confirmation)
+}
+
+// The message left for this menu by the previous run, if any
+fn take_message() -> Option<String> {
+    let mut state = State::load();
+    let message = state.message.take()?;
+
+    if let Err(e) = state.save() {
+        eprintln!("Could not save state: {e}");
+    }
+    Some(message)
+}
+
+// What rofi shows once an action is applied
+#[derive(Debug)]
+enum AfterApply {
+    // The action asks to be confirmed
+    Confirm(List),
+    // Applied with kb-custom-3, the output list is shown again
+    Restart,
+    // Printing nothing closes rofi
+    Close,
+}
+
+fn after_apply(confirmation: Option<List>) -> AfterApply {
+    match confirmation {
+        Some(list) => AfterApply::Confirm(list),
+        None if shortcut::apply_and_stay() => AfterApply::Restart,
+        None => AfterApply::Close,
+    }
+}
+
+// Lists the next set of options, or applies the action if it is complete
+fn step(
+    out: &mut impl Write,
+    backend: &mut Box<dyn DisplayBackend>,
+    config: &Config,
+    args: &VecDeque<String>,
+) -> Result<(), AppError> {
+    // Creating an output goes on to choosing its resolution, as if that
+    // output had been chosen instead
+    if args.front().is_some_and(|arg| arg == CREATE_OUTPUT) {
+        let name = backend.create_output()?;
+        let args = VecDeque::from([name, "Change resolution".to_string()]);
+        return step(out, backend, config, &args);
+    }
+
+    match Action::parse(backend, config, args.clone())? {
         // Still something missing, list next set of options
-        ParseResult::Next(options) => options.rofi_print(),
+        ParseResult::Next(mut options) => {
+            // Rescanning starts over from the list of outputs
+            match args.front() {
+                Some(arg) if arg == RESCAN => set_data(out, &VecDeque::new())?,
+                _ => set_data(out, args)?,
+            }
+            if options.message.is_none() {
+                options.message = take_message();
+            }
+            options.rofi_print(out, &config.icons, &config.prompts)?;
+        }
         // We have a full action, apply it
-        ParseResult::Done(action) => action.apply(backend)?,
+        ParseResult::Done(action) => {
+            match after_apply(apply(backend, config, &action)?) {
+                AfterApply::Confirm(confirmation) => {
+                    set_data(out, args)?;
+                    confirmation.rofi_print(
+                        out,
+                        &config.icons,
+                        &config.prompts,
+                    )?;
+                }
+                AfterApply::Restart => {
+                    // Start over with a fresh view of the outputs
+                    *backend = get_backend(config)?;
+                    return step(out, backend, config, &VecDeque::new());
+                }
+                AfterApply::Close => (),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Adds `op` to `args`, as if it was chosen from the operation menu. Going
+// back from its menu then goes back to the outputs, as the operation menu
+// was not shown. Choosing the output with kb-custom-4 shows that menu.
+fn add_default_operation(
+    args: &mut VecDeque<String>,
+    op: String,
+    input: Option<Input>,
+) {
+    if matches!(input, Some(Input::Selected(i)) if i == "Back") {
+        args.clear();
+    } else if !shortcut::show_operations() {
+        args.push_back(op);
+    }
+}
+
+fn get_backend(config: &Config) -> Result<Box<dyn DisplayBackend>, AppError> {
+    Ok(backend::from_env(config)?)
+}
+
+fn run(config: &Config, out: &mut impl Write) -> Result<(), AppError> {
+    let mut backend = get_backend(config)?;
+
+    let mut args = get_args();
+
+    // A custom keybinding on a row it is bound on skips the (rest of the)
+    // submenus. Elsewhere it is the same as selecting the row.
+    let shortcut = Shortcut::from_env().filter(|s| s.applies_to(&args));
+    if let (Some(shortcut), Some(output)) = (shortcut, args.front()) {
+        let action = Action::from_shortcut(&mut backend, output, shortcut)?;
+        return apply(&mut backend, config, &action).map(|_| ());
+    }
+
+    // Choose the default operation for the user
+    if let Some(op) = Action::default_operation(&mut backend, config, &args)? {
+        add_default_operation(&mut args, op, get_input());
+    }
+
+    let err = match step(out, &mut backend, config, &args) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    // The last choice failed. Instead of closing rofi, go back to the menu
+    // that choice was made in, and show the error there.
+    let mut prev_args = args.clone();
+    prev_args.pop_back();
+
+    match Action::parse(&mut backend, config, prev_args.clone()) {
+        Ok(ParseResult::Next(mut options)) if !args.is_empty() => {
+            options.message = Some(format!("{err}"));
+            set_data(out, &prev_args)?;
+            options.rofi_print(out, &config.icons, &config.prompts)?;
+            Ok(())
+        }
+        // Can't recover, the error is shown on its own. `Back` from there
+        // leads to the menu in which the failing choice was made.
+        _ => {
+            set_data(out, &args)?;
+            Err(err)
+        }
+    }
+}
+
+// Reverts the rotations that are waiting for confirmation, if they are
+// still waiting for this `timer` once the timeout has passed. Those that
+// were rotated again since wait for a later timer.
+fn revert_rotation(config: &Config, timer: u64) -> Result<(), AppError> {
+    std::thread::sleep(Duration::from_secs(config.confirm_timeout));
+
+    let mut state = State::load();
+    let (pending, others) = std::mem::take(&mut state.pending_rotations)
+        .into_iter()
+        .partition::<Vec<_>, _>(|p| p.timer == timer);
+    state.pending_rotations = others;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let mut backend = get_backend(config)?;
+    backend::batch(&mut backend, |backend| {
+        for p in &pending {
+            backend.set_transform(&p.output, &p.previous)?;
+        }
+        Ok::<_, AppError>(())
+    })?;
+
+    if let Err(e) = state.save() {
+        eprintln!("Could not save state: {e}");
+    }
+    Ok(())
+}
+
+fn export_layout(config: &Config) -> Result<(), AppError> {
+    let mut backend = get_backend(config)?;
+    println!("{}", backend.layout_command()?);
+    Ok(())
+}
+
+// Applies what `export_layout` printed, e.g. from a keybinding. Every
+// output in it has to be connected.
+fn import_layout(config: &Config, command: &str) -> Result<(), AppError> {
+    let mut backend = get_backend(config)?;
+    let profile = Profile::from_command(command)?;
+
+    let outputs = backend.get_outputs()?;
+    let missing = profile
+        .outputs
+        .keys()
+        .find(|name| !outputs.iter().any(|o| &o.name == *name && o.connected));
+    if let Some(name) = missing {
+        return Err(AppError::NoOuput(name.clone()));
+    }
+
+    profile.apply(&mut backend, config)
+}
+
+// What `backend::from_env` goes by, for bug reports about the wrong backend
+fn diagnose(config: &Config) -> Result<(), AppError> {
+    let detected = backend::Detected::from_env();
+    print!("{detected}");
+
+    let name = match env::var("DISPLAY_SERVER_OVERRIDE") {
+        Ok(name) => {
+            println!("DISPLAY_SERVER_OVERRIDE={name}");
+            Some(name)
+        }
+        Err(_) => {
+            println!("DISPLAY_SERVER_OVERRIDE=<unset>");
+            detected.backend_name().map(String::from)
+        }
+    };
+
+    let Some(name) = name else {
+        println!("Backend: none detected");
+        return Ok(());
+    };
+    println!("Backend: {name}");
+    match backend::from_name(&name, config) {
+        Ok(_) => println!("Connecting: ok"),
+        Err(e) => println!("Connecting: failed: {e}"),
     }
+    Ok(())
+}
 
+fn save_profile(config: &Config, name: &str) -> Result<(), AppError> {
+    let mut backend = get_backend(config)?;
+    let path = Profile::capture(&mut backend)?.save(name)?;
+    println!("Saved profile to {}", path.display());
     Ok(())
 }
 
+// Applies the saved profile that matches the connected outputs whenever
+// outputs are plugged in or out, or else the `hotplug` policy. Only returns
+// if something fails.
+fn watch(config: &Config) -> Result<(), AppError> {
+    let mut backend = get_backend(config)?;
+    // Before reading the outputs, so that no change goes unnoticed
+    backend.watch_changes()?;
+    let mut connected: Option<Vec<String>> = None;
+
+    loop {
+        let outputs = backend.get_outputs()?;
+        let now: Vec<String> = outputs
+            .iter()
+            .filter(|o| o.connected)
+            .map(|o| o.name.clone())
+            .collect();
+
+        // Applying a profile causes change events too, but it does not
+        // change which outputs are connected
+        if connected.as_ref() != Some(&now) {
+            // Reloaded every time, to pick up newly saved profiles
+            let profiles = Profile::load_all()?;
+            if let Some((name, profile)) =
+                profiles.iter().find(|(_, p)| p.matches(&outputs))
+            {
+                println!("Applying profile {name}");
+                if let Err(e) = profile.apply(&mut backend, config) {
+                    eprintln!("Could not apply profile {name}: {e}");
+                }
+            } else if let Some(before) = &connected {
+                // Not on the first round, those outputs were there already
+                let added: Vec<_> = now
+                    .iter()
+                    .filter(|o| !before.contains(o))
+                    .cloned()
+                    .collect();
+                let removed = before.iter().any(|o| !now.contains(o));
+
+                let res = match removed {
+                    true => hotplug::disconnected(&mut backend, config),
+                    false => Ok(()),
+                }
+                .and_then(|()| {
+                    hotplug::connected(&mut backend, config, &added)
+                });
+                if let Err(e) = res {
+                    eprintln!("Could not handle the change of outputs: {e}");
+                }
+            }
+            connected = Some(now);
+        }
+
+        backend.wait_for_change()?;
+    }
+}
+
 fn main() {
-    match run() {
-        Ok(_) => { std::process::exit(0); }
+    // Silent unless asked for with `RUST_LOG`, see the README
+    env_logger::init();
+
+    // Flags for use outside of rofi, these report to the terminal
+    let mut cli_args = env::args().skip(1);
+    let cli_res = match cli_args.next().as_deref() {
+        Some("--watch") => Some(Config::load().and_then(|c| watch(&c))),
+        Some("--export-layout") => {
+            Some(Config::load().and_then(|c| export_layout(&c)))
+        }
+        Some("--import-layout") => {
+            let command = cli_args.next().unwrap_or_default();
+            Some(Config::load().and_then(|c| import_layout(&c, &command)))
+        }
+        Some("--diagnose") => Some(Config::load().and_then(|c| diagnose(&c))),
+        Some("--save-profile") => {
+            let name = cli_args.next().unwrap_or_default();
+            Some(Config::load().and_then(|c| save_profile(&c, &name)))
+        }
+        Some("--revert-rotation") => {
+            let timer = cli_args.next().and_then(|t| t.parse().ok());
+            let timer = timer.unwrap_or_default();
+            Some(Config::load().and_then(|c| revert_rotation(&c, timer)))
+        }
+        _ => None,
+    };
+    if let Some(res) = cli_res {
+        if let Err(e) = res {
+            eprintln!("{e}");
+            std::process::exit(e.exit_code());
+        }
+        std::process::exit(0);
+    }
+
+    let mut out = io::stdout().lock();
+
+    // A broken config is shown like any other error, with the default icons
+    // and prompts
+    let (config, res) = match Config::load() {
+        Ok(config) => {
+            let res = run(&config, &mut out);
+            (config, res)
+        }
+        Err(e) => (Config::default(), Err(e)),
+    };
+
+    match res {
+        Ok(_) => {
+            std::process::exit(0);
+        }
         Err(e) => {
-            List::error(&format!("{e}")).rofi_print();
-            std::process::exit(1)
+            // Nowhere left to show it when rofi can not be written to
+            let _ = List::error(&format!("{e}")).rofi_print(
+                &mut out,
+                &config.icons,
+                &config.prompts,
+            );
+            std::process::exit(e.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn args(args: &[&str]) -> VecDeque<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn selected(s: &str) -> Option<Input> {
+        Some(Input::Selected(s.to_string()))
+    }
+
+    // Tests that set `ROFI_RETV` must not run at the same time
+    static RETV: Mutex<()> = Mutex::new(());
+
+    fn with_retv<T>(retv: &str, f: impl FnOnce() -> T) -> T {
+        let _lock = RETV.lock().unwrap_or_else(|e| e.into_inner());
+        env::set_var("ROFI_RETV", retv);
+        let res = f();
+        env::remove_var("ROFI_RETV");
+        res
+    }
+
+    #[test]
+    fn data_round_trips() {
+        let before = args(&["DP-1", "Change resolution"]);
+        assert_eq!(next_args(Some(&data_string(&before)), None), before);
+    }
+
+    #[test]
+    fn values_keep_colons() {
+        let before = args(&["DP-1", "Gamma"]);
+        let after =
+            next_args(Some(&data_string(&before)), selected("1:0.9:0.8"));
+
+        assert_eq!(after, args(&["DP-1", "Gamma", "1:0.9:0.8"]));
+        assert_eq!(next_args(Some(&data_string(&after)), None), after);
+    }
+
+    #[test]
+    fn back_pops_the_last_choice() {
+        let before = args(&["DP-1", "Change resolution"]);
+        let after = next_args(Some(&data_string(&before)), selected("Back"));
+        assert_eq!(after, args(&["DP-1"]));
+    }
+
+    #[test]
+    fn back_unwinds_one_menu_at_a_time() {
+        // Positioning picks a relation, then the output it is relative to
+        let mut data = args(&["DP-1", "Position", "Left of"]);
+        for expected in [&["DP-1", "Position"][..], &["DP-1"], &[]] {
+            data = next_args(Some(&data_string(&data)), selected("Back"));
+            assert_eq!(data, args(expected));
         }
+
+        // Going back from the first menu stays there
+        let data = next_args(Some(&data_string(&data)), selected("Back"));
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn empty_data_resets() {
+        assert_eq!(data_string(&VecDeque::new()), "");
+        assert!(next_args(Some(""), None).is_empty());
+        assert_eq!(next_args(None, selected("DP-1")), args(&["DP-1"]));
+    }
+
+    #[test]
+    fn typed_input_is_taken_as_it_is() {
+        let input = read_input(" ~/icc/<1>.icc ".to_string(), true, None);
+        assert_eq!(input, Input::Typed("~/icc/<1>.icc".to_string()));
+
+        // Typing "Back" does not go back
+        let input = read_input("Back".to_string(), true, None);
+        let after = next_args(Some("DP-1"), Some(input));
+        assert_eq!(after, args(&["DP-1", "Back"]));
+    }
+
+    #[test]
+    fn selected_input_prefers_the_info() {
+        let text = "1920x1080 <span size='small'>(current)</span>";
+        let input = read_input(text.to_string(), false, None);
+        assert_eq!(input, Input::Selected("1920x1080".to_string()));
+
+        // Rows carry their position and value
+        let info = Some("3:1920x1080".to_string());
+        let input = read_input("Full HD".to_string(), false, info);
+        assert_eq!(input, Input::Selected("1920x1080".to_string()));
+    }
+
+    #[test]
+    fn apply_and_stay_restarts() {
+        let after = with_retv("12", || after_apply(None));
+        assert!(matches!(after, AfterApply::Restart));
+
+        // A confirmation is still shown first
+        let after = with_retv("12", || after_apply(Some(List::default())));
+        assert!(matches!(after, AfterApply::Confirm(_)));
+    }
+
+    #[test]
+    fn selecting_closes_after_applying() {
+        let after = with_retv("1", || after_apply(None));
+        assert!(matches!(after, AfterApply::Close));
+    }
+
+    #[test]
+    fn show_operations_skips_the_default_operation() {
+        let mut data = args(&["DP-1"]);
+        let op = "Change resolution".to_string();
+        with_retv("13", || {
+            add_default_operation(&mut data, op, selected("DP-1"))
+        });
+        assert_eq!(data, args(&["DP-1"]));
+    }
+
+    #[test]
+    fn selecting_adds_the_default_operation() {
+        let mut data = args(&["DP-1"]);
+        let op = "Change resolution".to_string();
+        with_retv("1", || {
+            add_default_operation(&mut data, op, selected("DP-1"))
+        });
+        assert_eq!(data, args(&["DP-1", "Change resolution"]));
+
+        // Back from its menu goes back to the outputs
+        let op = "Change resolution".to_string();
+        with_retv("1", || {
+            add_default_operation(&mut data, op, selected("Back"))
+        });
+        assert!(data.is_empty());
     }
 }
@@ -0,0 +1,414 @@
+// Saved layouts, one per file in `$XDG_CONFIG_HOME/rofi-randr/profiles/`.
+// A profile lists every output that was connected when it was saved, and
+// matches again when exactly those outputs are connected. Outputs are
+// keyed by their EDID hash, so a monitor is still recognised when it is
+// plugged into another port. The connector name is the fallback for when
+// there is no EDID, or identical monitors share one.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{fs, io};
+
+use crate::action::gamma::Gamma;
+use crate::action::rate::Rate;
+use crate::action::resolution::Resolution;
+use crate::action::rotate::{Rotation, Transform};
+use crate::backend::{
+    batch, DisplayBackend, Error as BackendError, OutputEntry, OutputLayout,
+};
+use crate::config::{xdg_dir, Config};
+use crate::err::{AppError, ParseError};
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct OutputProfile {
+    pub enabled: bool,
+    // The rest only matters for enabled outputs
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+    #[serde(default)]
+    pub rotation: Rotation,
+    // Only known from sway layout commands, the layout has no reflection
+    #[serde(default)]
+    pub flipped: bool,
+    pub resolution: Option<Resolution>,
+    pub rate: Option<Rate>,
+    // Only kept from sway layout commands. Profiles leave the scale as it
+    // is, as the X backends would reset scaling transforms.
+    pub scale: Option<f64>,
+    // Left as they are when missing, for backends that can not read them
+    pub gamma: Option<Gamma>,
+    pub brightness: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Profile {
+    // Keyed by EDID hash or output name, see `key`
+    pub outputs: BTreeMap<String, OutputProfile>,
+}
+
+impl From<OutputLayout> for OutputProfile {
+    fn from(layout: OutputLayout) -> Self {
+        Self {
+            enabled: layout.enabled,
+            primary: layout.primary,
+            x: layout.x,
+            y: layout.y,
+            rotation: layout.rotation,
+            flipped: false,
+            resolution: layout.resolution,
+            rate: layout.rate,
+            scale: None,
+            gamma: layout.gamma,
+            brightness: layout.brightness,
+        }
+    }
+}
+
+// The arguments of one `--output` in an `xrandr` layout command
+fn from_xrandr_args(args: &[&str]) -> Option<OutputProfile> {
+    let mut o = OutputProfile {
+        enabled: true,
+        ..Default::default()
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().copied();
+        match *arg {
+            "--off" => o.enabled = false,
+            "--primary" => o.primary = true,
+            "--mode" => {
+                o.resolution = Some(Resolution::from_str(value()?).ok()?)
+            }
+            "--rate" => o.rate = Some(value()?.parse().ok()?),
+            "--pos" => {
+                let (x, y) = value()?.split_once('x')?;
+                (o.x, o.y) = (x.parse().ok()?, y.parse().ok()?);
+            }
+            "--rotate" => {
+                o.rotation = match value()? {
+                    "normal" => Rotation::Normal,
+                    "left" => Rotation::Left,
+                    "right" => Rotation::Right,
+                    "inverted" => Rotation::Inverted,
+                    _ => return None,
+                }
+            }
+            "--gamma" => o.gamma = Some(Gamma::from_str(value()?).ok()?),
+            "--brightness" => o.brightness = Some(value()?.parse().ok()?),
+            _ => return None,
+        }
+    }
+
+    Some(o)
+}
+
+// The words after the name in one `output` command of a `swaymsg` layout
+// command
+fn from_sway_args(args: &[&str]) -> Option<OutputProfile> {
+    let mut o = OutputProfile {
+        enabled: true,
+        ..Default::default()
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().copied();
+        match *arg {
+            "disable" => o.enabled = false,
+            "mode" => {
+                let mode = value()?;
+                let (res, rate) = match mode.split_once('@') {
+                    Some((res, rate)) => (res, Some(rate)),
+                    None => (mode, None),
+                };
+                o.resolution = Some(Resolution::from_str(res).ok()?);
+                if let Some(rate) = rate {
+                    o.rate = Some(rate.trim_end_matches("Hz").parse().ok()?);
+                }
+            }
+            "pos" => {
+                o.x = value()?.parse().ok()?;
+                o.y = value()?.parse().ok()?;
+            }
+            // Like `flipped-90`, or `flipped` on its own
+            "transform" => {
+                let transform = value()?;
+                let angle = transform.strip_prefix("flipped");
+                o.flipped = angle.is_some();
+                let angle = angle.unwrap_or(transform);
+                o.rotation = match angle.trim_start_matches('-') {
+                    "" | "0" | "normal" => Rotation::Normal,
+                    "90" => Rotation::Left,
+                    "180" => Rotation::Inverted,
+                    "270" => Rotation::Right,
+                    _ => return None,
+                }
+            }
+            "scale" => o.scale = Some(value()?.parse().ok()?),
+            _ => return None,
+        }
+    }
+
+    Some(o)
+}
+
+fn dir() -> Option<PathBuf> {
+    Some(xdg_dir("XDG_CONFIG_HOME", ".config")?.join("profiles"))
+}
+
+// What `output` is stored under in a profile
+fn key(output: &OutputEntry, connected: &[&OutputEntry]) -> String {
+    let unique = |hash: &String| {
+        connected
+            .iter()
+            .filter(|o| o.edid_hash.as_ref() == Some(hash))
+            .count()
+            == 1
+    };
+
+    match &output.edid_hash {
+        Some(hash) if unique(hash) => hash.clone(),
+        _ => output.name.clone(),
+    }
+}
+
+fn connected(outputs: &[OutputEntry]) -> Vec<&OutputEntry> {
+    outputs.iter().filter(|o| o.connected).collect()
+}
+
+impl Profile {
+    // Takes a snapshot of the current layout of all connected outputs
+    pub fn capture(
+        backend: &mut Box<dyn DisplayBackend>,
+    ) -> Result<Self, AppError> {
+        let current = backend.get_outputs()?;
+        let connected = connected(&current);
+
+        let mut outputs = BTreeMap::new();
+        for layout in backend.get_layout()? {
+            let Some(o) = connected.iter().find(|o| o.name == layout.name)
+            else {
+                continue;
+            };
+            outputs.insert(key(o, &connected), OutputProfile::from(layout));
+        }
+
+        Ok(Self { outputs })
+    }
+
+    // The saved settings of each connected output, by its current name.
+    // Profiles saved before outputs had EDID hashes are keyed by name.
+    fn resolve<'a>(
+        &'a self,
+        outputs: &'a [OutputEntry],
+    ) -> Vec<(&'a str, &'a OutputProfile)> {
+        let connected = connected(outputs);
+
+        connected
+            .iter()
+            .filter_map(|o| {
+                let saved = self
+                    .outputs
+                    .get(&key(o, &connected))
+                    .or_else(|| self.outputs.get(&o.name))?;
+                Some((o.name.as_str(), saved))
+            })
+            .collect()
+    }
+
+    // Reads what `--export-layout` printed back into a profile, keyed by
+    // output name. Shell quoting is not handled, the commands need none.
+    pub fn from_command(command: &str) -> Result<Self, ParseError> {
+        let invalid = || ParseError::LayoutCommand(command.to_string());
+        let words: Vec<_> = command.split_whitespace().collect();
+
+        let mut outputs = BTreeMap::new();
+        match words.split_first() {
+            // `--output NAME ARGS.. --output NAME ARGS..`
+            Some((&"xrandr", args)) => {
+                let mut groups = args.split(|a| *a == "--output");
+                if groups.next().is_none_or(|before| !before.is_empty()) {
+                    return Err(invalid());
+                }
+
+                for group in groups {
+                    let (name, args) =
+                        group.split_first().ok_or_else(invalid)?;
+                    let o = from_xrandr_args(args).ok_or_else(invalid)?;
+                    outputs.insert(name.to_string(), o);
+                }
+            }
+            // `swaymsg 'output NAME ARGS..; output NAME ARGS..'`
+            Some((&"swaymsg", _)) => {
+                let (_, commands) =
+                    command.trim().split_once(' ').ok_or_else(invalid)?;
+                let commands = commands.trim().trim_matches('\'');
+
+                for cmd in commands.split(';') {
+                    let words: Vec<_> = cmd.split_whitespace().collect();
+                    let Some((&"output", rest)) = words.split_first() else {
+                        return Err(invalid());
+                    };
+                    let (name, args) =
+                        rest.split_first().ok_or_else(invalid)?;
+                    let o = from_sway_args(args).ok_or_else(invalid)?;
+                    outputs.insert(name.to_string(), o);
+                }
+            }
+            _ => return Err(invalid()),
+        }
+
+        if outputs.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self { outputs })
+    }
+
+    pub fn matches(&self, outputs: &[OutputEntry]) -> bool {
+        let connected = connected(outputs).len();
+        let resolved = self.resolve(outputs).len();
+
+        connected == self.outputs.len() && resolved == connected
+    }
+
+    // Stops at the first failure, like the operations in the menus. Locked
+    // outputs are left as they are, the others are still set.
+    pub fn apply(
+        &self,
+        backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
+    ) -> Result<(), AppError> {
+        let outputs = backend.get_outputs()?;
+        let is_enabled =
+            |name: &str| outputs.iter().any(|o| o.name == name && o.enabled);
+
+        let mut resolved = self.resolve(&outputs);
+        resolved.retain(|(name, _)| !config.is_locked(name));
+
+        // Saved by another backend, maybe
+        let unsupported = |res| match res {
+            Err(BackendError::Unsupported) => Ok(()),
+            res => res,
+        };
+
+        // In one go where possible, so that it does not flicker
+        batch(backend, |backend| {
+            // Enable before disabling, so that there is always some output on
+            for &(name, o) in resolved.iter().filter(|(_, o)| o.enabled) {
+                if !is_enabled(name) {
+                    backend.enable(name)?;
+                }
+                if let Some(res) = &o.resolution {
+                    backend.set_resolution(name, res)?;
+                }
+                if let Some(rate) = o.rate {
+                    backend.set_rate(name, rate)?;
+                }
+                let transform = Transform {
+                    rotation: o.rotation.clone(),
+                    flipped: o.flipped,
+                };
+                backend.set_transform(name, &transform)?;
+                if let Some(scale) = o.scale {
+                    unsupported(backend.set_scale(name, scale))?;
+                }
+                backend.set_coordinates(name, o.x, o.y)?;
+                if let Some(gamma) = &o.gamma {
+                    unsupported(backend.set_gamma(name, gamma))?;
+                }
+                if let Some(brightness) = o.brightness {
+                    unsupported(backend.set_brightness(name, brightness))?;
+                }
+            }
+
+            // Not every backend has a primary output
+            if let Some((name, _)) = resolved.iter().find(|(_, o)| o.primary) {
+                match backend.set_primary(name) {
+                    Err(BackendError::Unsupported) => (),
+                    res => res?,
+                }
+            }
+
+            for (name, _) in resolved.iter().filter(|(_, o)| !o.enabled) {
+                if is_enabled(name) {
+                    backend.disable(name)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn save(&self, name: &str) -> Result<PathBuf, AppError> {
+        if name.is_empty() || name.contains('/') {
+            return Err(AppError::ProfileName(name.to_string()));
+        }
+        let dir = dir().ok_or(AppError::ProfileName(name.to_string()))?;
+        let path = dir.join(format!("{name}.toml"));
+
+        let write = || -> io::Result<()> {
+            fs::create_dir_all(&dir)?;
+            let content = toml::to_string(self).map_err(io::Error::other)?;
+            fs::write(&path, content)
+        };
+
+        match write() {
+            Ok(()) => Ok(path),
+            Err(source) => Err(AppError::ProfileWrite { path, source }),
+        }
+    }
+
+    // All saved profiles, sorted by name. No directory means no profiles.
+    pub fn load_all() -> Result<Vec<(String, Self)>, AppError> {
+        let Some(dir) = dir() else {
+            return Ok(Vec::new());
+        };
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(source) => {
+                return Err(AppError::ProfileRead { path: dir, source })
+            }
+        };
+
+        let mut profiles = Vec::new();
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(source) => {
+                    return Err(AppError::ProfileRead { path: dir, source })
+                }
+            };
+            if path.extension().is_none_or(|ext| ext != "toml") {
+                continue;
+            }
+            let Some(name) = path.file_stem() else {
+                continue;
+            };
+            let name = name.to_string_lossy().to_string();
+
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(source) => {
+                    return Err(AppError::ProfileRead { path, source })
+                }
+            };
+            let profile = toml::from_str(&content)
+                .map_err(|source| AppError::ProfileParse { path, source })?;
+
+            profiles.push((name, profile));
+        }
+
+        profiles.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(profiles)
+    }
+}
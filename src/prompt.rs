@@ -0,0 +1,86 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// User chosen prompts, from the `[prompts]` table in the config file, to
+// translate or reword them. Keys are the variant names in snake_case,
+// e.g. `output = "..."`.
+pub type PromptNames = HashMap<Prompt, String>;
+
+// The prompt shown in front of rofi's input field, one for each menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Prompt {
+    Error,
+    Output,
+    Operation,
+    Resolution,
+    Rate,
+    RateLimit,
+    Rotation,
+    KeepRotation,
+    Position,
+    Panning,
+    ProjectorFit,
+    MaxRenderTime,
+    Arrangement,
+    ColorDepth,
+    Hdr,
+    Tearing,
+    ScaleFilter,
+    Subpixel,
+    Power,
+    Gamma,
+    ColorProfile,
+    Brightness,
+    Workspace,
+    Seat,
+    ConfirmDisable,
+    // For outputs other than the last, with `confirm_disable = "always"`
+    ConfirmDisableOutput,
+    ConfirmChanges,
+    Layout,
+    Edid,
+    Identify,
+}
+
+impl Prompt {
+    pub fn text(&self, names: &PromptNames) -> String {
+        if let Some(text) = names.get(self) {
+            return text.clone();
+        }
+
+        match self {
+            Self::Error => "ERROR",
+            Self::Output => "Select output",
+            Self::Operation => "Select operation",
+            Self::Resolution => "Select resolution",
+            Self::Rate => "Select rate",
+            Self::RateLimit => "Select rate limit",
+            Self::Rotation => "Select rotation",
+            Self::KeepRotation => "Keep this rotation?",
+            Self::Position => "Select position",
+            Self::Panning => "Select panning area",
+            Self::ProjectorFit => "Select how to fit",
+            Self::MaxRenderTime => "Select max render time",
+            Self::Arrangement => "Select arrangement",
+            Self::ColorDepth => "Select color depth",
+            Self::Hdr => "Set HDR",
+            Self::Tearing => "Set tearing",
+            Self::ScaleFilter => "Select scale filter",
+            Self::Subpixel => "Select subpixel order",
+            Self::Power => "Select power state",
+            Self::Gamma => "Select gamma",
+            Self::ColorProfile => "Select color profile",
+            Self::Brightness => "Select brightness",
+            Self::Workspace => "Select workspace",
+            Self::Seat => "Select seat",
+            Self::ConfirmDisable => "Disable last active output?",
+            Self::ConfirmDisableOutput => "Disable this output?",
+            Self::ConfirmChanges => "Apply these changes?",
+            Self::Layout => "Layout",
+            Self::Edid => "EDID",
+            Self::Identify => "Outputs",
+        }
+        .to_string()
+    }
+}
@@ -1,14 +1,51 @@
 // Defines data structures and methods to construct and
 // print lists in the format that rofi understands.
+use itertools::Itertools;
+use std::io::{self, Write};
+use std::path::Path;
 use strum::IntoEnumIterator;
 
-use crate::action::resolution::Resolution;
+use crate::action::depth::{bpc, DEPTHS};
+use crate::action::dock;
+use crate::action::fit::FitMode;
+use crate::action::gamma::{self, Gamma, BRIGHTNESS_STEPS, GAMMA_PRESETS};
+use crate::action::icc;
+use crate::action::layout::Arrangement;
+use crate::action::panning;
+use crate::action::plan::{self, Change};
+use crate::action::power::PowerState;
+use crate::action::primary;
+use crate::action::rate;
+use crate::action::render_time;
+use crate::action::scale_filter::Filter;
+use crate::action::subpixel::Subpixel;
+use crate::action::toggle;
 use crate::action::{
-    position::Relation, rotate::Rotation, Action, Operation, ParseResult,
+    position::Relation,
+    rotate::{Transform, REVERT},
+    Action, Operation, ParseResult, CREATE_OUTPUT, EXPORT_LAYOUT, IDENTIFY,
+    RESCAN, SWAP_PRIMARY,
 };
-use crate::backend::{DisplayBackend, OutputEntry, RateEntry, ResolutionEntry};
+use crate::backend::{
+    DisplayBackend, Geometry, OutputEntry, RateEntry, ResolutionEntry,
+};
+use crate::config::{Config, OutputSort};
 use crate::err::AppError;
-use crate::icon::Icon;
+use crate::icon::{Icon, IconNames};
+use crate::prompt::{Prompt, PromptNames};
+use crate::state::State;
+
+// Between the position and the value of a row in its info field. The
+// position is only digits, so the value may contain this too.
+const INFO_SEPARATOR: char = ':';
+
+// The value of a row from its info field, as passed back in `ROFI_INFO`
+pub fn info_value(info: &str) -> &str {
+    match info.split_once(INFO_SEPARATOR) {
+        Some((index, value)) if index.parse::<usize>().is_ok() => value,
+        _ => info,
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct ListItem {
@@ -17,23 +54,33 @@ pub struct ListItem {
     pub icon: Option<Icon>,
     pub meta: Option<String>,
     pub non_selectable: bool,
+    // The value to pass on when it is not the text itself
     pub info: Option<String>,
+    // Highlighted by rofi, for what is currently set
+    pub active: bool,
 }
 
 impl ListItem {
-    pub fn rofi_print(&self) {
+    // Every row gets its position in the list and its value as info, so
+    // that the value comes back the same however the row was selected.
+    // See `get_input` for the other end.
+    pub fn rofi_print(
+        &self,
+        out: &mut impl Write,
+        index: usize,
+        icons: &IconNames,
+    ) -> io::Result<()> {
         let mut mods: Vec<String> = Vec::new();
         mods.push(format!("nonselectable\x1f{}", self.non_selectable));
 
         if let Some(icon) = &self.icon {
-            mods.push(format!("icon\x1f{}", icon.name()));
+            mods.push(format!("icon\x1f{}", icon.name(icons)));
         }
         if let Some(meta) = &self.meta {
             mods.push(format!("meta\x1f{meta}"));
         }
-        if let Some(info) = &self.info {
-            mods.push(format!("info\x1f{info}"));
-        }
+        let value = self.info.as_ref().unwrap_or(&self.text);
+        mods.push(format!("info\x1f{index}{INFO_SEPARATOR}{value}"));
         let cmt = if self.comments.is_empty() {
             String::new()
         } else {
@@ -41,7 +88,7 @@ impl ListItem {
             format!(" <span style='italic' size='small'>({cmt_str})</span>")
         };
 
-        println!("{}{}\0{}", self.text, cmt, mods.join("\x1f"),);
+        writeln!(out, "{}{}\0{}", self.text, cmt, mods.join("\x1f"))
     }
 
     pub fn back() -> Self {
@@ -57,7 +104,7 @@ impl ListItem {
 // List of options to show next
 #[derive(Debug, Default)]
 pub struct List {
-    pub prompt: Option<String>,
+    pub prompt: Option<Prompt>,
     pub message: Option<String>,
     // Inverted from rofi-script due to more sensible `Default`
     pub allow_custom: bool,
@@ -69,41 +116,134 @@ pub struct List {
 }
 
 impl List {
-    pub fn rofi_print(&self) {
+    // The rofi script protocol, see `rofi-script(5)`
+    pub fn rofi_print(
+        &self,
+        out: &mut impl Write,
+        icons: &IconNames,
+        prompts: &PromptNames,
+    ) -> io::Result<()> {
         if let Some(prompt) = &self.prompt {
-            println!("\0prompt\x1f{prompt}");
+            writeln!(out, "\0prompt\x1f{}", prompt.text(prompts))?;
         }
 
         if let Some(msg) = &self.message {
-            println!("\0message\x1f{msg}");
+            writeln!(out, "\0message\x1f{msg}")?;
         } else {
             // This needs to be reset between lists
-            println!("\0message\x1f");
+            writeln!(out, "\0message\x1f")?;
         };
 
-        println!("\0no-custom\x1f{}", !self.allow_custom);
-        println!("\0keep-selection\x1f{}", self.keep_selection);
-        println!("\0markup-rows\x1f{}", !self.no_markup);
+        writeln!(out, "\0no-custom\x1f{}", !self.allow_custom)?;
+        writeln!(out, "\0keep-selection\x1f{}", self.keep_selection)?;
+        writeln!(out, "\0markup-rows\x1f{}", !self.no_markup)?;
+        // Without this, rofi does not pass the custom keybindings on
+        writeln!(out, "\0use-hot-keys\x1ftrue")?;
 
-        self.list.iter().for_each(ListItem::rofi_print);
+        // Also needs to be reset between lists
+        let active = self.list.iter().positions(|item| item.active).join(",");
+        writeln!(out, "\0active\x1f{active}")?;
+
+        // Counted from 1, like the rows that rofi can select by number
+        for (i, item) in self.list.iter().enumerate() {
+            item.rofi_print(out, i + 1, icons)?;
+        }
         if !self.no_back {
-            ListItem::back().rofi_print();
+            ListItem::back().rofi_print(out, self.list.len() + 1, icons)?;
         }
+        Ok(())
     }
 
     pub fn error(msg: &str) -> Self {
         Self {
-            prompt: Some("ERROR".into()),
+            prompt: Some(Prompt::Error),
             message: Some(msg.to_string()),
             ..Default::default()
         }
     }
+
+    // Shown after exporting an EDID. Only the message, `Back` returns to
+    // the operations.
+    pub fn edid_exported(
+        output: &str,
+        path: &Path,
+        copied: io::Result<()>,
+    ) -> Self {
+        let status = match copied {
+            Ok(()) => String::from("Copied to the clipboard as hex"),
+            Err(e) => format!("Could not copy to the clipboard: {e}"),
+        };
+
+        Self {
+            prompt: Some(Prompt::Edid),
+            // On one line, like the export message
+            message: Some(format!(
+                "{output}: written to {} — {status}",
+                path.display()
+            )),
+            ..Default::default()
+        }
+    }
+
+    // Shown after rotating, when that needs confirming. Reverting comes
+    // first, so that it only takes enter on a display that is hard to read.
+    // Either choice is passed on as the rotation to end up with. Without a
+    // single previous rotation, as after rotating all outputs that were not
+    // all rotated the same, reverting is passed on as `rotate::REVERT`.
+    pub fn confirm_rotation(
+        transform: &Transform,
+        previous: Option<&Transform>,
+        timeout: u64,
+    ) -> Self {
+        let (revert_comment, revert_info) = match previous {
+            Some(previous) => (previous.explain(), previous.to_string()),
+            None => ("previous rotations".to_string(), REVERT.to_string()),
+        };
+
+        Self {
+            prompt: Some(Prompt::KeepRotation),
+            message: Some(format!("Reverting in {timeout} seconds")),
+            list: vec![
+                ListItem {
+                    text: "Revert".to_string(),
+                    comments: vec![revert_comment],
+                    icon: Some(Icon::Reset),
+                    info: Some(revert_info),
+                    ..Default::default()
+                },
+                ListItem {
+                    text: "Keep".to_string(),
+                    comments: vec![transform.explain()],
+                    icon: Some(Icon::Apply),
+                    info: Some(transform.to_string()),
+                    ..Default::default()
+                },
+            ],
+            no_back: true,
+            ..Default::default()
+        }
+    }
 }
 
 // TODO: lots of duplication here
+impl From<&Geometry> for ListItem {
+    fn from(g: &Geometry) -> Self {
+        ListItem {
+            text: format!("{}x{}+{}+{}", g.width, g.height, g.x, g.y),
+            comments: vec![
+                g.rotation.to_string().to_lowercase(),
+                format!("scale {}", g.scale),
+            ],
+            icon: Some(Icon::Fitsize),
+            non_selectable: true,
+            ..Default::default()
+        }
+    }
+}
+
 impl From<&OutputEntry> for ListItem {
     fn from(output: &OutputEntry) -> Self {
-        let (icon, comments) = match (output.connected, output.enabled) {
+        let (icon, mut comments) = match (output.connected, output.enabled) {
             (false, _) => {
                 (Icon::Disconnected, vec!["disconnected".to_string()])
             }
@@ -111,6 +251,16 @@ impl From<&OutputEntry> for ListItem {
             _ => (Icon::Connected, Vec::new()),
         };
 
+        if let Some(description) = &output.description {
+            comments.push(description.clone());
+        }
+        if let Some(provider) = &output.provider {
+            comments.push(provider.clone());
+        }
+        if output.adaptive_sync == Some(true) {
+            comments.push("VRR".to_string());
+        }
+
         ListItem {
             text: output.name.clone(),
             comments,
@@ -121,6 +271,22 @@ impl From<&OutputEntry> for ListItem {
     }
 }
 
+impl ListItem {
+    // Outputs with an alias show it instead of their name. The name is still
+    // what gets stored, through the info field (see `get_args`).
+    fn output(output: &OutputEntry, config: &Config) -> Self {
+        let mut item = ListItem::from(output);
+
+        if let Some(alias) = config.aliases.get(&output.name) {
+            item.text = alias.clone();
+            item.comments.insert(0, output.name.clone());
+            item.info = Some(output.name.clone());
+        }
+
+        item
+    }
+}
+
 impl From<Operation> for ListItem {
     fn from(op: Operation) -> Self {
         ListItem {
@@ -141,12 +307,31 @@ impl From<Relation> for ListItem {
     }
 }
 
-impl From<Rotation> for ListItem {
-    fn from(rot: Rotation) -> Self {
+impl From<Transform> for ListItem {
+    fn from(t: Transform) -> Self {
         ListItem {
-            text: rot.to_string(),
-            comments: vec![rot.explain()],
-            icon: Some(Icon::from(rot)),
+            text: t.to_string(),
+            comments: vec![t.explain()],
+            icon: Some(Icon::from(t.rotation)),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Change> for ListItem {
+    fn from(change: &Change) -> Self {
+        let icon = match change {
+            Change::Enable(_) => Icon::from(Operation::Enable),
+            Change::Disable(_) => Icon::from(Operation::Disable),
+            Change::RightOf(..) => Icon::from(Relation::RightOf),
+            Change::Move(..) => Icon::Arrange,
+            Change::Primary(_) => Icon::from(Operation::SetPrimary),
+        };
+
+        ListItem {
+            text: change.to_string(),
+            icon: Some(icon),
+            non_selectable: true,
             ..Default::default()
         }
     }
@@ -154,23 +339,40 @@ impl From<Rotation> for ListItem {
 
 impl From<&ResolutionEntry> for ListItem {
     fn from(res_entry: &ResolutionEntry) -> Self {
-        let comments = if res_entry.current {
+        let mut comments = if res_entry.current {
             vec!["Current".to_string()]
         } else {
             Vec::new()
         };
+        if res_entry.interlaced {
+            comments.push("interlaced".to_string());
+        }
+
+        // Rofi also matches on the meta field, so e.g. `16:9` or `1440p`
+        // find a resolution without being shown on every row
+        let res = &res_entry.val;
+        let meta = format!(
+            "{} {}p {:.1}MP",
+            res.aspect_ratio(),
+            res.height,
+            res.pixels() as f64 / 1e6
+        );
 
         ListItem {
-            text: format!("{}x{}", res_entry.val.width, res_entry.val.height),
+            text: format!("{}x{}", res.width, res.height),
             icon: Some(Icon::Fitsize),
             comments,
+            meta: Some(meta),
+            active: res_entry.current,
             ..Default::default()
         }
     }
 }
 
-impl From<&RateEntry> for ListItem {
-    fn from(rate_entry: &RateEntry) -> Self {
+impl ListItem {
+    // The text is rounded to `precision` decimals, so the exact rate is
+    // passed in the info field. `get_args` stores that instead of the text.
+    fn rate(rate_entry: &RateEntry, precision: usize) -> Self {
         let comments = if rate_entry.current {
             vec!["Current".to_string()]
         } else {
@@ -178,9 +380,12 @@ impl From<&RateEntry> for ListItem {
         };
 
         ListItem {
-            text: format!("{:.2} Hz", rate_entry.val),
+            text: format!("{:.precision$} Hz", rate_entry.val),
             icon: Some(Icon::Rate),
             comments,
+            meta: Some(rate_entry.val.to_string()),
+            info: Some(format!("{} Hz", rate_entry.val)),
+            active: rate_entry.current,
             ..Default::default()
         }
     }
@@ -190,20 +395,165 @@ impl ParseResult<Action> {
     // All outputs on the system (enabled+disabled+disconnected)
     pub fn output_list(
         backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
     ) -> Result<Self, AppError> {
         let mut outputs = backend.get_outputs()?;
+        outputs.retain(|o| config.lists_output(o));
 
-        // List connected outputs first
-        outputs.sort_by(|a, b| bool::cmp(&b.connected, &a.connected));
+        let state = State::load();
+        let last = match config.remember_last_output {
+            true => state.last_output,
+            false => None,
+        };
+        let is_last = |o: &OutputEntry| Some(&o.name) == last.as_ref();
+
+        match config.output_sort {
+            // The last used output first, then the other connected ones
+            OutputSort::Connected => {
+                outputs.sort_by_key(|o| (!is_last(o), !o.connected));
+            }
+            // Disabled outputs have no position, they go last
+            OutputSort::Position => outputs.sort_by_key(|o| {
+                let pos = o.geometry.as_ref().map(|g| (g.x, g.y));
+                (pos.is_none(), pos)
+            }),
+            OutputSort::Name => {
+                outputs.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+        }
+
+        let mut list: Vec<ListItem> = Vec::new();
+
+        // Entries for all outputs would be refused, see `Action::apply`
+        let any_locked = outputs
+            .iter()
+            .any(|o| (o.enabled || o.connected) && config.is_locked(&o.name));
+
+        // Only worth offering when they would change something
+        let internal = dock::internal(&outputs).filter(|_| !any_locked);
+        if let Some(internal) = internal {
+            let externals = dock::externals(&outputs);
+
+            if outputs.iter().any(|o| o.enabled && o.name != internal.name) {
+                let mut item = ListItem::from(Operation::LaptopOnly);
+                item.comments.push(internal.name.clone());
+                list.push(item);
+            }
+            if !externals.is_empty()
+                && (internal.enabled || externals.iter().any(|o| !o.enabled))
+            {
+                let mut item = ListItem::from(Operation::ExternalOnly);
+                item.comments
+                    .extend(externals.iter().map(|o| o.name.clone()));
+                list.push(item);
+            }
+        }
+
+        if backend.capabilities().primary {
+            let previous = state.previous_primary.as_deref();
+            if let Some(target) = primary::swap_target(&outputs, previous) {
+                list.push(ListItem {
+                    text: SWAP_PRIMARY.to_string(),
+                    comments: vec![target.name.clone()],
+                    icon: Some(Icon::Primary),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let enabled = outputs.iter().filter(|o| o.enabled);
+        if enabled.clone().count() > 1 {
+            if !any_locked {
+                let mut item =
+                    ListItem::from(Operation::RotateAll(Transform::default()));
+                item.comments.extend(enabled.map(|o| o.name.clone()));
+                list.push(item);
+                list.push(ListItem::from(Operation::Arrange(
+                    Arrangement::default(),
+                )));
+            }
+            if backend.capabilities().gamma && !any_locked {
+                list.push(ListItem::from(Operation::BrightnessAll(1.0)));
+                list.push(ListItem::from(
+                    Operation::GammaAll(Gamma::default()),
+                ));
+            }
+            list.push(ListItem {
+                text: IDENTIFY.to_string(),
+                comments: vec!["which output is where".to_string()],
+                icon: Some(Icon::Identify),
+                ..Default::default()
+            });
+        }
+
+        list.extend(outputs.iter().map(|o| {
+            let mut item = ListItem::output(o, config);
+            item.active = o.primary;
+            if is_last(o) {
+                item.comments.push("last used".to_string());
+            }
+            if config.is_locked(&o.name) {
+                item.comments.push("locked".to_string());
+            }
+            item
+        }));
+
+        let caps = backend.capabilities();
+        if caps.export {
+            list.push(ListItem {
+                text: EXPORT_LAYOUT.to_string(),
+                comments: vec!["as a shell command".to_string()],
+                icon: Some(Icon::Export),
+                ..Default::default()
+            });
+        }
+        if caps.virtual_outputs {
+            list.push(ListItem {
+                text: CREATE_OUTPUT.to_string(),
+                comments: vec!["headless".to_string()],
+                icon: Some(Icon::Create),
+                ..Default::default()
+            });
+        }
+        list.push(ListItem {
+            text: RESCAN.to_string(),
+            comments: vec!["read the outputs again".to_string()],
+            icon: Some(Icon::Rescan),
+            ..Default::default()
+        });
 
         Ok(Self::Next(List {
-            prompt: Some("Select output".to_string()),
-            list: outputs.iter().map(ListItem::from).collect(),
+            prompt: Some(Prompt::Output),
+            list,
             no_back: true,
             ..Default::default()
         }))
     }
 
+    // Nothing to choose, the command is shown in the message
+    pub fn export_list(command: &str, copied: io::Result<()>) -> Self {
+        let status = match copied {
+            Ok(()) => String::from("Copied to the clipboard"),
+            Err(e) => format!("Could not copy to the clipboard: {e}"),
+        };
+
+        Self::Next(List {
+            prompt: Some(Prompt::Layout),
+            // On one line, rofi reads the header line by line
+            message: Some(format!("{command} — {status}")),
+            ..Default::default()
+        })
+    }
+
+    // Only the message, `Back` returns to the outputs
+    pub fn identify_list(regions: String) -> Self {
+        Self::Next(List {
+            prompt: Some(Prompt::Identify),
+            message: Some(regions),
+            ..Default::default()
+        })
+    }
+
     // left/right/above/below
     pub fn relation_list(backend: &mut Box<dyn DisplayBackend>) -> Self {
         let list = backend
@@ -213,48 +563,341 @@ impl ParseResult<Action> {
             .collect();
 
         Self::Next(List {
-            prompt: Some("Select position".to_string()),
+            prompt: Some(Prompt::Position),
             list,
             ..Default::default()
         })
     }
 
-    // left/right/normal/inverted
-    pub fn rotation_list() -> Self {
+    // left/right/normal/inverted, and flipped versions of those if `flip`
+    pub fn rotation_list(flip: bool) -> Self {
         Self::Next(List {
-            prompt: Some("Select rotation".to_string()),
-            list: Rotation::iter().map(ListItem::from).collect(),
+            prompt: Some(Prompt::Rotation),
+            list: Transform::all(flip)
+                .into_iter()
+                .map(ListItem::from)
+                .collect(),
             ..Default::default()
         })
     }
 
-    // Confirm menu to avoid accidentally disabling the last display
-    pub fn confirm_disable_list() -> Self {
+    // 24/30 bit
+    pub fn depth_list() -> Self {
+        let list = DEPTHS
+            .iter()
+            .map(|d| ListItem {
+                text: d.to_string(),
+                comments: vec![format!("{} bits per channel", bpc(*d))],
+                icon: Some(Icon::ColorDepth),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::ColorDepth),
+            list,
+            ..Default::default()
+        })
+    }
+
+    // linear/nearest
+    pub fn projector_fit_list() -> Self {
+        let list = FitMode::iter()
+            .map(|f| ListItem {
+                text: f.to_string(),
+                comments: vec![f.explain().to_string()],
+                icon: Some(Icon::ProjectorFit),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::ProjectorFit),
+            list,
+            ..Default::default()
+        })
+    }
+
+    pub fn scale_filter_list() -> Self {
+        let list = Filter::iter()
+            .map(|f| ListItem {
+                text: f.to_string(),
+                icon: Some(Icon::ScaleFilter),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::ScaleFilter),
+            list,
+            ..Default::default()
+        })
+    }
+
+    // Only turning it off, areas are typed in
+    pub fn panning_list(output: &str) -> Self {
+        Self::Next(List {
+            prompt: Some(Prompt::Panning),
+            message: Some(format!("{output} (or type e.g. 3840x2160+0+0)")),
+            allow_custom: true,
+            list: vec![ListItem {
+                text: panning::OFF.to_string(),
+                icon: Some(Icon::Off),
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+    }
+
+    // Off first, then from least to most latency saved
+    pub fn max_render_time_list(output: &str) -> Self {
+        let off = ListItem {
+            text: render_time::OFF.to_string(),
+            icon: Some(Icon::Off),
+            ..Default::default()
+        };
+        let suggested = render_time::SUGGESTED.iter().map(|ms| ListItem {
+            text: format!("{ms} ms"),
+            icon: Some(Icon::RenderTime),
+            ..Default::default()
+        });
+
+        Self::Next(List {
+            prompt: Some(Prompt::MaxRenderTime),
+            message: Some(format!("{output} (or type e.g. 5)")),
+            allow_custom: true,
+            list: std::iter::once(off).chain(suggested).collect(),
+            ..Default::default()
+        })
+    }
+
+    // rgb/bgr/vrgb/vbgr/none
+    pub fn subpixel_list() -> Self {
+        let list = Subpixel::iter()
+            .map(|s| ListItem {
+                text: s.to_string(),
+                icon: Some(Icon::Subpixel),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::Subpixel),
+            list,
+            ..Default::default()
+        })
+    }
+
+    // The profiles in `icc::profile_dir`, any other can be typed in
+    pub fn color_profile_list(output: &str) -> Self {
+        let list = icc::profiles()
+            .iter()
+            .map(|p| ListItem {
+                text: p
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                icon: Some(Icon::ColorProfile),
+                info: Some(p.to_string_lossy().to_string()),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::ColorProfile),
+            message: Some(format!("{output} (or type a path)")),
+            allow_custom: true,
+            list,
+            ..Default::default()
+        })
+    }
+
+    // row/stack/grid
+    pub fn arrangement_list() -> Self {
+        let list = Arrangement::iter()
+            .map(|a| ListItem {
+                text: a.to_string(),
+                comments: vec![a.explain()],
+                icon: Some(Icon::Arrange),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::Arrangement),
+            list,
+            ..Default::default()
+        })
+    }
+
+    // on/standby/off
+    // `all` when the backend sets the power of all outputs at once
+    pub fn power_list(all: bool) -> Self {
+        let list = PowerState::iter()
+            .map(|p| ListItem {
+                text: p.to_string(),
+                icon: Some(Icon::Power),
+                ..Default::default()
+            })
+            .collect();
+
+        Self::Next(List {
+            prompt: Some(Prompt::Power),
+            message: all.then(|| "This affects all outputs".to_string()),
+            list,
+            ..Default::default()
+        })
+    }
+
+    // Reset first, then the presets from warm to warmest
+    pub fn gamma_list(output: &str) -> Self {
+        let reset = ListItem {
+            text: gamma::RESET.to_string(),
+            comments: vec!["1:1:1".to_string()],
+            icon: Some(Icon::Reset),
+            ..Default::default()
+        };
+        let presets = GAMMA_PRESETS.iter().map(|(name, g)| ListItem {
+            text: name.to_string(),
+            comments: vec![format!("{}:{}:{}", g.red, g.green, g.blue)],
+            icon: Some(Icon::Gamma),
+            ..Default::default()
+        });
+
         Self::Next(List {
-            prompt: Some("Disable last active output?".to_string()),
+            prompt: Some(Prompt::Gamma),
+            message: Some(format!("{output} (or type e.g. 1.0:0.9:0.8)")),
+            allow_custom: true,
+            list: std::iter::once(reset).chain(presets).collect(),
+            ..Default::default()
+        })
+    }
+
+    // Reset first, then from bright to dim
+    pub fn brightness_list() -> Self {
+        let reset = ListItem {
+            text: gamma::RESET.to_string(),
+            comments: vec!["100%".to_string()],
+            icon: Some(Icon::Reset),
+            ..Default::default()
+        };
+        let steps = BRIGHTNESS_STEPS.iter().map(|p| ListItem {
+            text: format!("{p}%"),
+            icon: Some(Icon::Brightness),
+            ..Default::default()
+        });
+
+        Self::Next(List {
+            prompt: Some(Prompt::Brightness),
+            list: std::iter::once(reset).chain(steps).collect(),
+            ..Default::default()
+        })
+    }
+
+    // On/off, for the setting that `prompt` asks for
+    pub fn toggle_list(prompt: Prompt) -> Self {
+        let item = |text: &str, icon: Icon| ListItem {
+            text: text.to_string(),
+            icon: Some(icon),
+            ..Default::default()
+        };
+
+        Self::Next(List {
+            prompt: Some(prompt),
             list: vec![
-                ListItem {
-                    text: "Yes".to_string(),
-                    icon: Some(Icon::Apply),
-                    ..Default::default()
-                },
+                item(toggle::ON, Icon::On),
+                item(toggle::OFF, Icon::Off),
             ],
             ..Default::default()
         })
     }
 
+    // Confirm menu to avoid accidentally disabling the last display
+    // The prompt warns when it is the `last` enabled output
+    pub fn confirm_disable_list(last: bool) -> Self {
+        let prompt = match last {
+            true => Prompt::ConfirmDisable,
+            false => Prompt::ConfirmDisableOutput,
+        };
+
+        Self::Next(List {
+            prompt: Some(prompt),
+            list: vec![ListItem {
+                text: "Yes".to_string(),
+                icon: Some(Icon::Apply),
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+    }
+
+    // What an action on several outputs is about to change, with outputs
+    // called by their alias. `Back` is the way to not apply it.
+    pub fn changes_list(changes: &[Change], config: &Config) -> Self {
+        let apply = ListItem {
+            text: plan::APPLY.to_string(),
+            icon: Some(Icon::Apply),
+            ..Default::default()
+        };
+
+        Self::Next(List {
+            prompt: Some(Prompt::ConfirmChanges),
+            message: changes
+                .is_empty()
+                .then(|| "Nothing to change".to_string()),
+            list: std::iter::once(apply)
+                .chain(changes.iter().map(|c| {
+                    ListItem::from(&c.renamed(|o| config.label(o).to_string()))
+                }))
+                .collect(),
+            ..Default::default()
+        })
+    }
+
     // Possible rates for the current resolution of the given output
     pub fn rate_list(
         backend: &mut Box<dyn DisplayBackend>,
         output: &str,
+        config: &Config,
     ) -> Result<Self, AppError> {
         let mut rates = backend.get_rates(output)?;
 
+        // The current rate stays, so that the list shows what is set
+        rates.retain(|r| r.current || config.lists_rate(r.val));
         rates.sort_by(|a, b| f64::total_cmp(&b.val, &a.val));
 
+        // Typed in rates are for custom modelines, see `rate::parse`
+        let precision = config.rate_precision;
+        Ok(Self::Next(List {
+            prompt: Some(Prompt::Rate),
+            message: Some(format!("{output} (or type e.g. 75.1)")),
+            allow_custom: true,
+            list: rates.iter().map(|r| ListItem::rate(r, precision)).collect(),
+            ..Default::default()
+        }))
+    }
+
+    // The power saver entry, which tells the lowest rate it goes to
+    pub fn rate_limit_list(
+        backend: &mut Box<dyn DisplayBackend>,
+        output: &str,
+        config: &Config,
+    ) -> Result<Self, AppError> {
+        let precision = config.rate_precision;
+        let comments = rate::min(backend, output)?
+            .map(|min| vec![format!("{min:.precision$} Hz")])
+            .unwrap_or_default();
+
         Ok(Self::Next(List {
-            prompt: Some("Select rate".to_string()),
-            list: rates.iter().map(ListItem::from).collect(),
+            prompt: Some(Prompt::RateLimit),
+            message: Some(format!("{output} (or type a maximum e.g. 60)")),
+            allow_custom: true,
+            list: vec![ListItem {
+                text: rate::POWER_SAVER.to_string(),
+                icon: Some(Icon::PowerSaver),
+                comments,
+                ..Default::default()
+            }],
             ..Default::default()
         }))
     }
@@ -262,22 +905,28 @@ impl ParseResult<Action> {
     // Available resolutions for the given output
     pub fn resolution_list(
         backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
         output: &str,
     ) -> Result<Self, AppError> {
         let mut resolutions = backend.get_resolutions(output)?;
 
+        // Filtering everything out would leave nothing to choose from.
+        // The current resolution stays, so that the list shows what is set.
+        if resolutions
+            .iter()
+            .any(|r| config.lists_aspect_ratio(&r.val))
+        {
+            resolutions
+                .retain(|r| r.current || config.lists_aspect_ratio(&r.val));
+        }
+
         // Sort (reversed) by total pixel count
-        let res_cmp = |m1: &Resolution, m2: &Resolution| {
-            u64::cmp(
-                &(u64::from(m1.width) * u64::from(m1.height)),
-                &(u64::from(m2.width) * u64::from(m2.height)),
-            )
-        };
-        resolutions.sort_by(|a, b| res_cmp(&b.val, &a.val));
+        resolutions.sort_by_key(|r| std::cmp::Reverse(r.val.pixels()));
 
         Ok(Self::Next(List {
-            prompt: Some("Select resolution ".to_string()),
-            message: Some(output.to_string()),
+            prompt: Some(Prompt::Resolution),
+            message: Some(format!("{output} (or type e.g. 1920x1080@60)")),
+            allow_custom: true,
             list: resolutions.iter().map(ListItem::from).collect(),
             ..Default::default()
         }))
@@ -286,20 +935,22 @@ impl ParseResult<Action> {
     // list_outputs not equal to `output`
     pub fn relatives_list(
         backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
         output: &str,
         relation: &Relation,
     ) -> Result<Self, AppError> {
         let outputs = backend.get_outputs()?;
-        let mut others: Vec<&OutputEntry> =
-            outputs.iter().filter(|o| o.name != output).collect();
+        let mut others: Vec<&OutputEntry> = outputs
+            .iter()
+            .filter(|o| o.name != output && config.lists_output(o))
+            .collect();
 
         // List connected outputs first
         others.sort_by(|a, b| bool::cmp(&b.connected, &a.connected));
 
         let mut list = others
             .iter()
-            .copied()
-            .map(ListItem::from)
+            .map(|o| ListItem::output(o, config))
             .collect::<Vec<ListItem>>();
 
         // In this menu, you should only be able to select enabled displays
@@ -310,8 +961,100 @@ impl ParseResult<Action> {
         }
 
         Ok(Self::Next(List {
-            prompt: Some("Select output".to_string()),
-            message: Some(format!("{output} ({relation}...)")),
+            prompt: Some(Prompt::Output),
+            message: Some(format!("{output} ({relation} ...)")),
+            list,
+            ..Default::default()
+        }))
+    }
+
+    // Connected outputs other than `output`. Unlike with positioning, the
+    // target may be disabled: mirroring enables it.
+    pub fn mirror_target_list(
+        backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
+        output: &str,
+    ) -> Result<Self, AppError> {
+        let outputs = backend.get_outputs()?;
+        let list = outputs
+            .iter()
+            .filter(|o| o.name != output && o.connected)
+            .map(|o| ListItem::output(o, config))
+            .collect();
+
+        Ok(Self::Next(List {
+            prompt: Some(Prompt::Output),
+            message: Some(format!("Mirror {output} to...")),
+            list,
+            ..Default::default()
+        }))
+    }
+
+    // Other enabled outputs, since only those have a position
+    pub fn swap_target_list(
+        backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
+        output: &str,
+    ) -> Result<Self, AppError> {
+        let outputs = backend.get_outputs()?;
+        let list = outputs
+            .iter()
+            .filter(|o| o.name != output && o.enabled)
+            .map(|o| ListItem::output(o, config))
+            .collect();
+
+        Ok(Self::Next(List {
+            prompt: Some(Prompt::Output),
+            message: Some(format!("Swap {output} with...")),
+            list,
+            ..Default::default()
+        }))
+    }
+
+    // Existing workspaces. New ones can be typed in.
+    pub fn workspace_list(
+        backend: &mut Box<dyn DisplayBackend>,
+        output: &str,
+    ) -> Result<Self, AppError> {
+        let list = backend
+            .get_workspaces()?
+            .into_iter()
+            .map(|ws| ListItem {
+                text: ws.name,
+                comments: vec![format!("on {}", ws.output)],
+                icon: Some(Icon::Workspace),
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Self::Next(List {
+            prompt: Some(Prompt::Workspace),
+            message: Some(format!("Assign to {output}...")),
+            allow_custom: true,
+            list,
+            ..Default::default()
+        }))
+    }
+
+    // With how many of their devices would follow the output
+    pub fn seat_list(
+        backend: &mut Box<dyn DisplayBackend>,
+        output: &str,
+    ) -> Result<Self, AppError> {
+        let list = backend
+            .get_seats()?
+            .into_iter()
+            .map(|seat| ListItem {
+                text: seat.name,
+                comments: vec![format!("{} devices", seat.devices)],
+                icon: Some(Icon::Seat),
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Self::Next(List {
+            prompt: Some(Prompt::Seat),
+            message: Some(format!("Bind to {output}...")),
             list,
             ..Default::default()
         }))
@@ -320,16 +1063,195 @@ impl ParseResult<Action> {
     // Enabled displays have all options except enable
     pub fn operation_list(
         backend: &mut Box<dyn DisplayBackend>,
+        config: &Config,
         output: &OutputEntry,
     ) -> Self {
-        let supported_ops = backend.supported_operations(output);
-        let op_list = supported_ops.into_iter().map(ListItem::from).collect();
+        let locked = config.is_locked(&output.name);
+        let mut supported_ops = backend.supported_operations(output);
+        if locked {
+            supported_ops.retain(|op| !op.changes_output());
+        }
+
+        // Show where the output currently is above the operations
+        let op_list = output
+            .geometry
+            .iter()
+            .map(ListItem::from)
+            .chain(supported_ops.into_iter().map(ListItem::from))
+            .collect();
+
+        // Explain why operations that other backends have are missing
+        let caps = backend.capabilities();
+        let unsupported = caps.unsupported();
+        let message = if locked {
+            format!("{}, locked in the config", output.name)
+        } else if !output.enabled || unsupported.is_empty() {
+            output.name.clone()
+        } else {
+            let ops = unsupported.iter().join(", ");
+            format!(
+                "{}, not available with {}: {ops}",
+                output.name, caps.backend
+            )
+        };
 
         Self::Next(List {
-            prompt: Some("Select operation".to_string()),
-            message: Some(output.name.clone()),
+            prompt: Some(Prompt::Operation),
+            message: Some(message),
             list: op_list,
             ..Default::default()
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn print(list: &List) -> String {
+        let mut out = Vec::new();
+        list.rofi_print(&mut out, &IconNames::new(), &PromptNames::new())
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn prints_header_rows_and_back() {
+        let list = List {
+            prompt: Some(Prompt::Rate),
+            message: Some("HDMI-1".to_string()),
+            allow_custom: true,
+            keep_selection: true,
+            list: vec![
+                ListItem {
+                    text: "60.00 Hz".to_string(),
+                    active: true,
+                    ..Default::default()
+                },
+                ListItem {
+                    text: "Apply".to_string(),
+                    comments: vec!["now".to_string(), "all".to_string()],
+                    icon: Some(Icon::Apply),
+                    meta: Some("go".to_string()),
+                    info: Some("apply".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            print(&list),
+            "\0prompt\x1fSelect rate\n\
+             \0message\x1fHDMI-1\n\
+             \0no-custom\x1ffalse\n\
+             \0keep-selection\x1ftrue\n\
+             \0markup-rows\x1ftrue\n\
+             \0use-hot-keys\x1ftrue\n\
+             \0active\x1f0\n\
+             60.00 Hz\0nonselectable\x1ffalse\x1finfo\x1f1:60.00 Hz\n\
+             Apply <span style='italic' size='small'>(now, all)</span>\
+             \0nonselectable\x1ffalse\x1ficon\x1fdialog-apply\
+             \x1fmeta\x1fgo\x1finfo\x1f2:apply\n\
+             Back <span style='italic' size='small'>(previous menu)</span>\
+             \0nonselectable\x1ffalse\x1ficon\x1fback\x1finfo\x1f3:Back\n"
+        );
+    }
+
+    #[test]
+    fn resets_message_and_active_without_back() {
+        let list = List {
+            no_markup: true,
+            no_back: true,
+            list: vec![ListItem {
+                text: "Keep".to_string(),
+                non_selectable: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            print(&list),
+            "\0message\x1f\n\
+             \0no-custom\x1ftrue\n\
+             \0keep-selection\x1ffalse\n\
+             \0markup-rows\x1ffalse\n\
+             \0use-hot-keys\x1ftrue\n\
+             \0active\x1f\n\
+             Keep\0nonselectable\x1ftrue\x1finfo\x1f1:Keep\n"
+        );
+    }
+
+    #[test]
+    fn uses_configured_names() {
+        let icons = IconNames::from([(Icon::Back, "go-back".to_string())]);
+        let prompts = PromptNames::from([(Prompt::Error, "Oops".to_string())]);
+        let mut out = Vec::new();
+        List::error("broken")
+            .rofi_print(&mut out, &icons, &prompts)
+            .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("\0prompt\x1fOops\n\0message\x1fbroken\n"));
+        assert!(out.contains("\x1ficon\x1fgo-back\x1finfo\x1f1:Back\n"));
+    }
+
+    #[test]
+    fn export_message_is_one_line() {
+        let ParseResult::Next(list) = ParseResult::export_list(
+            "xrandr --output DP-1 --auto",
+            Err(io::Error::other("no wl-copy")),
+        ) else {
+            panic!("export list is a menu");
+        };
+
+        assert_eq!(
+            list.message.as_deref(),
+            Some(
+                "xrandr --output DP-1 --auto — Could not copy to the \
+                 clipboard: no wl-copy"
+            )
+        );
+    }
+
+    #[test]
+    fn edid_message_is_one_line() {
+        let path = Path::new("/run/user/1000/rofi-randr/DP-1.bin");
+        let list = List::edid_exported("DP-1", path, Ok(()));
+
+        assert_eq!(
+            list.message.as_deref(),
+            Some(
+                "DP-1: written to /run/user/1000/rofi-randr/DP-1.bin — \
+                 Copied to the clipboard as hex"
+            )
+        );
+    }
+    #[test]
+    fn changes_name_outputs_by_their_alias() {
+        let config = Config {
+            aliases: HashMap::from([(
+                "DP-1".to_string(),
+                "Desk Left".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let changes = [
+            Change::Disable("eDP-1".to_string()),
+            Change::RightOf("HDMI-1".to_string(), "DP-1".to_string()),
+        ];
+
+        let ParseResult::Next(list) =
+            ParseResult::changes_list(&changes, &config)
+        else {
+            panic!("expected a list");
+        };
+        let texts: Vec<_> = list.list.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            ["Apply", "Disable eDP-1", "Put HDMI-1 right of Desk Left"]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+// State that is kept between runs, in
+// `$XDG_STATE_HOME/rofi-randr/state.toml` (or `~/.local/state/...`).
+// Losing it is harmless, so it is read and written on a best effort basis.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::action::rotate::Transform;
+use crate::config::xdg_dir;
+
+// A rotation that has not been confirmed yet, see `Config::confirm_rotation`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PendingRotation {
+    pub output: String,
+    pub previous: Transform,
+    // The revert timer that this waits for, see `spawn_revert_timer`.
+    // Rotating again before it runs out starts another one.
+    #[serde(default)]
+    pub timer: u64,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct State {
+    pub last_output: Option<String>,
+    // The output that was primary before the current one, see `Swap primary`
+    pub previous_primary: Option<String>,
+    // One for each output that was rotated, when rotating all at once
+    pub pending_rotations: Vec<PendingRotation>,
+    // Shown in the next menu, for warnings about the action that was applied
+    pub message: Option<String>,
+}
+
+fn path() -> Option<PathBuf> {
+    Some(xdg_dir("XDG_STATE_HOME", ".local/state")?.join("state.toml"))
+}
+
+impl State {
+    // A missing or unreadable state file is the same as an empty one
+    pub fn load() -> Self {
+        path()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = path().ok_or(std::io::ErrorKind::NotFound)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        fs::write(path, content)
+    }
+}